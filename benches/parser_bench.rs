@@ -110,7 +110,7 @@ fn benchmark_comment_heavy(c: &mut Criterion) {
     
     for i in 0..1000 {
         comment_heavy.push_str(&format!("# This is comment number {}\n", i));
-        comment_heavy.push_str(&format!("# With multiple lines\n"));
+        comment_heavy.push_str("# With multiple lines\n");
         comment_heavy.push_str(&format!("KEY_{}=value_{}\n", i, i));
     }
     
@@ -134,5 +134,117 @@ fn benchmark_comment_heavy(c: &mut Criterion) {
     group.finish();
 }
 
-criterion_group!(benches, benchmark_parser, benchmark_error_handling, benchmark_quote_types, benchmark_comment_heavy);
+fn benchmark_multiline(c: &mut Criterion) {
+    let mut multiline = String::new();
+    let mut continuations = String::new();
+    
+    for i in 0..200 {
+        multiline.push_str(&format!("CERT_{}=\"-----BEGIN CERTIFICATE-----\n", i));
+        for _ in 0..8 {
+            multiline.push_str("MIIBszCCAVmgAwIBAgIUQ0FBQ0FBQ0FBQ0FBQ0FBQ0FBQ0FBQ0Ew\n");
+        }
+        multiline.push_str("-----END CERTIFICATE-----\"\n");
+        
+        continuations.push_str(&format!("PATH_{}=/usr/local/bin\\\n", i));
+        for _ in 0..8 {
+            continuations.push_str(":/opt/tool/bin\\\n");
+        }
+        continuations.push_str(":/usr/bin\n");
+    }
+    
+    let mut group = c.benchmark_group("multiline");
+    
+    group.bench_function("double_quoted_multiline_200", |b| {
+        b.iter(|| korni::parse_entries_count(black_box(&multiline)))
+    });
+    
+    group.bench_function("continuation_heavy_200", |b| {
+        b.iter(|| korni::parse_entries_count(black_box(&continuations)))
+    });
+    
+    group.finish();
+}
+
+fn benchmark_escapes(c: &mut Criterion) {
+    // Pathological: every other character of the value is an escape
+    let mut escape_dense = String::new();
+    for i in 0..1000 {
+        escape_dense.push_str(&format!("KEY_{}=\"", i));
+        for _ in 0..16 {
+            escape_dense.push_str("a\\n\\\"\\$");
+        }
+        escape_dense.push_str("\"\n");
+    }
+    
+    let mut group = c.benchmark_group("escapes");
+    
+    group.bench_function("escape_dense_1k_lines", |b| {
+        b.iter(|| korni::parse_entries_count(black_box(&escape_dense)))
+    });
+    
+    group.finish();
+}
+
+fn benchmark_ownership(c: &mut Criterion) {
+    let mut env = String::new();
+    for i in 0..1000 {
+        env.push_str(&format!("KEY_{}=value_{}\n", i, i));
+    }
+    
+    let mut group = c.benchmark_group("ownership");
+    
+    group.bench_function("borrowed_entries_1k", |b| {
+        b.iter(|| korni::parse(black_box(&env)))
+    });
+    
+    group.bench_function("owned_entries_1k", |b| {
+        b.iter(|| {
+            korni::parse(black_box(&env)).into_iter().map(korni::Entry::into_owned).collect::<Vec<_>>()
+        })
+    });
+    
+    group.bench_function("owned_environment_1k", |b| {
+        b.iter(|| Korni::from_reader(std::io::Cursor::new(black_box(env.clone()))).parse())
+    });
+    
+    group.bench_function("bufread_1k", |b| {
+        b.iter(|| korni::EnvIterator::from_bufread(black_box(env.as_bytes())).count())
+    });
+    
+    group.finish();
+}
+
+fn benchmark_from_entries(c: &mut Criterion) {
+    let mut env = String::new();
+    for i in 0..1000 {
+        env.push_str(&format!("KEY_{}=value_{}\n", i, i));
+    }
+    let borrowed = korni::parse(&env);
+    let owned: Vec<_> = borrowed.iter().cloned().map(korni::Entry::into_owned).collect();
+    
+    let mut group = c.benchmark_group("from_entries");
+    
+    group.bench_function("borrowed_keys_1k", |b| {
+        b.iter_batched(|| borrowed.clone(), korni::Environment::from_entries, criterion::BatchSize::SmallInput)
+    });
+    
+    // Owned keys are where cloning the key for the map costs an allocation
+    group.bench_function("owned_keys_1k", |b| {
+        b.iter_batched(|| owned.clone(), korni::Environment::from_entries, criterion::BatchSize::SmallInput)
+    });
+    
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    benchmark_parser,
+    benchmark_error_handling,
+    benchmark_quote_types,
+    benchmark_comment_heavy,
+    benchmark_multiline,
+    benchmark_escapes,
+    benchmark_ownership,
+    benchmark_from_entries
+);
 criterion_main!(benches);
@@ -0,0 +1,268 @@
+//! Security-oriented checks over parsed environments.
+//!
+//! Reports describe values (length, entropy, classification) without ever
+//! including them, so they can be shared in security reviews.
+
+use std::path::Path;
+use crate::env::Environment;
+use crate::error::Error;
+use crate::glob::glob_match;
+use crate::infer::{compatible_types, InferredType};
+use crate::line_index::LineIndex;
+use crate::types::Span;
+
+/// Key segments that mark a variable as holding a secret.
+const SECRET_SEGMENTS: &[&str] = &[
+    "PASSWORD", "PASSWD", "PASS", "SECRET", "TOKEN", "APIKEY",
+    "CREDENTIAL", "CREDENTIALS", "AUTH", "PRIVATE",
+];
+
+/// Values that are weak regardless of their entropy.
+const COMMON_PASSWORDS: &[&str] = &[
+    "admin", "administrator", "password", "passw0rd", "changeme", "secret",
+    "root", "test", "guest", "default", "letmein", "qwerty", "123456",
+    "12345678", "111111", "abc123", "postgres", "mysql", "dev", "local",
+];
+
+/// Heuristic: a key names a secret if one of its `_`-separated segments is a
+/// well-known secret word, or it ends in `_KEY` (e.g. `API_KEY`). `PWD`
+/// counts only next to other segments, as in `DB_PWD`: on its own it is the
+/// shell's working directory.
+pub fn is_secret_key(key: &str) -> bool {
+    let upper = key.to_ascii_uppercase();
+    upper.ends_with("_KEY")
+        || upper.split('_').any(|segment| SECRET_SEGMENTS.contains(&segment))
+        || (upper.contains('_') && upper.split('_').any(|segment| segment == "PWD"))
+}
+
+/// Shannon entropy of `value` in bits per character.
+pub fn shannon_entropy(value: &str) -> f64 {
+    let len = value.chars().count();
+    if len == 0 {
+        return 0.0;
+    }
+    let mut counts: Vec<(char, usize)> = Vec::new();
+    for c in value.chars() {
+        match counts.iter_mut().find(|(seen, _)| *seen == c) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((c, 1)),
+        }
+    }
+    counts.iter()
+        .map(|&(_, n)| {
+            let p = n as f64 / len as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SecretStrength {
+    Empty,
+    Weak,
+    Moderate,
+    Strong,
+}
+
+/// Total estimated bits below which a secret is weak / only moderate.
+const WEAK_BITS: f64 = 28.0;
+const STRONG_BITS: f64 = 60.0;
+
+pub fn classify_secret(value: &str) -> SecretStrength {
+    if value.is_empty() {
+        return SecretStrength::Empty;
+    }
+    if COMMON_PASSWORDS.iter().any(|common| value.eq_ignore_ascii_case(common)) {
+        return SecretStrength::Weak;
+    }
+    let bits = shannon_entropy(value) * value.chars().count() as f64;
+    if bits < WEAK_BITS {
+        SecretStrength::Weak
+    } else if bits < STRONG_BITS {
+        SecretStrength::Moderate
+    } else {
+        SecretStrength::Strong
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecretFinding {
+    pub key: String,
+    pub strength: SecretStrength,
+    /// Length of the value in characters.
+    pub length: usize,
+    /// Entropy in bits per character.
+    pub entropy: f64,
+    pub key_span: Option<Span>,
+}
+
+/// Classifies every secret-looking, active pair in source order.
+pub fn secrets(env: &Environment<'_>) -> Vec<SecretFinding> {
+    env.iter_ordered()
+        .filter(|kv| !kv.is_comment && is_secret_key(&kv.key))
+        .map(|kv| SecretFinding {
+            key: kv.key.to_string(),
+            strength: classify_secret(&kv.value),
+            length: kv.value.chars().count(),
+            entropy: shannon_entropy(&kv.value),
+            key_span: kv.key_span,
+        })
+        .collect()
+}
+
+/// Secrets classified `Empty` or `Weak`.
+pub fn weak_secrets(env: &Environment<'_>) -> Vec<SecretFinding> {
+    secrets(env).into_iter()
+        .filter(|f| f.strength <= SecretStrength::Weak)
+        .collect()
+}
+
+/// What `shell_injection` looks for: command substitution, command
+/// separators and pipes, longest first.
+pub const SHELL_METACHARACTERS: &[&str] = &["$(", "&&", "||", "`", ";", "|", "\n"];
+
+/// A shell metacharacter in a value, as reported by `shell_injection`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellInjectionFinding {
+    pub key: String,
+    /// The metacharacter found, one of `SHELL_METACHARACTERS`.
+    pub metacharacter: &'static str,
+    /// The metacharacter within the source, see `KeyValuePair::decoded_span`.
+    pub span: Option<Span>,
+}
+
+/// Metacharacters (see `SHELL_METACHARACTERS`) in the values of active
+/// pairs matching one of `keys`, names or globs of the variables the
+/// consumer will interpolate into a shell command. Each occurrence is
+/// reported, in source order.
+pub fn shell_injection(env: &Environment<'_>, keys: &[&str]) -> Vec<ShellInjectionFinding> {
+    let index = env.source().map(LineIndex::new);
+    let source = env.source().zip(index.as_ref());
+    let mut findings = Vec::new();
+    for kv in env.iter_ordered().filter(|kv| !kv.is_comment && keys.iter().any(|pattern| glob_match(pattern, &kv.key))) {
+        let mut i = 0;
+        while i < kv.value.len() {
+            match SHELL_METACHARACTERS.iter().find(|m| kv.value[i..].starts_with(*m)) {
+                Some(metacharacter) => {
+                    findings.push(ShellInjectionFinding {
+                        key: kv.key.to_string(),
+                        metacharacter,
+                        span: kv.decoded_span(i..i + metacharacter.len(), source),
+                    });
+                    i += metacharacter.len();
+                },
+                None => i += kv.value[i..].chars().next().map_or(1, char::len_utf8),
+            }
+        }
+    }
+    findings
+}
+
+/// What `cross_check` found about a key. Environments are given by their
+/// index in the slice passed to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrossCheckKind {
+    /// Defined in some environments but not in `missing_from`.
+    Missing { missing_from: Vec<usize> },
+    /// A secret (see `is_secret_key`) with the same value in all of `envs`,
+    /// e.g. a production password reused in staging.
+    SharedSecret { envs: Vec<usize> },
+    /// Values that no one type fits, e.g. a number in one file and a
+    /// duration in another; `types` holds the inferred type in each
+    /// environment, `None` where the key is unset or empty.
+    TypeMismatch { types: Vec<Option<InferredType>> },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrossCheckIssue {
+    pub key: String,
+    pub kind: CrossCheckKind,
+}
+
+/// Compares the environments of one application, e.g. for dev, staging
+/// and prod, reporting keys missing from some of them, secrets shared
+/// between them and values whose types disagree. Only active pairs count.
+///
+/// Issues are grouped by key, in the order keys are first seen, and within
+/// a key as listed in `CrossCheckKind`; each group of environments sharing
+/// a secret is reported separately.
+pub fn cross_check(envs: &[Environment<'_>]) -> Vec<CrossCheckIssue> {
+    let mut keys: Vec<&str> = Vec::new();
+    for env in envs {
+        for kv in env.iter_ordered().filter(|kv| !kv.is_comment) {
+            if !keys.contains(&kv.key.as_ref()) {
+                keys.push(&kv.key);
+            }
+        }
+    }
+
+    let mut issues = Vec::new();
+    for key in keys {
+        let values: Vec<Option<&str>> = envs.iter()
+            .map(|env| env.get_entry(key).filter(|kv| !kv.is_comment).map(|kv| kv.value.as_ref()))
+            .collect();
+        let issue = |kind| CrossCheckIssue { key: key.to_string(), kind };
+
+        let missing_from: Vec<usize> = (0..envs.len()).filter(|&i| values[i].is_none()).collect();
+        if !missing_from.is_empty() {
+            issues.push(issue(CrossCheckKind::Missing { missing_from }));
+        }
+
+        if is_secret_key(key) {
+            let mut grouped = vec![false; envs.len()];
+            for i in 0..envs.len() {
+                let Some(value) = values[i].filter(|v| !v.is_empty() && !grouped[i]) else { continue };
+                let same: Vec<usize> = (i..envs.len()).filter(|&j| values[j] == Some(value)).collect();
+                if same.len() > 1 {
+                    same.iter().for_each(|&j| grouped[j] = true);
+                    issues.push(issue(CrossCheckKind::SharedSecret { envs: same }));
+                }
+            }
+        }
+
+        let present: Vec<&str> = values.iter().flatten().copied().filter(|v| !v.is_empty()).collect();
+        let mut fits = present.first().map(|value| compatible_types(value)).unwrap_or_default();
+        for value in present.iter().skip(1) {
+            let candidates = compatible_types(value);
+            fits.retain(|inferred| candidates.contains(inferred));
+        }
+        if present.len() > 1 && fits.is_empty() {
+            let types = values.iter()
+                .map(|value| value.filter(|v| !v.is_empty()).map(|v| InferredType::infer(v).0))
+                .collect();
+            issues.push(issue(CrossCheckKind::TypeMismatch { types }));
+        }
+    }
+    issues
+}
+
+/// Fails with `Error::InsecurePermissions` if any user may read the file at
+/// `path`. Always passes on platforms without Unix permissions.
+pub fn check_permissions(path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    let meta = std::fs::metadata(path)
+        .map_err(|e| Error::Io(format!("Failed to inspect {}: {}", path.display(), e)))?;
+    check_metadata(path, &meta)
+}
+
+#[cfg(unix)]
+pub(crate) fn check_metadata(path: &Path, meta: &std::fs::Metadata) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+    let mode = meta.permissions().mode() & 0o777;
+    if mode & 0o004 != 0 {
+        return Err(Error::InsecurePermissions { path: path.display().to_string(), mode });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn check_metadata(_path: &Path, _meta: &std::fs::Metadata) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Whether git would ignore the file at `path`, judging by the `.gitignore`
+/// files of its work tree; `None` if `path` is not inside one. A secret
+/// file that is not ignored is one `git add .` away from being committed.
+pub fn is_gitignored(path: impl AsRef<Path>) -> Option<bool> {
+    crate::gitignore::is_ignored(path.as_ref())
+}
@@ -0,0 +1,103 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::date::Date;
+use crate::error::Error;
+use crate::spec::validate_key;
+use crate::types::{Entry, KeyValuePair, QuoteType};
+use crate::writer::encode_value;
+
+/// A `.env` file that is only ever appended to, for environments whose
+/// changes must be auditable in place.
+///
+/// Every change adds a new definition below the existing ones, annotated
+/// with who made it and when:
+///
+/// ```env
+/// # @changed-by: alice
+/// # @changed-at: 2026-10-14T09:30:00Z
+/// API_URL=https://api.example.com
+/// ```
+///
+/// The last definition of a key wins when the file is loaded, so the file
+/// stays a valid `.env` file; earlier definitions remain as history.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    path: PathBuf,
+}
+
+impl AuditLog {
+    /// The file is created on the first append if it does not exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Appends `key=value` changed by `changed_by` now.
+    pub fn append(&self, key: &str, value: &str, changed_by: &str) -> Result<(), Error> {
+        self.append_at(key, value, changed_by, SystemTime::now())
+    }
+
+    /// Appends `key=value` changed by `changed_by` at `at`. The value is
+    /// quoted as needed and always written on a single line.
+    pub fn append_at(&self, key: &str, value: &str, changed_by: &str, at: SystemTime) -> Result<(), Error> {
+        validate_key(key)?;
+        if changed_by.contains(['\n', '\r']) {
+            return Err(Error::Generic { offset: 0, message: "Author must not contain line breaks".into() });
+        }
+
+        // Line breaks can only be double-quoted, and then become escapes
+        let (_, encoded) = encode_value(value, QuoteType::None);
+        let mut record = String::new();
+        if !self.ends_with_newline()? {
+            record.push('\n');
+        }
+        record.push_str(&format!("# @changed-by: {}\n# @changed-at: {}\n{}={}\n", changed_by, timestamp(at), key, encoded));
+
+        let io_err = |action: &str, e: std::io::Error| Error::Io(format!("Failed to {} {}: {}", action, self.path.display(), e));
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)
+            .map_err(|e| io_err("open", e))?;
+        file.write_all(record.as_bytes()).map_err(|e| io_err("append to", e))?;
+        file.sync_data().map_err(|e| io_err("sync", e))
+    }
+
+    /// Every definition of `key`, oldest first, with its `changed-by` and
+    /// `changed-at` annotations.
+    pub fn history(&self, key: &str) -> Result<Vec<KeyValuePair<'static>>, Error> {
+        let content = fs::read_to_string(&self.path)
+            .map_err(|e| Error::Io(format!("Failed to read {}: {}", self.path.display(), e)))?;
+        Ok(crate::parse(&content).into_iter()
+            .filter_map(|entry| match entry {
+                Entry::Pair(kv) if kv.key == key && !kv.is_comment => Some(kv.into_owned()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    fn ends_with_newline(&self) -> Result<bool, Error> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(true),
+            Err(e) => return Err(Error::Io(format!("Failed to open {}: {}", self.path.display(), e))),
+        };
+        let mut last = [b'\n'];
+        if file.seek(SeekFrom::End(0)).is_ok_and(|len| len > 0) {
+            file.seek(SeekFrom::End(-1))
+                .and_then(|_| file.read_exact(&mut last))
+                .map_err(|e| Error::Io(format!("Failed to read {}: {}", self.path.display(), e)))?;
+        }
+        Ok(last[0] == b'\n')
+    }
+}
+
+/// `at` as an RFC 3339 UTC timestamp with second precision.
+fn timestamp(at: SystemTime) -> String {
+    let secs = at.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let date = Date::from_unix_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!("{}T{:02}:{:02}:{:02}Z", date, time / 3600, time % 3600 / 60, time % 60)
+}
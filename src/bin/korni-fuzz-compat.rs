@@ -0,0 +1,63 @@
+//! Reports divergences between korni and Docker env-file semantics.
+//!
+//! Usage: korni-fuzz-compat [--seed N] [--iterations N] [FILE...]
+//!
+//! With files, each one is compared once; otherwise random inputs are
+//! generated from the seed. Exits with status 1 if any divergence is found.
+
+use korni::compat::{compare, DockerEnvFile, InputGenerator, Reference};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut seed = 1;
+    let mut iterations = 10_000;
+    let mut files = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut number = |name: &str| -> Option<u64> {
+            let parsed = args.next().and_then(|v| v.parse().ok());
+            if parsed.is_none() {
+                eprintln!("{} expects a number", name);
+            }
+            parsed
+        };
+        match arg.as_str() {
+            "--seed" => match number("--seed") { Some(n) => seed = n, None => return ExitCode::from(2) },
+            "--iterations" => match number("--iterations") { Some(n) => iterations = n, None => return ExitCode::from(2) },
+            _ => files.push(arg),
+        }
+    }
+
+    let inputs: Vec<String> = if files.is_empty() {
+        let mut generator = InputGenerator::new(seed);
+        (0..iterations).map(|_| generator.next_input(12)).collect()
+    } else {
+        let mut inputs = Vec::new();
+        for file in &files {
+            match std::fs::read_to_string(file) {
+                Ok(content) => inputs.push(content),
+                Err(e) => {
+                    eprintln!("Failed to read {}: {}", file, e);
+                    return ExitCode::from(2);
+                }
+            }
+        }
+        inputs
+    };
+
+    let mut diverging = 0;
+    for input in &inputs {
+        let comparison = compare(input, &DockerEnvFile);
+        if !comparison.is_compatible() {
+            diverging += 1;
+            println!("input: {:?}", input);
+            for divergence in &comparison.divergences {
+                println!("  {:?}", divergence);
+            }
+        }
+    }
+
+    println!("{} of {} inputs diverge from {}", diverging, inputs.len(), DockerEnvFile.name());
+    if diverging > 0 { ExitCode::from(1) } else { ExitCode::SUCCESS }
+}
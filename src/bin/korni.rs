@@ -0,0 +1,61 @@
+//! Command-line tools for `.env` files.
+//!
+//! Usage: korni codegen [--name NAME] FILE
+//!
+//! `codegen` reads an annotated file such as `.env.example` (see
+//! `Schema::from_annotated`) and prints a Rust struct named NAME (`Config`
+//! by default) with a `load(&Environment)` constructor.
+
+use korni::schema::Schema;
+use korni::Korni;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: korni codegen [--name NAME] FILE";
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("codegen") => codegen(args),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        },
+    }
+}
+
+fn codegen(mut args: impl Iterator<Item = String>) -> ExitCode {
+    let mut name = "Config".to_string();
+    let mut file = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => match args.next() {
+                Some(value) => name = value,
+                None => {
+                    eprintln!("--name expects a struct name");
+                    return ExitCode::from(2);
+                },
+            },
+            _ if file.is_none() => file = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                return ExitCode::from(2);
+            },
+        }
+    }
+    let Some(file) = file else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let schema = Korni::from_file(&file).parse().and_then(|env| Schema::from_annotated(&env));
+    match schema {
+        Ok(schema) => {
+            print!("{}", schema.to_rust_struct(&name));
+            ExitCode::SUCCESS
+        },
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            ExitCode::from(1)
+        },
+    }
+}
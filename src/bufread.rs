@@ -0,0 +1,188 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::BufRead;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::types::{Entry, ParseOptions, Span};
+
+/// Parses a `BufRead` one logical line at a time: a physical line, plus the
+/// following ones while a quoted value is still open, a value ends in a
+/// line-continuation backslash, or `# @annotation` comments wait for their
+/// pair. Memory use is bounded by the longest logical line (and, with
+/// `warn_duplicate_keys`, the set of keys seen).
+///
+/// Created by `EnvIterator::from_bufread`. Offsets and positions refer to
+/// the whole input. A read failure is yielded as a final `Error::Io`, and
+/// invalid UTF-8 as a final `Error::InvalidUtf8` at its offset.
+pub struct BufReadIter<R> {
+    reader: R,
+    options: ParseOptions,
+    /// Bytes of a physical line that has not been terminated yet.
+    partial: Vec<u8>,
+    buf: String,
+    /// Byte offset and line number of `buf` in the whole input.
+    offset: usize,
+    line: usize,
+    entry_index: usize,
+    queue: VecDeque<Entry<'static>>,
+    seen_keys: HashMap<String, Span>,
+    done: bool,
+    /// Treat end of input as "nothing more yet" instead of the end.
+    follow: bool,
+}
+
+impl<R: BufRead> BufReadIter<R> {
+    pub(crate) fn new(reader: R, options: ParseOptions) -> Self {
+        Self {
+            reader,
+            options,
+            partial: Vec::new(),
+            buf: String::new(),
+            offset: 0,
+            line: 0,
+            entry_index: 0,
+            queue: VecDeque::new(),
+            seen_keys: HashMap::new(),
+            done: false,
+            follow: false,
+        }
+    }
+
+    /// Yields only entries that later input cannot change; at end of input
+    /// `next()` returns `None` but a later call picks up appended data.
+    pub(crate) fn following(reader: R, options: ParseOptions) -> Self {
+        Self { follow: true, ..Self::new(reader, options) }
+    }
+
+    /// Appends the next physical line to `buf`. Returns `false` when none is
+    /// available (yet).
+    fn read_line(&mut self) -> Result<bool, Error> {
+        let start = self.offset + self.buf.len();
+        let invalid_utf8 = |offset: usize| Error::InvalidUtf8 { offset, reason: "Invalid UTF-8 sequence".into() };
+        loop {
+            let read = self.reader.read_until(b'\n', &mut self.partial).map_err(|e| match e.kind() {
+                std::io::ErrorKind::InvalidData => invalid_utf8(start + self.partial.len()),
+                _ => Error::Io(format!("Failed to read from reader: {}", e)),
+            })?;
+            if self.partial.last() == Some(&b'\n') {
+                break;
+            }
+            if read == 0 {
+                // In follow mode an unterminated line may still grow
+                if self.follow {
+                    return Ok(false);
+                }
+                self.done = true;
+                if self.partial.is_empty() {
+                    return Ok(false);
+                }
+                break;
+            }
+        }
+        let line = std::str::from_utf8(&self.partial).map_err(|e| invalid_utf8(start + e.valid_up_to()))?;
+        self.buf.push_str(line);
+        self.partial.clear();
+        Ok(true)
+    }
+
+    /// Reads and parses the next logical line into `queue`. Returns `false`
+    /// when no complete logical line is available.
+    fn fill(&mut self) -> bool {
+        let mut chunk_options = self.options;
+        chunk_options.warn_duplicate_keys = false;
+        chunk_options.track_positions |= self.options.warn_duplicate_keys;
+
+        let entries = loop {
+            let last_line_start = self.buf.len();
+            match self.read_line() {
+                Ok(true) => {},
+                Ok(false) if self.done && !self.buf.is_empty() => {},
+                Ok(false) => return false,
+                Err(e) => {
+                    self.done = true;
+                    self.queue.push_back(Entry::Error(e));
+                    return false;
+                },
+            }
+            let entries: Vec<Entry<'static>> = Parser::with_options(&self.buf, chunk_options).parse()
+                .into_iter()
+                .map(Entry::into_owned)
+                .collect();
+            if self.done || self.is_complete(&entries, &self.buf[last_line_start..]) {
+                break entries;
+            }
+        };
+
+        if self.offset > 0 && self.buf.starts_with('\u{FEFF}') {
+            self.push(Entry::Error(Error::InvalidBom { offset: self.offset }));
+        }
+        for mut entry in entries {
+            let (offset, line, track) = (self.offset, self.line, self.options.track_positions);
+            entry.for_each_position(|pos| {
+                pos.offset += offset;
+                if track {
+                    pos.line += line;
+                } else {
+                    pos.line = 0;
+                    pos.col = 0;
+                }
+            });
+            if let Entry::Error(e) | Entry::Warning(e) = &mut entry {
+                e.shift(offset);
+            }
+
+            let mut duplicate = None;
+            if let Entry::Pair(kv) = &mut entry {
+                if self.options.warn_duplicate_keys && !kv.is_comment {
+                    let span = kv.key_span.unwrap_or_default();
+                    duplicate = self.seen_keys.insert(kv.key.to_string(), span)
+                        .map(|first_span| Error::DuplicateKey { key: kv.key.to_string(), first_span, second_span: span });
+                }
+                if !track {
+                    kv.clear_positions();
+                }
+            }
+            self.push(entry);
+            if let Some(warning) = duplicate {
+                self.push(Entry::Warning(warning));
+            }
+        }
+
+        self.offset += self.buf.len();
+        self.line += self.buf.matches('\n').count();
+        self.buf.clear();
+        true
+    }
+
+    /// Whether `entries`, parsed from the buffer ending in `last_line`, are
+    /// unaffected by the lines that follow.
+    fn is_complete(&self, entries: &[Entry<'_>], last_line: &str) -> bool {
+        let unclosed = entries.iter().any(|e| matches!(e, Entry::Error(Error::UnclosedQuote { .. })));
+        let content = last_line.trim_end_matches(['\n', '\r']);
+        let continued = content.ends_with('\\');
+        let annotation = content.trim_start_matches([' ', '\t'])
+            .strip_prefix(|c: char| c.is_ascii() && self.options.comment_chars.contains(&(c as u8)))
+            .is_some_and(|rest| rest.trim_start_matches([' ', '\t']).starts_with('@'));
+        !(unclosed || continued || annotation)
+    }
+
+    fn push(&mut self, mut entry: Entry<'static>) {
+        if let Entry::Pair(kv) = &mut entry {
+            kv.index = self.entry_index;
+        }
+        self.entry_index += 1;
+        self.queue.push_back(entry);
+    }
+}
+
+impl<R: BufRead> Iterator for BufReadIter<R> {
+    type Item = Entry<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.queue.is_empty() && !self.done {
+            if !self.fill() {
+                break;
+            }
+        }
+        self.queue.pop_front()
+    }
+}
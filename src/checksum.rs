@@ -0,0 +1,154 @@
+//! Tamper and truncation detection for `.env` files.
+//!
+//! A signed file ends with a `# korni-checksum: sha256:<hex>` comment over
+//! everything above it; a sidecar manifest (`<file>.sha256`, in `sha256sum`
+//! format) covers the whole file and leaves it untouched. Both are integrity
+//! checks, not signatures: anyone who can edit the file can recompute them,
+//! so keep the manifest out of reach of whoever writes the file when that
+//! matters.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::error::Error;
+
+const PREFIX: &str = "# korni-checksum: sha256:";
+
+/// `content` with a trailing checksum line, replacing an existing one.
+pub fn sign(content: &str) -> String {
+    let (body, _) = split(content);
+    let mut signed = body.to_string();
+    if !signed.is_empty() && !signed.ends_with('\n') {
+        signed.push('\n');
+    }
+    let hash = hex(&sha256(signed.as_bytes()));
+    signed.push_str(PREFIX);
+    signed.push_str(&hash);
+    signed.push('\n');
+    signed
+}
+
+/// Checks the trailing checksum line of `content`. Fails with
+/// `Error::ChecksumMismatch` if it is missing (e.g. the file was truncated)
+/// or does not match the text above it.
+pub fn verify(content: &str) -> Result<(), Error> {
+    let (body, expected) = split(content);
+    let actual = hex(&sha256(body.as_bytes()));
+    match expected {
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => Ok(()),
+        expected => Err(Error::ChecksumMismatch { expected: expected.map(str::to_string), actual }),
+    }
+}
+
+/// Writes the manifest for `path` next to it as `<path>.sha256` and
+/// returns the manifest's path.
+pub fn write_manifest(path: impl AsRef<Path>) -> Result<PathBuf, Error> {
+    let path = path.as_ref();
+    let content = read(path)?;
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    let manifest = manifest_path(path);
+    fs::write(&manifest, format!("{}  {}\n", hex(&sha256(&content)), name))
+        .map_err(|e| Error::Io(format!("Failed to write {}: {}", manifest.display(), e)))?;
+    Ok(manifest)
+}
+
+/// Checks `path` against its `<path>.sha256` manifest.
+pub fn verify_manifest(path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    let manifest = manifest_path(path);
+    let listed = fs::read_to_string(&manifest)
+        .map_err(|e| Error::Io(format!("Failed to read {}: {}", manifest.display(), e)))?;
+    let expected = listed.split_whitespace().next().map(str::to_string);
+    let actual = hex(&sha256(&read(path)?));
+    match expected {
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => Ok(()),
+        expected => Err(Error::ChecksumMismatch { expected, actual }),
+    }
+}
+
+fn manifest_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|e| Error::Io(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+/// Splits off a trailing checksum line, returning the text above it and the
+/// hash it lists.
+fn split(content: &str) -> (&str, Option<&str>) {
+    let trimmed = content.trim_end_matches(['\n', '\r']);
+    let start = trimmed.rfind('\n').map_or(0, |i| i + 1);
+    match trimmed[start..].strip_prefix(PREFIX) {
+        Some(hash) => (&content[..start], Some(hash.trim())),
+        None => (content, None),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4).
+fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut h: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+    ];
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut hh] = h;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = hh.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            hh = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (state, value) in h.iter_mut().zip([a, b, c, d, e, f, g, hh]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut out = [0u8; 32];
+    for (chunk, word) in out.chunks_exact_mut(4).zip(h) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    out
+}
@@ -0,0 +1,132 @@
+use std::fmt::Write;
+use crate::schema::{KeySpec, Schema, ValueType};
+use crate::spec::{is_key_char, is_key_start};
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "type", "unsafe", "use", "where", "while", "abstract", "become",
+    "box", "do", "final", "gen", "macro", "override", "priv", "try", "typeof", "unsized", "virtual", "yield",
+];
+
+/// See `Schema::to_rust_struct`. Keys that are required or have a default
+/// become plain fields, the others `Option`s.
+pub(crate) fn rust_struct(schema: &Schema, name: &str) -> String {
+    let keys: Vec<(&str, &KeySpec)> = schema.keys().collect();
+    let fields = field_names(keys.iter().map(|&(key, _)| key));
+    let mut out = String::new();
+    out.push_str("// Generated by `korni codegen`; edit the schema instead.\n\n");
+    out.push_str("#[derive(Debug, Clone, PartialEq)]\n");
+    let _ = writeln!(out, "pub struct {} {{", name);
+    for (&(key, spec), field) in keys.iter().zip(&fields) {
+        if let Some(description) = &spec.description {
+            for line in description.lines() {
+                let _ = writeln!(out, "    /// {}", line);
+            }
+        }
+        if field.trim_start_matches("r#") != key.to_lowercase() {
+            let _ = writeln!(out, "    /// Read from `{}`.", key);
+        }
+        let ty = rust_type(spec.value_type);
+        match spec.required || spec.default.is_some() {
+            true => { let _ = writeln!(out, "    pub {}: {},", field, ty); },
+            false => { let _ = writeln!(out, "    pub {}: Option<{}>,", field, ty); },
+        }
+    }
+    out.push_str("}\n\n");
+
+    let _ = writeln!(out, "impl {} {{", name);
+    out.push_str("    #[allow(clippy::result_large_err)]\n");
+    out.push_str("    pub fn load(env: &korni::Environment<'_>) -> Result<Self, korni::schema::Violation> {\n");
+    let typed = keys.iter().any(|(_, spec)| spec.value_type != ValueType::String);
+    let required = keys.iter().any(|(_, spec)| spec.required && spec.default.is_none());
+    match (typed, required) {
+        (true, _) => out.push_str("        use korni::schema::{ValueType, Violation, ViolationKind};\n"),
+        (false, true) => out.push_str("        use korni::schema::{Violation, ViolationKind};\n"),
+        (false, false) => {},
+    }
+    out.push_str("        let get = |key: &str| env.get_entry(key).filter(|kv| !kv.is_comment).map(|kv| kv.value.as_ref());\n");
+    if required {
+        out.push_str("        let missing = |key: &str| Violation::new(env, key, ViolationKind::Missing);\n");
+    }
+    if typed {
+        out.push_str("        let invalid = |key: &str, value_type| Violation::new(env, key, ViolationKind::InvalidType(value_type));\n");
+    }
+    out.push_str("        Ok(Self {\n");
+    for (&(key, spec), field) in keys.iter().zip(&fields) {
+        let convert = conversion(key, spec.value_type);
+        match (&spec.default, spec.required) {
+            (Some(default), _) => {
+                let _ = writeln!(out, "            {}: {{\n                let v = get({:?}).unwrap_or({:?});\n                {}\n            }},", field, key, default, convert);
+            },
+            (None, true) => {
+                let _ = writeln!(out, "            {}: match get({:?}) {{\n                Some(v) => {},\n                None => return Err(missing({:?})),\n            }},", field, key, convert, key);
+            },
+            (None, false) if spec.value_type == ValueType::String => {
+                let _ = writeln!(out, "            {}: get({:?}).map(str::to_string),", field, key);
+            },
+            (None, false) => {
+                let _ = writeln!(out, "            {}: match get({:?}) {{\n                Some(v) => Some({}),\n                None => None,\n            }},", field, key, convert);
+            },
+        }
+    }
+    out.push_str("        })\n    }\n}\n");
+    out
+}
+
+fn rust_type(value_type: ValueType) -> &'static str {
+    match value_type {
+        ValueType::String | ValueType::Url => "String",
+        ValueType::Integer => "i64",
+        ValueType::Float => "f64",
+        ValueType::Bool => "bool",
+    }
+}
+
+/// An expression converting `v: &str` to the field's type.
+fn conversion(key: &str, value_type: ValueType) -> String {
+    match value_type {
+        ValueType::String => "v.to_string()".to_string(),
+        ValueType::Url => format!("Some(v).filter(|v| ValueType::Url.matches(v)).ok_or_else(|| invalid({:?}, ValueType::Url))?.to_string()", key),
+        ValueType::Integer => format!("v.parse::<i64>().map_err(|_| invalid({:?}, ValueType::Integer))?", key),
+        ValueType::Float => format!("v.parse::<f64>().map_err(|_| invalid({:?}, ValueType::Float))?", key),
+        ValueType::Bool => format!("korni::schema::parse_bool(v).ok_or_else(|| invalid({:?}, ValueType::Bool))?", key),
+    }
+}
+
+/// Field names for `keys` in order. Keys differing only in case, such as
+/// `API_KEY` and `api_key`, would share a field; later ones get `_2`, `_3`
+/// and so on.
+fn field_names<'k>(keys: impl Iterator<Item = &'k str>) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    for key in keys {
+        let name = field_name(key);
+        let field = match fields.contains(&name) {
+            false => name,
+            true => (2..)
+                .map(|n| format!("{}_{}", name.trim_start_matches("r#"), n))
+                .find(|candidate| !fields.contains(candidate))
+                .expect("unbounded"),
+        };
+        fields.push(field);
+    }
+    fields
+}
+
+/// `key` lowercased as a Rust identifier: characters an identifier cannot
+/// hold become `_`, a name that cannot start one (e.g. `2FA`) gets a
+/// leading `_`, and `_` on its own, which is not a name, is `_field`.
+fn field_name(key: &str) -> String {
+    let mut name: String = key.to_lowercase().chars()
+        .map(|c| if is_key_char(c, true) { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c| is_key_start(c, true)) {
+        name.insert(0, '_');
+    }
+    match name.as_str() {
+        "_" => "_field".to_string(),
+        "self" | "super" | "crate" => format!("{}_", name),
+        _ if KEYWORDS.contains(&name.as_str()) => format!("r#{}", name),
+        _ => name,
+    }
+}
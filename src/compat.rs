@@ -0,0 +1,157 @@
+//! Differential testing of korni against other dotenv dialects.
+//!
+//! A [`Reference`] describes how another implementation parses a file. The
+//! crate ships a model of Docker's `--env-file` semantics; other parsers
+//! (e.g. dotenvy) can be plugged in by implementing the trait.
+
+use std::collections::BTreeMap;
+use crate::types::Entry;
+
+/// Another parser whose results korni is compared against.
+pub trait Reference {
+    fn name(&self) -> &str;
+
+    /// Key/value pairs in definition order, or a description of why the
+    /// input was rejected.
+    fn parse(&self, input: &str) -> Result<Vec<(String, String)>, String>;
+}
+
+/// Docker `--env-file` semantics: the value is everything after the first
+/// `=`, verbatim; quotes and escapes are not processed and lines without
+/// `=` are skipped (docker would read them from the host environment).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DockerEnvFile;
+
+impl Reference for DockerEnvFile {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn parse(&self, input: &str) -> Result<Vec<(String, String)>, String> {
+        let input = input.strip_prefix('\u{FEFF}').unwrap_or(input);
+        let mut pairs = Vec::new();
+        for (n, line) in input.split('\n').enumerate() {
+            let line = line.trim_start_matches([' ', '\t']);
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if key.is_empty() {
+                return Err(format!("no variable name on line {}", n + 1));
+            }
+            if key.contains([' ', '\t']) {
+                return Err(format!("variable '{}' contains whitespaces", key));
+            }
+            pairs.push((key.to_string(), value.to_string()));
+        }
+        Ok(pairs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// korni reported errors the reference did not.
+    RejectedByKorni { errors: Vec<String> },
+    /// The reference failed where korni did not.
+    RejectedByReference { reason: String },
+    MissingInKorni { key: String },
+    MissingInReference { key: String },
+    ValueMismatch { key: String, korni: String, reference: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub reference: String,
+    pub divergences: Vec<Divergence>,
+}
+
+impl Comparison {
+    pub fn is_compatible(&self) -> bool {
+        self.divergences.is_empty()
+    }
+}
+
+/// Parses `input` with korni and with `reference`, using later-wins semantics
+/// on both sides, and lists every observable difference.
+pub fn compare(input: &str, reference: &dyn Reference) -> Comparison {
+    let mut korni_errors = Vec::new();
+    let mut korni_map = BTreeMap::new();
+    for entry in crate::parse(input) {
+        match entry {
+            Entry::Pair(kv) => { korni_map.insert(kv.key.into_owned(), kv.value.into_owned()); },
+            Entry::Error(e) => korni_errors.push(e.to_string()),
+            _ => {},
+        }
+    }
+
+    let mut divergences = Vec::new();
+    let reference_map: BTreeMap<String, String> = match reference.parse(input) {
+        Ok(pairs) => {
+            if !korni_errors.is_empty() {
+                divergences.push(Divergence::RejectedByKorni { errors: korni_errors });
+            }
+            pairs.into_iter().collect()
+        },
+        Err(reason) => {
+            if korni_errors.is_empty() {
+                divergences.push(Divergence::RejectedByReference { reason });
+            }
+            return Comparison { reference: reference.name().to_string(), divergences };
+        },
+    };
+
+    for (key, value) in &korni_map {
+        match reference_map.get(key) {
+            None => divergences.push(Divergence::MissingInReference { key: key.clone() }),
+            Some(other) if other != value => divergences.push(Divergence::ValueMismatch {
+                key: key.clone(),
+                korni: value.clone(),
+                reference: other.clone(),
+            }),
+            Some(_) => {},
+        }
+    }
+    for key in reference_map.keys().filter(|k| !korni_map.contains_key(*k)) {
+        divergences.push(Divergence::MissingInKorni { key: key.clone() });
+    }
+
+    Comparison { reference: reference.name().to_string(), divergences }
+}
+
+/// Deterministic generator of random `.env`-like inputs built from
+/// fragments that commonly trip up dotenv implementations.
+#[derive(Debug, Clone)]
+pub struct InputGenerator {
+    state: u64,
+}
+
+const FRAGMENTS: &[&str] = &[
+    "KEY", "_k", "export ", "=", "==", " ", "\t", "'", "\"", "\\", "\\n", "$", "${A}",
+    "#", " # c", "value", "多", "\r", "\n", "1", "-",
+];
+
+impl InputGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        // xorshift64
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    pub fn next_input(&mut self, max_fragments: usize) -> String {
+        let count = (self.next_u64() as usize % max_fragments.max(1)) + 1;
+        let mut input = String::new();
+        for _ in 0..count {
+            let i = self.next_u64() as usize % FRAGMENTS.len();
+            input.push_str(FRAGMENTS[i]);
+        }
+        input
+    }
+}
@@ -0,0 +1,168 @@
+//! Machine-readable conformance vectors: input → expected entries.
+//!
+//! The format is a JSON array of vectors:
+//!
+//! ```json
+//! [{
+//!   "name": "double quoted escapes",
+//!   "input": "K=\"a\\nb\"",
+//!   "options": { "include_comments": true },
+//!   "expected": [
+//!     { "pair": { "key": "K", "value": "a\nb", "quote": "double" } },
+//!     { "comment": { "text": "note" } },
+//!     { "error": "UnclosedQuote" }
+//!   ]
+//! }]
+//! ```
+//!
+//! Omitted pair fields are not checked except `exported`/`commented`, which
+//! default to `false`. Errors and warnings are matched by `Error::kind()`.
+
+use serde::Deserialize;
+use crate::error::Error;
+use crate::types::{Entry, ParseOptions, QuoteType};
+
+/// The vectors korni's own test-suite runs against.
+pub const CORPUS: &str = include_str!("../tests/vectors/edf-1.0.json");
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    pub input: String,
+    #[serde(default)]
+    pub options: VectorOptions,
+    pub expected: Vec<ExpectedEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VectorOptions {
+    pub include_comments: bool,
+    pub warn_duplicate_keys: bool,
+    pub warn_truncated_values: bool,
+    pub forbid_export: bool,
+    pub unicode_keys: bool,
+    pub unquoted_to_eol: bool,
+}
+
+impl VectorOptions {
+    pub fn to_parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            include_comments: self.include_comments,
+            warn_duplicate_keys: self.warn_duplicate_keys,
+            warn_truncated_values: self.warn_truncated_values,
+            forbid_export: self.forbid_export,
+            unicode_keys: self.unicode_keys,
+            unquoted_to_eol: self.unquoted_to_eol,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase", deny_unknown_fields)]
+pub enum ExpectedEntry {
+    Pair(ExpectedPair),
+    Comment(ExpectedComment),
+    Error(String),
+    Warning(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedPair {
+    pub key: String,
+    pub value: String,
+    #[serde(default)]
+    pub quote: Option<ExpectedQuote>,
+    #[serde(default)]
+    pub exported: bool,
+    #[serde(default)]
+    pub commented: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExpectedQuote {
+    Single,
+    Double,
+    None,
+}
+
+impl From<ExpectedQuote> for QuoteType {
+    fn from(quote: ExpectedQuote) -> Self {
+        match quote {
+            ExpectedQuote::Single => QuoteType::Single,
+            ExpectedQuote::Double => QuoteType::Double,
+            ExpectedQuote::None => QuoteType::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExpectedComment {
+    /// Comment content (after the comment character and leading whitespace).
+    pub text: Option<String>,
+}
+
+/// Why a vector failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub vector: String,
+    /// Index of the first differing entry, or `None` for a count mismatch.
+    pub index: Option<usize>,
+    pub message: String,
+}
+
+pub fn load_vectors(json: &str) -> Result<Vec<TestVector>, Error> {
+    serde_json::from_str(json).map_err(|e| Error::Generic {
+        offset: 0,
+        message: format!("Invalid conformance vectors: {}", e),
+    })
+}
+
+impl TestVector {
+    /// Checks korni's own parser against this vector.
+    pub fn check(&self) -> Result<(), Mismatch> {
+        let entries = crate::parse_with_options(&self.input, self.options.to_parse_options());
+        self.check_entries(&entries)
+    }
+
+    /// Checks entries produced by any implementation (e.g. a compatibility
+    /// mode) for this vector's input.
+    pub fn check_entries(&self, entries: &[Entry<'_>]) -> Result<(), Mismatch> {
+        let mismatch = |index, message: String| Mismatch { vector: self.name.clone(), index, message };
+
+        if entries.len() != self.expected.len() {
+            return Err(mismatch(None, format!("expected {} entries, got {}", self.expected.len(), entries.len())));
+        }
+
+        for (i, (expected, actual)) in self.expected.iter().zip(entries).enumerate() {
+            let ok = match (expected, actual) {
+                (ExpectedEntry::Pair(p), Entry::Pair(kv)) => {
+                    p.key == kv.key
+                        && p.value == kv.value
+                        && p.quote.is_none_or(|q| QuoteType::from(q) == kv.quote)
+                        && p.exported == kv.is_exported
+                        && p.commented == kv.is_comment
+                },
+                (ExpectedEntry::Comment(c), Entry::Comment(comment)) => {
+                    c.text.as_ref().is_none_or(|text| self.input.get(comment.content_span.range()) == Some(text.as_str()))
+                },
+                (ExpectedEntry::Error(kind), Entry::Error(e)) => kind == e.kind(),
+                (ExpectedEntry::Warning(kind), Entry::Warning(w)) => kind == w.kind(),
+                _ => false,
+            };
+            if !ok {
+                return Err(mismatch(Some(i), format!("expected {:?}, got {:?}", expected, actual)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs every vector against korni, returning all failures.
+pub fn run(vectors: &[TestVector]) -> Vec<Mismatch> {
+    vectors.iter().filter_map(|v| v.check().err()).collect()
+}
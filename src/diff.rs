@@ -0,0 +1,201 @@
+use crate::document::Document;
+use std::fmt::Write;
+use crate::glob::glob_match;
+
+/// Stands in for values hidden by `Diff::redact`.
+pub const REDACTED: &str = "[REDACTED]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Change {
+    Added { key: String, value: String },
+    Removed { key: String, value: String },
+    Changed { key: String, old: String, new: String },
+}
+
+impl Change {
+    pub fn key(&self) -> &str {
+        match self {
+            Change::Added { key, .. } | Change::Removed { key, .. } | Change::Changed { key, .. } => key,
+        }
+    }
+
+    /// `"added"`, `"removed"` or `"changed"`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Change::Added { .. } => "added",
+            Change::Removed { .. } => "removed",
+            Change::Changed { .. } => "changed",
+        }
+    }
+}
+
+/// Differences between two environments, sorted by key. Produced by
+/// `Environment::diff`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Diff {
+    pub changes: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Change> {
+        self.changes.iter()
+    }
+
+    /// Drops changes to keys matching any of `patterns`, e.g. volatile
+    /// entries like `BUILD_*` or `DEPLOYED_AT`. A pattern is a key name or a
+    /// glob with `*`, `?` and `[...]`.
+    pub fn ignoring<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<S> = patterns.into_iter().collect();
+        self.changes.retain(|change| !patterns.iter().any(|p| glob_match(p.as_ref(), change.key())));
+        self
+    }
+
+    /// Replaces the values of keys matching any of `patterns` (names or
+    /// globs, as for `ignoring`) with `REDACTED`, so the diff can be
+    /// reported without leaking secrets. `["*"]` hides every value. A
+    /// redacted diff should not be passed to `apply_to`.
+    pub fn redact<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<S> = patterns.into_iter().collect();
+        for change in &mut self.changes {
+            if !patterns.iter().any(|p| glob_match(p.as_ref(), change.key())) {
+                continue;
+            }
+            match change {
+                Change::Added { value, .. } | Change::Removed { value, .. } => *value = REDACTED.to_string(),
+                Change::Changed { old, new, .. } => {
+                    *old = REDACTED.to_string();
+                    *new = REDACTED.to_string();
+                },
+            }
+        }
+        self
+    }
+
+    /// The diff as a JSON object with `added`, `removed` and `changed`
+    /// arrays, each sorted by key:
+    ///
+    /// ```json
+    /// {"added":[{"key":"E","value":"5"}],"removed":[],"changed":[{"key":"B","old":"2","new":"20"}]}
+    /// ```
+    ///
+    /// The `serde` feature serializes `Diff` in the same shape.
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{");
+        for (i, kind) in ["added", "removed", "changed"].into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let _ = write!(out, "\"{}\":[", kind);
+            let mut first = true;
+            for change in self.changes.iter().filter(|c| c.kind() == kind) {
+                if !first {
+                    out.push(',');
+                }
+                first = false;
+                let fields: &[(&str, &str)] = match change {
+                    Change::Added { key, value } | Change::Removed { key, value } => &[("key", key), ("value", value)],
+                    Change::Changed { key, old, new } => &[("key", key), ("old", old), ("new", new)],
+                };
+                out.push('{');
+                for (j, (name, value)) in fields.iter().enumerate() {
+                    if j > 0 {
+                        out.push(',');
+                    }
+                    let _ = write!(out, "\"{}\":", name);
+                    json_string(&mut out, value);
+                }
+                out.push('}');
+            }
+            out.push(']');
+        }
+        out.push('}');
+        out
+    }
+
+    /// Updates `doc` so that it defines the new side of this diff. Only the
+    /// lines of touched keys change: values are replaced in place, removed
+    /// keys lose their lines and added keys are appended. The changes are
+    /// applied as one `Document::edit` batch, reparsing once.
+    pub fn apply_to(&self, doc: &mut Document) {
+        self.changes.iter()
+            .fold(doc.edit(), |edit, change| match change {
+                Change::Added { key, value } | Change::Changed { key, new: value, .. } => edit.set(key, value),
+                Change::Removed { key, .. } => edit.remove(key),
+            })
+            .commit();
+    }
+}
+
+impl<'d> IntoIterator for &'d Diff {
+    type Item = &'d Change;
+    type IntoIter = std::slice::Iter<'d, Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub(crate) fn json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => { let _ = write!(out, "\\u{:04x}", c as u32); },
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Diff {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        #[derive(serde::Serialize)]
+        struct Value<'d> { key: &'d str, value: &'d str }
+        #[derive(serde::Serialize)]
+        struct Changed<'d> { key: &'d str, old: &'d str, new: &'d str }
+
+        let values = |kind: &str| -> Vec<Value<'_>> {
+            self.changes.iter()
+                .filter_map(|c| match c {
+                    Change::Added { key, value } | Change::Removed { key, value } if c.kind() == kind => Some(Value { key, value }),
+                    _ => None,
+                })
+                .collect()
+        };
+        let changed: Vec<Changed<'_>> = self.changes.iter()
+            .filter_map(|c| match c {
+                Change::Changed { key, old, new } => Some(Changed { key, old, new }),
+                _ => None,
+            })
+            .collect();
+
+        let mut state = serializer.serialize_struct("Diff", 3)?;
+        state.serialize_field("added", &values("added"))?;
+        state.serialize_field("removed", &values("removed"))?;
+        state.serialize_field("changed", &changed)?;
+        state.end()
+    }
+}
@@ -0,0 +1,760 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use crate::env::Environment;
+use crate::error::Error;
+use crate::line_index::LineIndex;
+use crate::locate::{locate, Located};
+use crate::spec::{validate_key, validate_key_with};
+use crate::types::{Comment, Entry, KeyValuePair, ParseOptions, QuoteType, Span};
+use crate::writer::encode_value;
+
+/// A `.env` file kept as text, with its parsed entries alongside.
+///
+/// Unlike `Environment`, a `Document` is lossless: `to_string()` returns the
+/// source exactly as written, and edits only change the text they touch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Document {
+    source: String,
+    options: ParseOptions,
+    entries: Vec<Entry<'static>>,
+}
+
+/// How `Document::save_with` writes a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SaveOptions {
+    /// Copy the existing file to `<path>.bak` before replacing it.
+    pub backup: bool,
+}
+
+/// Where a key is named, as returned by `Document::occurrences`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyOccurrence {
+    /// The key's name only, without `${` and `}` for references.
+    pub span: Span,
+    pub kind: OccurrenceKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OccurrenceKind {
+    Definition,
+    /// A commented-out definition (`# KEY=value`).
+    Commented,
+    /// A `${KEY}` reference, including forms like `${KEY:-default}`.
+    Reference,
+}
+
+/// Pairs grouped under a `# --- Name ---` header comment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section<'d> {
+    /// The header text, or `None` for pairs before the first header.
+    pub name: Option<&'d str>,
+    pub header: Option<Comment>,
+    pub pairs: Vec<&'d KeyValuePair<'static>>,
+}
+
+impl Document {
+    pub fn parse(input: impl Into<String>) -> Self {
+        Self::parse_with_options(input, ParseOptions::default())
+    }
+
+    /// Writes `(key, value)` pairs as a new document, one `KEY=value` line
+    /// each with values quoted only as needed. Keys are checked with
+    /// `validate_key`; a repeated key is written once, at its first place
+    /// with its last value.
+    pub fn from_pairs<I, K, V>(pairs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let mut lines: Vec<(K, V)> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for (key, value) in pairs {
+            validate_key(key.as_ref())?;
+            match positions.get(key.as_ref()) {
+                Some(&i) => lines[i].1 = value,
+                None => {
+                    positions.insert(key.as_ref().to_string(), lines.len());
+                    lines.push((key, value));
+                },
+            }
+        }
+        let mut source = String::new();
+        for (key, value) in &lines {
+            let (_, encoded) = encode_value(value.as_ref(), QuoteType::None);
+            source.push_str(key.as_ref());
+            source.push('=');
+            source.push_str(&encoded);
+            source.push('\n');
+        }
+        Ok(Self::parse(source))
+    }
+
+    /// Comments and positions are always tracked, whatever `options` says.
+    pub fn parse_with_options(input: impl Into<String>, options: ParseOptions) -> Self {
+        let options = ParseOptions { include_comments: true, track_positions: true, ..options };
+        let mut doc = Document { source: input.into(), options, entries: Vec::new() };
+        doc.reparse();
+        doc
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| Error::Io(format!("Failed to read {}: {}", path.display(), e)))?;
+        Ok(Self::parse(source))
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        self.save_with(path, SaveOptions::default())
+    }
+
+    /// Writes the document atomically: the text goes to a temporary file in
+    /// the same directory, is synced to disk, then renamed over `path`, so
+    /// readers never see a half-written file.
+    ///
+    /// An existing file's permissions are kept. New files that define
+    /// secrets (see `audit::is_secret_key`) are created readable by the
+    /// owner only on Unix.
+    pub fn save_with(&self, path: impl AsRef<Path>, options: SaveOptions) -> Result<(), Error> {
+        let path = path.as_ref();
+        let io_err = |action: &str, e: io::Error| Error::Io(format!("Failed to {} {}: {}", action, path.display(), e));
+
+        let existing = match fs::metadata(path) {
+            Ok(meta) => Some(meta.permissions()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(io_err("inspect", e)),
+        };
+
+        let file_name = path.file_name()
+            .ok_or_else(|| Error::Io(format!("Invalid file path {}", path.display())))?;
+        let mut tmp_name = std::ffi::OsString::from(".");
+        tmp_name.push(file_name);
+        tmp_name.push(format!(".{}.tmp", std::process::id()));
+        let tmp = path.with_file_name(tmp_name);
+
+        let write = || -> io::Result<()> {
+            let mut file = fs::File::create(&tmp)?;
+            match &existing {
+                Some(permissions) => file.set_permissions(permissions.clone())?,
+                None if self.defines_secrets() => restrict_permissions(&file)?,
+                None => {},
+            }
+            file.write_all(self.source.as_bytes())?;
+            file.sync_all()
+        };
+        if let Err(e) = write() {
+            let _ = fs::remove_file(&tmp);
+            return Err(io_err("write", e));
+        }
+
+        if options.backup && existing.is_some() {
+            let mut backup = path.as_os_str().to_owned();
+            backup.push(".bak");
+            if let Err(e) = fs::copy(path, &backup) {
+                let _ = fs::remove_file(&tmp);
+                return Err(io_err("back up", e));
+            }
+        }
+
+        if let Err(e) = fs::rename(&tmp, path) {
+            let _ = fs::remove_file(&tmp);
+            return Err(io_err("replace", e));
+        }
+        sync_parent_dir(path);
+        Ok(())
+    }
+
+    fn defines_secrets(&self) -> bool {
+        self.entries.iter()
+            .filter_map(Entry::as_pair)
+            .any(|kv| crate::audit::is_secret_key(&kv.key))
+    }
+
+    fn reparse(&mut self) {
+        self.entries = crate::parse_with_options(&self.source, self.options)
+            .into_iter()
+            .map(Entry::into_owned)
+            .collect();
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn entries(&self) -> &[Entry<'static>] {
+        &self.entries
+    }
+
+    /// The last active definition of `key`.
+    pub fn get(&self, key: &str) -> Option<&KeyValuePair<'static>> {
+        self.entries.iter()
+            .rev()
+            .filter_map(Entry::as_pair)
+            .find(|kv| !kv.is_comment && kv.key == key)
+    }
+
+    /// The entry at a 0-indexed line and byte column, and the part of it
+    /// there.
+    pub fn entry_at(&self, line: usize, col: usize) -> Option<Located<'_, 'static>> {
+        let offset = LineIndex::new(&self.source).offset(line, col)?;
+        let comments = self.entries.iter().filter_map(Entry::as_comment);
+        locate(self.entries.iter().filter_map(Entry::as_pair), comments, Some(&self.source), offset)
+    }
+
+    pub fn to_env(&self) -> Environment<'static> {
+        let mut env = Environment::from_entries(self.entries.clone());
+        env.read_metadata(&self.source);
+        env.with_source(self.source.as_str())
+    }
+
+    /// Pairs grouped by the header comment above them, in source order.
+    pub fn sections(&self) -> Vec<Section<'_>> {
+        let mut sections = vec![Section { name: None, header: None, pairs: Vec::new() }];
+        for entry in &self.entries {
+            match entry {
+                Entry::Comment(c) => {
+                    if let Some(name) = section_name(c.content(&self.source)) {
+                        sections.push(Section { name: Some(name), header: Some(*c), pairs: Vec::new() });
+                    }
+                },
+                Entry::Pair(kv) => sections.last_mut().unwrap().pairs.push(kv),
+                _ => {},
+            }
+        }
+        if sections[0].pairs.is_empty() {
+            sections.remove(0);
+        }
+        sections
+    }
+
+    pub fn section(&self, name: &str) -> Option<Section<'_>> {
+        self.sections().into_iter().find(|s| s.name == Some(name))
+    }
+
+    /// Sets `key` to `value`, replacing the value of its last active
+    /// definition in place, or appending `KEY=value` to the end of the file.
+    ///
+    /// A replaced value keeps its quoting style unless the new value cannot
+    /// be written that way, in which case it is double-quoted.
+    pub fn set(&mut self, key: &str, value: &str) {
+        match self.replace_value(key, value) {
+            Some(edit) => self.apply(vec![edit]),
+            None => {
+                let end = self.source.len();
+                self.insert_pair(end, key, value, None);
+            },
+        }
+    }
+
+    /// Like `set`, but a new pair is added after the last pair of its
+    /// prefix group (`DB_USER` goes after the last `DB_*` key) rather than
+    /// at the end of the file, preceded by `comment` as `#` lines if given.
+    /// The comment is not used when `key` already exists.
+    pub fn upsert(&mut self, key: &str, value: &str, comment: Option<&str>) {
+        if self.get(key).is_some() {
+            self.set(key, value);
+            return;
+        }
+        let at = self.prefix_group_end(key).unwrap_or(self.source.len());
+        self.insert_pair(at, key, value, comment);
+    }
+
+    /// Deletes the lines of every active definition of `key`, returning
+    /// whether there were any.
+    pub fn remove(&mut self, key: &str) -> bool {
+        let edits: Vec<_> = self.removals(key).into_iter().map(|range| (range, String::new())).collect();
+        let removed = !edits.is_empty();
+        if removed {
+            self.apply(edits);
+        }
+        removed
+    }
+
+    /// Renames every definition of `old`, commented-out ones included, and
+    /// rewrites `${old}` references in other values to match. References in
+    /// single-quoted values are literal text and are left alone.
+    ///
+    /// Returns `Ok(false)` without changes if `old` is not defined, the
+    /// error of `validate_key_with` if `new` is not a valid key under the
+    /// document's options, and an error if it is already defined.
+    pub fn rename_key(&mut self, old: &str, new: &str) -> Result<bool, Error> {
+        validate_key_with(new, self.options.unicode_keys)?;
+        if let Some(existing) = self.get(new).filter(|_| old != new) {
+            return Err(Error::Generic {
+                offset: existing.key_span.map_or(0, |s| s.start.offset),
+                message: format!("Renaming '{}' to '{}' collides with an existing key", old, new),
+            });
+        }
+
+        let occurrences = self.occurrences(old);
+        if occurrences.iter().all(|o| o.kind == OccurrenceKind::Reference) {
+            return Ok(false);
+        }
+        self.apply(occurrences.into_iter().map(|o| (o.span.range(), new.to_string())).collect());
+        Ok(true)
+    }
+
+    /// Every place `key` is named, in source order: its definitions,
+    /// commented-out ones included, and `${key}` references in other values.
+    /// Single-quoted values are literal text and never reference a key.
+    pub fn occurrences(&self, key: &str) -> Vec<KeyOccurrence> {
+        let index = LineIndex::new(&self.source);
+        let mut occurrences = Vec::new();
+        for kv in self.entries.iter().filter_map(Entry::as_pair) {
+            if let Some(span) = kv.key_span.filter(|_| kv.key == key) {
+                let kind = if kv.is_comment { OccurrenceKind::Commented } else { OccurrenceKind::Definition };
+                occurrences.push(KeyOccurrence { span, kind });
+            }
+            let Some(content) = kv.content_span.filter(|_| kv.quote != QuoteType::Single) else { continue };
+            let raw = &self.source[content.range()];
+            occurrences.extend(crate::interpolation::references(raw, kv.quote == QuoteType::Double).into_iter()
+                .filter(|name| &raw[name.clone()] == key)
+                .map(|name| KeyOccurrence {
+                    span: index.span(Span::from_offsets(content.start.offset + name.start, content.start.offset + name.end)),
+                    kind: OccurrenceKind::Reference,
+                }));
+        }
+        occurrences
+    }
+
+    /// Uncomments the last commented-out definition of `key`, turning
+    /// `# KEY=value` back into `KEY=value` with the line's indentation and
+    /// `export` prefix kept. Returns whether there was one; an active
+    /// definition of `key` is left alone, so the later of the two wins.
+    pub fn enable(&mut self, key: &str) -> bool {
+        let index = LineIndex::new(&self.source);
+        let marker = self.entries.iter()
+            .rev()
+            .filter_map(Entry::as_pair)
+            .filter(|kv| kv.is_comment && kv.key == key)
+            .find_map(|kv| {
+                let key_start = kv.key_span?.start;
+                let line_start = index.offset(key_start.line, 0)?;
+                let prefix = &self.source.as_bytes()[line_start..key_start.offset];
+                let hash = line_start + prefix.iter().position(|b| self.options.comment_chars.contains(b))?;
+                let rest = &self.source.as_bytes()[hash + 1..];
+                Some(hash..hash + 1 + rest.iter().take_while(|&&b| b == b' ' || b == b'\t').count())
+            });
+        match marker {
+            Some(range) => {
+                self.apply(vec![(range, String::new())]);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Comments out every active definition of `key` by inserting `# `
+    /// after the line's indentation, so that `enable` restores it exactly.
+    /// Returns whether there were any.
+    pub fn disable(&mut self, key: &str) -> bool {
+        let comment = format!("{} ", self.options.comment_chars.first().map_or('#', |&c| c as char));
+        let edits: Vec<TextEdit> = self.removals(key).into_iter()
+            .map(|line| {
+                let indent = self.source.as_bytes()[line.clone()].iter().take_while(|&&b| b == b' ' || b == b'\t').count();
+                (line.start + indent..line.start + indent, comment.clone())
+            })
+            .collect();
+        let disabled = !edits.is_empty();
+        if disabled {
+            self.apply(edits);
+        }
+        disabled
+    }
+
+    /// Starts a batch of edits that are applied together, with the document
+    /// reparsed once, when the returned `Edit` is committed.
+    pub fn edit(&mut self) -> Edit<'_> {
+        Edit { doc: self, ops: Vec::new() }
+    }
+
+    /// The new value text for the last active definition of `key`.
+    fn replace_value(&self, key: &str, value: &str) -> Option<TextEdit> {
+        let kv = self.get(key)?;
+        let (_, encoded) = encode_value(value, kv.quote);
+        Some((value_range(kv), encoded))
+    }
+
+    /// Whole-line ranges of every active definition of `key`.
+    fn removals(&self, key: &str) -> Vec<std::ops::Range<usize>> {
+        let index = LineIndex::new(&self.source);
+        self.entries.iter()
+            .filter_map(Entry::as_pair)
+            .filter(|kv| !kv.is_comment && kv.key == key)
+            .filter_map(|kv| {
+                let start = index.offset(kv.key_span?.start.line, 0)?;
+                let last = kv.raw_span.map_or(kv.key_span?.end.line, |s| s.end.line);
+                let end = index.offset(last + 1, 0).unwrap_or(self.source.len());
+                Some(start..end)
+            })
+            .collect()
+    }
+
+    /// Byte offset just past the line of the last active pair sharing
+    /// `key`'s prefix (the part before the first `_`).
+    fn prefix_group_end(&self, key: &str) -> Option<usize> {
+        let (prefix, _) = key.split_once('_')?;
+        let last = self.entries.iter()
+            .rev()
+            .filter_map(Entry::as_pair)
+            .find(|kv| !kv.is_comment && kv.key.split_once('_').is_some_and(|(p, _)| p == prefix))?;
+        let line = last.raw_span.or(last.key_span)?.end.line;
+        Some(LineIndex::new(&self.source).offset(line + 1, 0).unwrap_or(self.source.len()))
+    }
+
+    /// Inserts `KEY=value` (and its comment lines) as whole lines at `at`,
+    /// which must be the start of a line or the end of the source.
+    fn insert_pair(&mut self, at: usize, key: &str, value: &str, comment: Option<&str>) {
+        let mut text = self.line_break_before(at).to_string();
+        text.push_str(&self.pair_lines(key, value, comment));
+        self.apply(vec![(at..at, text)]);
+    }
+
+    /// The newline needed before inserting whole lines at `at`, if the
+    /// source ends there without one.
+    fn line_break_before(&self, at: usize) -> &'static str {
+        if at == self.source.len() && !self.source.is_empty() && !self.source.ends_with('\n') {
+            self.newline()
+        } else {
+            ""
+        }
+    }
+
+    fn pair_lines(&self, key: &str, value: &str, comment: Option<&str>) -> String {
+        let newline = self.newline();
+        let mut text = String::new();
+        for line in comment.into_iter().flat_map(str::lines) {
+            text.push('#');
+            if !line.is_empty() {
+                text.push(' ');
+                text.push_str(line);
+            }
+            text.push_str(newline);
+        }
+        let (_, encoded) = encode_value(value, QuoteType::None);
+        text.push_str(key);
+        text.push('=');
+        text.push_str(&encoded);
+        text.push_str(newline);
+        text
+    }
+
+    /// Applies non-overlapping edits to the source and reparses once.
+    fn apply(&mut self, edits: Vec<TextEdit>) {
+        apply_edits(&mut self.source, edits);
+        self.reparse();
+    }
+
+    fn newline(&self) -> &'static str {
+        if self.source.contains("\r\n") { "\r\n" } else { "\n" }
+    }
+
+    /// Sorts pairs by key across the whole file. Each pair moves together
+    /// with the comment lines directly above it and its inline comment;
+    /// blank lines, detached comments and section headers stay where they are.
+    pub fn sort_keys(&mut self) {
+        self.sort_blocks(false);
+    }
+
+    /// Like `sort_keys`, but pairs never leave their section.
+    pub fn sort_keys_in_sections(&mut self) {
+        self.sort_blocks(true);
+    }
+
+    fn sort_blocks(&mut self, per_section: bool) {
+        let lines: Vec<&str> = self.source.split_inclusive('\n').collect();
+        let blocks = self.pair_blocks(lines.len());
+
+        let mut sorted = blocks.clone();
+        sorted.sort_by(|a, b| {
+            let group = |block: &Block| if per_section { block.section } else { 0 };
+            group(a).cmp(&group(b)).then_with(|| a.key.cmp(&b.key))
+        });
+        if sorted == blocks {
+            return;
+        }
+
+        let newline = self.newline();
+        let mut out = String::with_capacity(self.source.len() + newline.len());
+        let mut line = 0;
+        let mut slots = blocks.iter().zip(&sorted).peekable();
+        while line < lines.len() {
+            let Some((slot, block)) = slots.next_if(|(slot, _)| slot.lines.start == line) else {
+                out.push_str(lines[line]);
+                line += 1;
+                continue;
+            };
+            for text in &lines[block.lines.clone()] {
+                out.push_str(text);
+                if !text.ends_with('\n') {
+                    out.push_str(newline);
+                }
+            }
+            line = slot.lines.end;
+        }
+        if !self.source.ends_with('\n') {
+            out.truncate(out.trim_end_matches(['\r', '\n']).len());
+        }
+
+        self.source = out;
+        self.reparse();
+    }
+
+    /// Every active pair with its leading comment lines, in source order.
+    fn pair_blocks(&self, line_count: usize) -> Vec<Block> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Line { Other, Comment, Header, Pair(usize) }
+
+        let mut kinds = vec![Line::Other; line_count];
+        for (i, entry) in self.entries.iter().enumerate() {
+            match entry {
+                Entry::Comment(c) => {
+                    let header = section_name(c.content(&self.source)).is_some();
+                    kinds[c.span.start.line] = if header { Line::Header } else { Line::Comment };
+                },
+                Entry::Pair(kv) => {
+                    let (Some(key_span), raw_span) = (kv.key_span, kv.raw_span) else { continue };
+                    if kv.is_comment {
+                        kinds[key_span.start.line] = Line::Comment;
+                        continue;
+                    }
+                    let end = raw_span.map_or(key_span.end.line, |s| s.end.line);
+                    for kind in &mut kinds[key_span.start.line..=end.min(line_count - 1)] {
+                        *kind = Line::Pair(i);
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        let mut blocks = Vec::new();
+        let mut section = 0;
+        let mut comments_from = None;
+        let mut line = 0;
+        while line < line_count {
+            match kinds[line] {
+                Line::Comment => { comments_from.get_or_insert(line); },
+                Line::Pair(i) => {
+                    let start = comments_from.take().unwrap_or(line);
+                    while line + 1 < line_count && kinds[line + 1] == Line::Pair(i) {
+                        line += 1;
+                    }
+                    let key = self.entries[i].key().unwrap_or_default().to_string();
+                    blocks.push(Block { key, section, lines: start..line + 1 });
+                },
+                Line::Header => {
+                    section += 1;
+                    comments_from = None;
+                },
+                Line::Other => comments_from = None,
+            }
+            line += 1;
+        }
+        blocks
+    }
+}
+
+/// Byte range of the value as written, quotes included.
+fn value_range(kv: &KeyValuePair<'_>) -> std::ops::Range<usize> {
+    match (kv.raw_span, kv.equals_pos) {
+        (Some(span), _) => span.range(),
+        (None, Some(equals)) => equals.offset + 1..equals.offset + 1,
+        (None, None) => kv.key_span.map_or(0..0, |s| s.end.offset..s.end.offset),
+    }
+}
+
+/// A byte range of the source and the text replacing it.
+type TextEdit = (std::ops::Range<usize>, String);
+
+fn apply_edits(source: &mut String, mut edits: Vec<TextEdit>) {
+    edits.sort_by_key(|(range, _)| range.start);
+    for (range, text) in edits.into_iter().rev() {
+        source.replace_range(range, &text);
+    }
+}
+
+/// Edits staged by `Document::edit`. Nothing changes until `commit`;
+/// dropping an uncommitted `Edit` discards it.
+///
+/// Every operation is resolved against the document as it was when the
+/// batch started. Staging a key again replaces its earlier operation.
+#[must_use = "staged edits are discarded unless committed"]
+pub struct Edit<'d> {
+    doc: &'d mut Document,
+    /// The last operation per key (`None` removes it), in the order the
+    /// keys were first staged.
+    ops: Vec<(String, Option<String>)>,
+}
+
+impl Edit<'_> {
+    /// Stages `Document::set(key, value)`.
+    pub fn set(self, key: &str, value: &str) -> Self {
+        self.stage(key, Some(value.to_string()))
+    }
+
+    /// Stages `Document::remove(key)`.
+    pub fn remove(self, key: &str) -> Self {
+        self.stage(key, None)
+    }
+
+    fn stage(mut self, key: &str, value: Option<String>) -> Self {
+        match self.ops.iter_mut().find(|(k, _)| k == key) {
+            Some(op) => op.1 = value,
+            None => self.ops.push((key.to_string(), value)),
+        }
+        self
+    }
+
+    /// A unified diff (without context lines) of what `commit` would
+    /// change. It shows file content, values included.
+    pub fn dry_run(&self) -> String {
+        let mut source = self.doc.source.clone();
+        apply_edits(&mut source, self.edits());
+        line_diff(&self.doc.source, &source)
+    }
+
+    pub fn commit(self) {
+        let edits = self.edits();
+        if !edits.is_empty() {
+            self.doc.apply(edits);
+        }
+    }
+
+    fn edits(&self) -> Vec<TextEdit> {
+        let doc = &*self.doc;
+        let end = doc.source.len();
+        let mut edits = Vec::new();
+        let mut appended = String::new();
+        // Removing the unterminated last line leaves a terminated one
+        let mut break_before = doc.line_break_before(end);
+        for (key, value) in &self.ops {
+            match value {
+                Some(value) => match doc.replace_value(key, value) {
+                    Some(edit) => edits.push(edit),
+                    None => appended.push_str(&doc.pair_lines(key, value, None)),
+                },
+                None => {
+                    for range in doc.removals(key) {
+                        if range.end == end {
+                            break_before = "";
+                        }
+                        edits.push((range, String::new()));
+                    }
+                },
+            }
+        }
+        if !appended.is_empty() {
+            edits.push((end..end, format!("{}{}", break_before, appended)));
+        }
+        edits
+    }
+}
+
+/// Line-based unified diff of `old` and `new` with no context lines.
+fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Edits usually touch a few lines, so only the part between the common
+    // prefix and suffix needs the quadratic table
+    let prefix = old.iter().zip(&new).take_while(|(x, y)| x == y).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(x, y)| x == y).count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence of lines, computed from the end
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut out = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+            continue;
+        }
+        let (hunk_i, hunk_j) = (i, j);
+        while (i < a.len() || j < b.len()) && !(i < a.len() && j < b.len() && a[i] == b[j]) {
+            if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        let range = |start: usize, len: usize| {
+            let start = prefix + start;
+            if len == 0 { format!("{},0", start) } else { format!("{},{}", start + 1, len) }
+        };
+        out.push_str(&format!("@@ -{} +{} @@\n", range(hunk_i, i - hunk_i), range(hunk_j, j - hunk_j)));
+        for (sign, lines) in [('-', &a[hunk_i..i]), ('+', &b[hunk_j..j])] {
+            for line in lines {
+                out.push(sign);
+                out.push_str(line);
+                if !line.ends_with('\n') {
+                    out.push_str("\n\\ No newline at end of file\n");
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Lines of one sortable pair, including the comments directly above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Block {
+    key: String,
+    section: usize,
+    lines: std::ops::Range<usize>,
+}
+
+#[cfg(unix)]
+fn restrict_permissions(file: &fs::File) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    file.set_permissions(fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_permissions(_file: &fs::File) -> io::Result<()> {
+    Ok(())
+}
+
+/// Makes the rename itself durable. Best effort: not every platform can
+/// open or sync a directory.
+fn sync_parent_dir(path: &Path) {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    if let Ok(dir) = fs::File::open(parent) {
+        let _ = dir.sync_all();
+    }
+}
+
+const RULE_CHARS: &[char] = &['-', '='];
+
+/// `--- Database ---` or `=== Database ===` (at least three rule characters
+/// on each side) names the section `Database`.
+pub(crate) fn section_name(content: &str) -> Option<&str> {
+    let content = content.trim();
+    let inner = content.trim_start_matches(RULE_CHARS);
+    let leading = content.len() - inner.len();
+    let name = inner.trim_end_matches(RULE_CHARS);
+    let trailing = inner.len() - name.len();
+    let name = name.trim();
+    (leading >= 3 && trailing >= 3 && !name.is_empty()).then_some(name)
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
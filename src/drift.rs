@@ -0,0 +1,143 @@
+//! Detecting when a `.env` file drifts from a reference environment.
+//!
+//! `watch` polls a file and reports a `Diff` against the reference each time
+//! the set of differences changes, with secret values redacted, so a drift
+//! daemon is just a loop over the returned iterator.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::audit::is_secret_key;
+use crate::diff::Diff;
+use crate::env::Environment;
+use crate::error::Error;
+use crate::loader::Korni;
+
+/// Watches `path` for drift from `reference`, checking every `interval`.
+///
+/// Each item is the diff from `reference` to the file's current content
+/// (`Removed` means the file lacks a reference key), reported only when it
+/// differs from the previously reported one. The first check reports only
+/// if there is drift; an empty diff later means the file is back in sync.
+/// Iterating blocks between checks; use `DriftWatch::poll` to check from
+/// your own loop instead.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// let reference = korni::Korni::from_file(".env.production").parse()?;
+/// for drift in korni::drift::watch(".env", &reference, Duration::from_secs(30)).ignoring(["BUILD_*"]) {
+///     println!("{}", drift?.to_json());
+/// }
+/// # Ok::<(), korni::Error>(())
+/// ```
+pub fn watch(path: impl Into<PathBuf>, reference: &Environment<'_>, interval: Duration) -> DriftWatch {
+    DriftWatch {
+        path: path.into(),
+        reference: reference.clone().into_owned(),
+        interval,
+        ignore: Vec::new(),
+        redact: Vec::new(),
+        stamp: None,
+        reported: None,
+    }
+}
+
+/// Iterator of drift notifications returned by `watch`.
+#[derive(Debug)]
+pub struct DriftWatch {
+    path: PathBuf,
+    reference: Environment<'static>,
+    interval: Duration,
+    ignore: Vec<String>,
+    redact: Vec<String>,
+    /// Modification time and length at the last read, to skip rereading an
+    /// unchanged file.
+    stamp: Option<(SystemTime, u64)>,
+    /// The last notification before redaction, so that a secret changing
+    /// again is noticed, or the message of the last reported error.
+    reported: Option<Result<Diff, String>>,
+}
+
+impl DriftWatch {
+    /// Leaves keys matching any of `patterns` (names or globs) out of the
+    /// comparison, as `Diff::ignoring` does.
+    pub fn ignoring<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.ignore.extend(patterns.into_iter().map(|p| p.as_ref().to_string()));
+        self
+    }
+
+    /// Also redacts the values of keys matching `patterns`. Keys that
+    /// `audit::is_secret_key` recognizes are always redacted.
+    pub fn redact<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.redact.extend(patterns.into_iter().map(|p| p.as_ref().to_string()));
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks the file once, without waiting. Returns a notification if the
+    /// drift changed since the last one, or an error if the file could not
+    /// be read (reported once until reading succeeds again).
+    pub fn poll(&mut self) -> Option<Result<Diff, Error>> {
+        let stamp = std::fs::metadata(&self.path)
+            .ok()
+            .and_then(|m| Some((m.modified().ok()?, m.len())));
+        if stamp.is_some() && stamp == self.stamp {
+            return None;
+        }
+
+        let current = Korni::from_file(&self.path).parse().map(|env| self.reference.diff(&env).ignoring(&self.ignore));
+        match current {
+            Ok(diff) => {
+                self.stamp = stamp;
+                let unchanged = match &self.reported {
+                    Some(Ok(last)) => *last == diff,
+                    None => diff.is_empty(),
+                    Some(Err(_)) => false,
+                };
+                let notification = (!unchanged).then(|| Ok(self.redacted(&diff)));
+                self.reported = Some(Ok(diff));
+                notification
+            },
+            Err(e) => {
+                self.stamp = None;
+                let message = e.to_string();
+                let repeated = matches!(&self.reported, Some(Err(last)) if *last == message);
+                self.reported = Some(Err(message));
+                (!repeated).then_some(Err(e))
+            },
+        }
+    }
+
+    fn redacted(&self, diff: &Diff) -> Diff {
+        let secrets: Vec<String> = diff.iter()
+            .map(|change| change.key())
+            .filter(|key| is_secret_key(key))
+            .map(str::to_string)
+            .collect();
+        diff.clone().redact(self.redact.iter().chain(&secrets))
+    }
+}
+
+impl Iterator for DriftWatch {
+    type Item = Result<Diff, Error>;
+
+    /// Blocks until the next notification; never returns `None`.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.poll() {
+                return Some(item);
+            }
+            std::thread::sleep(self.interval);
+        }
+    }
+}
@@ -1,14 +1,89 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::borrow::Cow;
-use crate::types::{Entry, KeyValuePair, Span};
+use std::ffi::{OsStr, OsString};
+use std::sync::Arc;
+use crate::types::{Annotation, Comment, Entry, KeyValuePair, QuoteType};
 use crate::error::Error;
+use crate::date::Date;
+use crate::diff::{Change, Diff};
+use crate::graph::DependencyGraph;
+use crate::infer::{infer_types, TypeGuess};
+use crate::interpolation::references;
+use crate::limits::{check_limits, Platform};
+use crate::line_index::LineIndex;
+use crate::locate::{locate, Located};
+use crate::metadata::Metadata;
+use crate::schema::Schema;
+use crate::spec::validate_key;
+use crate::table::PairTable;
+use crate::writer::{minimal_quote, EnvDisplay, SortedDisplay, WriteOptions};
+
+/// Summary counts returned by `Environment::stats()`. Values are measured,
+/// never included.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Stats {
+    pub pairs: usize,
+    pub unquoted: usize,
+    pub single_quoted: usize,
+    pub double_quoted: usize,
+    pub exported: usize,
+    /// Commented-out pairs (`# KEY=value`).
+    pub commented: usize,
+    pub comments: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    pub longest_key: Option<String>,
+    pub longest_value_key: Option<String>,
+    pub longest_value_len: usize,
+    /// Sum of decoded value lengths in bytes.
+    pub total_value_bytes: usize,
+}
+
+/// How `Environment::apply()` treats variables already set in the process,
+/// set with the builders' `override_policy()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverridePolicy {
+    /// Variables already set keep their values (dotenvy's `dotenv()`).
+    #[default]
+    ProcessEnvWins,
+    /// File values replace existing variables (dotenvy's `dotenv_override()`).
+    FileWins,
+    /// The process environment is left untouched; the file is only read.
+    Ignore,
+}
+
+/// An `Environment` that can no longer change, returned by
+/// `Environment::freeze`. It offers the whole read-only API of
+/// `Environment` through `Deref`; clones share it.
+#[derive(Debug, Clone)]
+pub struct FrozenEnvironment(Arc<Environment<'static>>);
+
+impl std::ops::Deref for FrozenEnvironment {
+    type Target = Environment<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 /// Parsed environment with rich query API.
+///
+/// Clones share their pairs, comments and diagnostics, so passing snapshots
+/// around copies nothing; a clone that is changed only stores its changes
+/// until they add up.
 #[derive(Debug, Clone, Default)]
 pub struct Environment<'a> {
-    pub(crate) pairs: HashMap<Cow<'a, str>, KeyValuePair<'a>>,
-    pub(crate) comments: Vec<Span>,
-    pub(crate) errors: Vec<Error>,
+    pub(crate) pairs: PairTable<'a>,
+    pub(crate) comments: Arc<Vec<Comment>>,
+    pub(crate) errors: Arc<Vec<Error>>,
+    pub(crate) warnings: Arc<Vec<Error>>,
+    /// Variables whose key or value is not valid UTF-8, kept verbatim.
+    pub(crate) os_vars: Arc<Vec<(OsString, OsString)>>,
+    pub(crate) source: Option<Arc<str>>,
+    pub(crate) policy: OverridePolicy,
+    pub(crate) metadata: Option<Arc<Metadata>>,
+    /// Values of commented-out definitions per key, in source order.
+    pub(crate) alternatives: Arc<HashMap<Cow<'a, str>, Vec<Cow<'a, str>>>>,
 }
 
 impl<'a> Environment<'a> {
@@ -16,23 +91,273 @@ impl<'a> Environment<'a> {
         Self::default()
     }
 
+    /// Builds an environment from `(key, value)` pairs, each quoted as
+    /// `minimal_quote` chooses so that writing it out gives a valid file.
+    /// Keys are checked with `validate_key`; a repeated key keeps the place
+    /// of its first pair and the value of its last.
+    pub fn from_pairs<I, K, V>(pairs: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        let mut table = PairTable::default();
+        let mut next = 0;
+        for (key, value) in pairs {
+            let (key, value) = (key.into(), value.into());
+            validate_key(&key)?;
+            let index = match table.get(&key) {
+                Some(existing) => existing.index,
+                None => {
+                    next += 1;
+                    next - 1
+                },
+            };
+            let quote = minimal_quote(&value);
+            let mut kv = KeyValuePair::new_fast(key, value, quote, false, false);
+            kv.index = index;
+            table.insert(kv);
+        }
+        Ok(Environment { pairs: table, ..Environment::default() })
+    }
+
     pub fn from_entries(entries: Vec<Entry<'a>>) -> Self {
-        let mut env = Environment {
-            pairs: HashMap::with_capacity(entries.len()),
-            comments: Vec::with_capacity(entries.len() / 4),
-            errors: Vec::new(),
-        };
+        let mut alternatives: HashMap<Cow<'a, str>, Vec<Cow<'a, str>>> = HashMap::new();
+        let mut comments = Vec::with_capacity(entries.len() / 4);
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+        let mut env = Environment { pairs: PairTable::with_capacity(entries.len()), ..Environment::default() };
         
         for entry in entries {
             match entry {
-                Entry::Pair(kv) => { env.pairs.insert(kv.key.clone(), *kv); },
-                Entry::Comment(span) => { env.comments.push(span); },
-                Entry::Error(err) => { env.errors.push(err); },
+                Entry::Pair(kv) => {
+                    if kv.is_comment {
+                        alternatives.entry(kv.key.clone()).or_default().push(kv.value.clone());
+                        // A commented-out definition never hides an active one
+                        if env.pairs.get(&kv.key).is_some_and(|existing| !existing.is_comment) {
+                            continue;
+                        }
+                    }
+                    env.pairs.insert_boxed(kv);
+                },
+                Entry::Comment(c) => { comments.push(c); },
+                Entry::Error(err) => { errors.push(err); },
+                Entry::Warning(w) => { warnings.push(w); },
             }
         }
+        env.comments = Arc::new(comments);
+        env.errors = Arc::new(errors);
+        env.warnings = Arc::new(warnings);
+        env.alternatives = Arc::new(alternatives);
         env
     }
 
+    /// Adds `other`'s pairs, overriding existing active definitions, and its
+    /// errors and warnings. Its pairs order after the current ones; its
+    /// comments and source are dropped, as their spans refer to another text.
+    pub(crate) fn extend(&mut self, other: Environment<'a>) {
+        let offset = self.pairs.values().map(|kv| kv.index + 1).max().unwrap_or(0);
+        for mut kv in other.pairs.into_pairs() {
+            if kv.is_comment && self.pairs.get(&kv.key).is_some_and(|existing| !existing.is_comment) {
+                continue;
+            }
+            kv.index += offset;
+            self.pairs.insert(kv);
+        }
+        append(&mut self.errors, other.errors);
+        append(&mut self.warnings, other.warnings);
+        append(&mut self.os_vars, other.os_vars);
+        if !other.alternatives.is_empty() {
+            let alternatives = Arc::make_mut(&mut self.alternatives);
+            for (key, values) in Arc::unwrap_or_clone(other.alternatives) {
+                alternatives.entry(key).or_default().extend(values);
+            }
+        }
+        self.source = None;
+        if self.metadata.is_none() {
+            self.metadata = other.metadata;
+        }
+    }
+
+    /// Reads the `# korni:` metadata block of `input`, the text this
+    /// environment was parsed from; a malformed block becomes a warning.
+    pub(crate) fn read_metadata(&mut self, input: &str) {
+        match Metadata::from_source(input) {
+            Ok(metadata) => self.metadata = metadata.map(Arc::new),
+            Err(e) => Arc::make_mut(&mut self.warnings).push(e),
+        }
+    }
+
+    /// The file's `# korni: version=1, profile=prod` header, if it has one.
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata.as_deref()
+    }
+
+    /// Adds every pair of `other` as `{prefix}_{KEY}`, e.g. a service's
+    /// `HOST` as `DB_HOST` for prefix `DB`, overriding existing definitions
+    /// as loading a later file would. Errors and warnings are appended
+    /// unchanged.
+    pub fn merge_namespaced(&mut self, prefix: &str, mut other: Environment<'a>) {
+        let pairs = std::mem::take(&mut other.pairs).into_pairs().into_iter()
+            .map(|mut kv| {
+                kv.key = Cow::Owned(format!("{}_{}", prefix, kv.key));
+                kv
+            });
+        other.pairs = PairTable::from_pairs(pairs);
+        other.alternatives = Arc::new(Arc::unwrap_or_clone(std::mem::take(&mut other.alternatives)).into_iter()
+            .map(|(key, values)| (Cow::Owned(format!("{}_{}", prefix, key)), values))
+            .collect());
+        self.extend(other);
+    }
+
+    /// A child environment for per-request or per-test overrides: it starts
+    /// with this environment's pairs and records only its own `set` and
+    /// `remove` calls, without copying the pairs it inherits. Changes to
+    /// either environment afterwards do not affect the other.
+    pub fn overlay(&self) -> Environment<'a> {
+        Environment { pairs: self.pairs.layer(), ..self.clone() }
+    }
+
+    /// Sets `key` to `value` as an unquoted, unexported pair. A new key
+    /// orders after the existing pairs; an existing one keeps its place.
+    /// The key is not validated.
+    pub fn set(&mut self, key: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) {
+        let key = key.into();
+        let index = match self.pairs.get(&key) {
+            Some(existing) => existing.index,
+            None => self.pairs.values().map(|kv| kv.index + 1).max().unwrap_or(0),
+        };
+        let mut kv = KeyValuePair::new_fast(key, value.into(), QuoteType::None, false, false);
+        kv.index = index;
+        self.pairs.insert(kv);
+    }
+
+    /// Sets every key `schema` gives a default for that is not set (or only
+    /// commented out) to that default. Such pairs carry a
+    /// `@provenance: default` annotation, so they can be told apart from
+    /// configured values; they order after the existing pairs.
+    pub fn with_defaults(mut self, schema: &Schema) -> Self {
+        let mut index = self.pairs.values().map(|kv| kv.index + 1).max().unwrap_or(0);
+        for (key, spec) in schema.keys() {
+            let Some(default) = &spec.default else { continue };
+            if self.pairs.get(key).is_some_and(|kv| !kv.is_comment) {
+                continue;
+            }
+            let mut kv = KeyValuePair::new_fast(key.to_string(), Cow::Owned(default.clone()), minimal_quote(default), false, false);
+            kv.index = index;
+            kv.annotations.push(Annotation { name: Cow::Borrowed("provenance"), value: Cow::Borrowed("default") });
+            self.pairs.insert(kv);
+            index += 1;
+        }
+        self
+    }
+
+    /// Removes `key`, returning its pair if it was defined.
+    pub fn remove(&mut self, key: &str) -> Option<KeyValuePair<'a>> {
+        self.pairs.remove(key)
+    }
+
+    /// Seals the environment, e.g. after startup validation: the result can
+    /// be shared across threads but never changed.
+    pub fn freeze(self) -> FrozenEnvironment {
+        FrozenEnvironment(Arc::new(self.into_owned()))
+    }
+
+    /// The pairs whose keys start with `{prefix}_`, with that prefix removed:
+    /// the reverse of `merge_namespaced`. Errors, warnings and comments are
+    /// not copied.
+    pub fn strip_prefix(&self, prefix: &str) -> Environment<'a> {
+        let pairs = self.pairs.values()
+            .filter_map(|kv| {
+                let stripped = kv.key.strip_prefix(prefix).and_then(|rest| rest.strip_prefix('_')).filter(|key| !key.is_empty())?;
+                let mut kv = kv.clone();
+                kv.key = Cow::Owned(stripped.to_string());
+                Some(kv)
+            });
+        Environment { pairs: PairTable::from_pairs(pairs), ..Environment::new() }
+    }
+
+    /// Attaches the source text this environment was parsed from, enabling
+    /// annotated snippets in `error_report()` and `get_entry_with_context()`.
+    pub fn with_source(mut self, source: impl Into<Arc<str>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Captures the current process environment.
+    ///
+    /// Variables that are valid UTF-8 become regular pairs; the rest are kept
+    /// as raw `OsString`s so that `apply_os()` restores them byte-for-byte.
+    pub fn from_process_env_os() -> Environment<'static> {
+        let mut env = Environment::new();
+        for (key, value) in std::env::vars_os() {
+            match (key.into_string(), value.into_string()) {
+                (Ok(key), Ok(value)) => {
+                    env.pairs.insert(KeyValuePair::new_fast(key, Cow::Owned(value), QuoteType::None, false, false));
+                },
+                (key, value) => {
+                    let key = key.map(OsString::from).unwrap_or_else(|k| k);
+                    let value = value.map(OsString::from).unwrap_or_else(|v| v);
+                    Arc::make_mut(&mut env.os_vars).push((key, value));
+                },
+            }
+        }
+        env
+    }
+
+    /// Sets the variables of this environment in the current process as
+    /// `apply()` does, active pairs only and according to
+    /// `override_policy()`, including the non-UTF-8 ones captured by
+    /// `from_process_env_os()`.
+    pub fn apply_os(&self) {
+        self.apply_with(self.policy);
+    }
+
+    /// Sets the active variables of this environment in the current process
+    /// according to its `override_policy()`, returning the keys that were
+    /// set.
+    pub fn apply(&self) -> Vec<&str> {
+        self.apply_with(self.policy)
+    }
+
+    /// Like `apply()`, with `policy` instead of the environment's own.
+    pub fn apply_with(&self, policy: OverridePolicy) -> Vec<&str> {
+        let mut applied = Vec::new();
+        if policy == OverridePolicy::Ignore {
+            return applied;
+        }
+        let keep = |key: &OsStr| policy == OverridePolicy::ProcessEnvWins && std::env::var_os(key).is_some();
+        for kv in self.iter_ordered().filter(|kv| !kv.is_comment) {
+            if !keep(OsStr::new(kv.key.as_ref())) {
+                std::env::set_var(kv.key.as_ref(), kv.value.as_ref());
+                applied.push(kv.key.as_ref());
+            }
+        }
+        for (key, value) in self.os_vars.iter().filter(|(key, _)| !keep(key)) {
+            std::env::set_var(key, value);
+        }
+        applied
+    }
+
+    /// The policy `apply()` uses; `ProcessEnvWins` unless set on the builder.
+    pub fn override_policy(&self) -> OverridePolicy {
+        self.policy
+    }
+
+    pub fn get_os(&self, key: impl AsRef<OsStr>) -> Option<&OsStr> {
+        let key = key.as_ref();
+        if let Some(kv) = key.to_str().and_then(|k| self.pairs.get(k)) {
+            return Some(OsStr::new(kv.value.as_ref()));
+        }
+        self.os_vars.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_os_str())
+    }
+
     pub fn get(&self, key: &str) -> Option<&str> {
         self.pairs.get(key).map(|kv| kv.value.as_ref())
     }
@@ -41,14 +366,129 @@ impl<'a> Environment<'a> {
         self.get(key).unwrap_or(default)
     }
 
+    /// The values of `keys`, in the same order:
+    /// `let [host, port] = env.get_many(["DB_HOST", "DB_PORT"]);`
+    pub fn get_many<const N: usize>(&self, keys: [&str; N]) -> [Option<&str>; N] {
+        keys.map(|key| self.get(key))
+    }
+
+    /// The values of those `keys` that are defined, by key.
+    pub fn get_many_map<'k, I>(&self, keys: I) -> HashMap<&'k str, &str>
+    where
+        I: IntoIterator<Item = &'k str>,
+    {
+        keys.into_iter()
+            .filter_map(|key| Some((key, self.get(key)?)))
+            .collect()
+    }
+
     pub fn get_entry(&self, key: &str) -> Option<&KeyValuePair<'a>> {
         self.pairs.get(key)
     }
+
+    /// `get`, but a miss returns the closest defined key, if any is close
+    /// enough to be a likely typo (`DATABSE_URL` for `DATABASE_URL`), for
+    /// "did you mean" messages. Case differences count as one edit each.
+    pub fn get_suggest(&self, key: &str) -> Result<&str, Option<&str>> {
+        if let Some(value) = self.get(key) {
+            return Ok(value);
+        }
+        let limit = (key.chars().count() / 3).max(1);
+        let suggestion = self.pairs.values()
+            .filter(|kv| !kv.is_comment)
+            .map(|kv| (edit_distance(key, &kv.key), kv.key.as_ref()))
+            .filter(|&(distance, _)| distance <= limit)
+            .min();
+        Err(suggestion.map(|(_, key)| key))
+    }
+
+    /// The entry at byte `offset` of the source and the part of it there,
+    /// e.g. for editor hovers. Needs `track_positions`; inline comments are
+    /// only found when the source is attached (`with_source`).
+    pub fn entry_at(&self, offset: usize) -> Option<Located<'_, 'a>> {
+        locate(self.pairs.values(), self.comments.iter(), self.source.as_deref(), offset)
+    }
+
+    /// Values of commented-out definitions of `key` (`# DATABASE_URL=old`),
+    /// most recent first and without repeats or the active value, e.g. to
+    /// offer previous values when editing.
+    pub fn alternatives(&self, key: &str) -> Vec<&str> {
+        let active = self.pairs.get(key).filter(|kv| !kv.is_comment).map(|kv| kv.value.as_ref());
+        let mut values: Vec<&str> = Vec::new();
+        for value in self.alternatives.get(key).into_iter().flatten().rev() {
+            if Some(value.as_ref()) != active && !values.contains(&value.as_ref()) {
+                values.push(value);
+            }
+        }
+        values
+    }
     
     pub fn iter(&self) -> impl Iterator<Item = &KeyValuePair<'a>> {
         self.pairs.values()
     }
     
+    /// Iterates pairs in the order they appeared in the source.
+    pub fn iter_ordered(&self) -> impl Iterator<Item = &KeyValuePair<'a>> {
+        let mut pairs: Vec<_> = self.pairs.values().collect();
+        pairs.sort_by_key(|kv| kv.index);
+        pairs.into_iter()
+    }
+    
+    /// Active pairs whose `# @rotate-by: YYYY-MM-DD` date is before `today`,
+    /// in source order.
+    pub fn expired(&self, today: Date) -> Vec<&KeyValuePair<'a>> {
+        self.iter_ordered()
+            .filter(|kv| !kv.is_comment && kv.rotate_by().is_some_and(|date| date < today))
+            .collect()
+    }
+
+    pub fn comments(&self) -> &[Comment] {
+        &self.comments
+    }
+    
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats {
+            pairs: self.pairs.len(),
+            comments: self.comments.len(),
+            errors: self.errors.len(),
+            warnings: self.warnings.len(),
+            ..Stats::default()
+        };
+        let mut longest_key_len = 0;
+        // Ordered so ties resolve to the earliest definition
+        for kv in self.iter_ordered() {
+            match kv.quote {
+                QuoteType::None => stats.unquoted += 1,
+                QuoteType::Single => stats.single_quoted += 1,
+                QuoteType::Double => stats.double_quoted += 1,
+            }
+            stats.exported += kv.is_exported as usize;
+            stats.commented += kv.is_comment as usize;
+            stats.total_value_bytes += kv.value.len();
+            if kv.key.len() > longest_key_len {
+                longest_key_len = kv.key.len();
+                stats.longest_key = Some(kv.key.to_string());
+            }
+            if kv.value.len() > stats.longest_value_len {
+                stats.longest_value_len = kv.value.len();
+                stats.longest_value_key = Some(kv.key.to_string());
+            }
+        }
+        stats
+    }
+
+    /// Displays the environment as .env text with keys sorted; the plain
+    /// `Display` impl keeps source order.
+    pub fn display_sorted(&self) -> SortedDisplay<'_, 'a> {
+        SortedDisplay(self)
+    }
+
+    /// Renders as `.env` text according to `options`, e.g. with `export `
+    /// on every pair for shell sourcing.
+    pub fn display_with(&self, options: WriteOptions) -> EnvDisplay<'_, 'a> {
+        EnvDisplay(self, options)
+    }
+    
     pub fn has_errors(&self) -> bool {
         !self.errors.is_empty()
     }
@@ -56,20 +496,163 @@ impl<'a> Environment<'a> {
     pub fn errors(&self) -> &[Error] {
         &self.errors
     }
+
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
     
+    /// Describes every error and warning, one block per diagnostic. When the
+    /// source was retained, each block includes the offending line.
+    pub fn error_report(&self) -> String {
+        let index = self.source.as_deref().map(LineIndex::new);
+        let render = |diagnostic: &Error| match (&self.source, &index) {
+            (Some(source), Some(index)) => diagnostic.render_with(source, index),
+            _ => diagnostic.to_string(),
+        };
+        self.errors.iter()
+            .map(|e| format!("error: {}", render(e)))
+            .chain(self.warnings.iter().map(|w| format!("warning: {}", render(w))))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Returns the entry together with an annotated snippet of its definition.
+    ///
+    /// `None` when the key is missing, or when the source or key positions
+    /// were not retained.
+    pub fn get_entry_with_context(&self, key: &str) -> Option<(&KeyValuePair<'a>, String)> {
+        let kv = self.pairs.get(key)?;
+        let source = self.source.as_deref()?;
+        let span = kv.key_span?;
+        let snippet = LineIndex::new(source).render_snippet(source, &format!("{} is defined here", kv.key), span);
+        Some((kv, snippet))
+    }
+
+    /// Changes that turn `self` into `other`, considering active pairs only.
+    pub fn diff(&self, other: &Environment<'_>) -> Diff {
+        fn active(env: &Environment<'_>) -> BTreeMap<String, String> {
+            env.pairs.values()
+                .filter(|kv| !kv.is_comment)
+                .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+                .collect()
+        }
+        let (old, new) = (active(self), active(other));
+
+        let mut changes = Vec::new();
+        for (key, value) in &old {
+            match new.get(key) {
+                None => changes.push(Change::Removed { key: key.clone(), value: value.clone() }),
+                Some(v) if v != value => changes.push(Change::Changed { key: key.clone(), old: value.clone(), new: v.clone() }),
+                Some(_) => {},
+            }
+        }
+        for (key, value) in new.iter().filter(|(k, _)| !old.contains_key(*k)) {
+            changes.push(Change::Added { key: key.clone(), value: value.clone() });
+        }
+        changes.sort_by(|a, b| a.key().cmp(b.key()));
+        Diff { changes }
+    }
+
+    /// Which active pairs reference which keys through `${VAR}`. With the
+    /// source attached, values are scanned as written, so `\${VAR}` in a
+    /// double-quoted value is not a reference; single-quoted values never
+    /// contain references.
+    pub fn dependency_graph(&self) -> DependencyGraph {
+        let source = self.source.as_deref();
+        let edges = self.pairs.values()
+            .filter(|kv| !kv.is_comment)
+            .map(|kv| {
+                let (text, escapes) = match (source, kv.content_span) {
+                    (Some(source), Some(span)) if source.get(span.range()).is_some() => (&source[span.range()], kv.quote == QuoteType::Double),
+                    _ => (kv.value.as_ref(), false),
+                };
+                let mut deps: Vec<String> = Vec::new();
+                if kv.quote != QuoteType::Single {
+                    for name in references(text, escapes) {
+                        if !deps.iter().any(|d| *d == text[name.clone()]) {
+                            deps.push(text[name].to_string());
+                        }
+                    }
+                }
+                (kv.key.to_string(), deps)
+            })
+            .collect();
+        DependencyGraph::new(edges)
+    }
+
     pub fn to_map(&self) -> HashMap<String, String> {
-        self.pairs.iter()
-            .map(|(k, v)| (k.to_string(), v.value.to_string()))
+        self.pairs.values()
+            .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+            .collect()
+    }
+
+    /// The active variables, including the non-UTF-8 ones, in the shape
+    /// `std::process::Command::envs` takes. Commented-out pairs are left
+    /// out.
+    pub fn to_os_map(&self) -> HashMap<OsString, OsString> {
+        self.pairs.values()
+            .filter(|kv| !kv.is_comment)
+            .map(|kv| (OsString::from(kv.key.as_ref()), OsString::from(kv.value.as_ref())))
+            .chain(self.os_vars.iter().cloned())
             .collect()
     }
 
+    /// A guess at the type of every active value, in source order, to
+    /// start a schema for a file that has none; see
+    /// `schema::InferredType::infer`.
+    pub fn infer_types(&self) -> Vec<TypeGuess> {
+        infer_types(self)
+    }
+
+    /// Active variables (those of `to_os_map()`) too large for `platform`
+    /// to pass to a child process, and the whole block if it is, as
+    /// `Error::PlatformLimit`s in source order; empty if all fit.
+    ///
+    /// Spawning fails at that point with only an OS error (`E2BIG` on
+    /// Unix), so check before `apply()` or `Command::envs`, e.g. with
+    /// `Platform::current()`. The block limit is shared with the inherited
+    /// environment and the command line, which are not counted here.
+    pub fn check_platform_limits(&self, platform: Platform) -> Vec<Error> {
+        check_limits(self, platform)
+    }
+
     pub fn into_owned(self) -> Environment<'static> {
         Environment {
-            pairs: self.pairs.into_iter()
-                .map(|(k, v)| (Cow::Owned(k.into_owned()), v.into_owned()))
-                .collect(),
+            pairs: PairTable::from_pairs(self.pairs.into_pairs().into_iter().map(KeyValuePair::into_owned)),
             comments: self.comments,
             errors: self.errors,
+            warnings: self.warnings,
+            os_vars: self.os_vars,
+            source: self.source,
+            policy: self.policy,
+            metadata: self.metadata,
+            alternatives: Arc::new(Arc::unwrap_or_clone(self.alternatives).into_iter()
+                .map(|(k, values)| (Cow::Owned(k.into_owned()), values.into_iter().map(|v| Cow::Owned(v.into_owned())).collect()))
+                .collect()),
+        }
+    }
+}
+
+/// Appends `other` to `list`, copying `list` only if it is shared and
+/// `other` adds to it.
+fn append<T: Clone>(list: &mut Arc<Vec<T>>, other: Arc<Vec<T>>) {
+    if !other.is_empty() {
+        Arc::make_mut(list).extend(Arc::unwrap_or_clone(other));
+    }
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
         }
     }
+    row[b.len()]
 }
@@ -1,19 +1,68 @@
 use std::fmt;
+use crate::limits::Platform;
+use crate::line_index::LineIndex;
+use crate::types::Span;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Error {
     InvalidUtf8 { offset: usize, reason: String },
-    UnclosedQuote { quote_type: &'static str, offset: usize },
-    InvalidKey { offset: usize, reason: String },
-    ForbiddenWhitespace { location: &'static str, offset: usize },
+    /// `key` names the pair whose value is unclosed; the value itself is never
+    /// included in errors.
+    UnclosedQuote { key: String, quote_type: &'static str, offset: usize },
+    InvalidKey { offset: usize, reason: InvalidKeyReason },
+    ForbiddenWhitespace { key: String, location: &'static str, offset: usize },
     DoubleEquals { offset: usize },
     InvalidBom { offset: usize },
     Expected { offset: usize, expected: &'static str },
     Generic { offset: usize, message: String },
+    DuplicateKey { key: String, first_span: Span, second_span: Span },
+    /// An unquoted value ended at whitespace and `dropped` is the rest of
+    /// the line that was ignored.
+    TruncatedValue { key: String, dropped: Span },
+    ExportForbidden { offset: usize },
+    /// A value of `len` bytes starting at `offset` was cut to `limit` bytes
+    /// (`ParseOptions::max_value_len`).
+    ValueTooLong { key: String, len: usize, limit: usize, offset: usize },
+    /// An allowed but discouraged construct enabled in
+    /// `ParseOptions::deprecations`; `key` names the pair it belongs to.
+    Deprecated { rule: Deprecation, key: String, offset: usize },
+    /// The file can be read by any user; `mode` holds the Unix permission bits.
+    InsecurePermissions { path: String, mode: u32 },
+    /// The content does not match its checksum; `expected` is `None` if no
+    /// checksum was found.
+    ChecksumMismatch { expected: Option<String>, actual: String },
+    /// A variable (`key`) or, with no key, the whole environment block is
+    /// `size` long, over what `platform` accepts when spawning a process;
+    /// see `Environment::check_platform_limits`.
+    PlatformLimit { key: Option<String>, size: usize, limit: usize, platform: Platform },
     Io(String),
 }
 
 impl Error {
+    /// The variant name, e.g. `"UnclosedQuote"`; stable across releases.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::InvalidUtf8 { .. } => "InvalidUtf8",
+            Error::UnclosedQuote { .. } => "UnclosedQuote",
+            Error::InvalidKey { .. } => "InvalidKey",
+            Error::ForbiddenWhitespace { .. } => "ForbiddenWhitespace",
+            Error::DoubleEquals { .. } => "DoubleEquals",
+            Error::InvalidBom { .. } => "InvalidBom",
+            Error::Expected { .. } => "Expected",
+            Error::Generic { .. } => "Generic",
+            Error::DuplicateKey { .. } => "DuplicateKey",
+            Error::TruncatedValue { .. } => "TruncatedValue",
+            Error::ExportForbidden { .. } => "ExportForbidden",
+            Error::ValueTooLong { .. } => "ValueTooLong",
+            Error::Deprecated { .. } => "Deprecated",
+            Error::InsecurePermissions { .. } => "InsecurePermissions",
+            Error::ChecksumMismatch { .. } => "ChecksumMismatch",
+            Error::PlatformLimit { .. } => "PlatformLimit",
+            Error::Io(_) => "Io",
+        }
+    }
+
     pub fn offset(&self) -> usize {
         match self {
             Error::InvalidUtf8 { offset, .. } => *offset,
@@ -24,25 +73,166 @@ impl Error {
             Error::InvalidBom { offset } => *offset,
             Error::Expected { offset, .. } => *offset,
             Error::Generic { offset, .. } => *offset,
-            Error::Io(_) => 0,
+            Error::DuplicateKey { second_span, .. } => second_span.start.offset,
+            Error::TruncatedValue { dropped, .. } => dropped.start.offset,
+            Error::ExportForbidden { offset } => *offset,
+            Error::ValueTooLong { offset, .. } => *offset,
+            Error::Deprecated { offset, .. } => *offset,
+            Error::InsecurePermissions { .. } | Error::ChecksumMismatch { .. } | Error::PlatformLimit { .. } | Error::Io(_) => 0,
         }
     }
 }
 
+impl Error {
+    /// Moves the error `by` bytes later in the source, e.g. when the text it
+    /// was found in is a slice of a larger input. Spans are left alone.
+    pub(crate) fn shift(&mut self, by: usize) {
+        match self {
+            Error::InvalidUtf8 { offset, .. }
+            | Error::UnclosedQuote { offset, .. }
+            | Error::InvalidKey { offset, .. }
+            | Error::ForbiddenWhitespace { offset, .. }
+            | Error::DoubleEquals { offset }
+            | Error::InvalidBom { offset }
+            | Error::Expected { offset, .. }
+            | Error::Generic { offset, .. }
+            | Error::ExportForbidden { offset }
+            | Error::ValueTooLong { offset, .. }
+            | Error::Deprecated { offset, .. } => *offset += by,
+            Error::DuplicateKey { .. }
+            | Error::TruncatedValue { .. }
+            | Error::InsecurePermissions { .. }
+            | Error::ChecksumMismatch { .. }
+            | Error::PlatformLimit { .. }
+            | Error::Io(_) => {},
+        }
+    }
+
+    /// Renders the error with the offending source line and a caret.
+    ///
+    /// The snippet contains source text, so only show it where values may
+    /// be disclosed; `Display` never includes values.
+    pub fn render(&self, source: &str) -> String {
+        self.render_with(source, &LineIndex::new(source))
+    }
+
+    pub fn render_with(&self, source: &str, index: &LineIndex) -> String {
+        if matches!(self, Error::InsecurePermissions { .. } | Error::ChecksumMismatch { .. } | Error::PlatformLimit { .. } | Error::Io(_)) {
+            return self.to_string();
+        }
+        let offset = self.offset();
+        index.render_snippet(source, &self.to_string(), Span::from_offsets(offset, offset))
+    }
+}
+
+impl Error {
+    /// A machine-applicable correction for common mistakes in `source`, the
+    /// text the error was found in: whitespace around `=`, a doubled `=`, a
+    /// key starting with a digit (prefixed with `_`) and an unclosed quote
+    /// (closed at the end of its line). Other errors have none.
+    pub fn fix(&self, source: &str) -> Option<Fix> {
+        self.fix_with(source, &LineIndex::new(source))
+    }
+
+    pub fn fix_with(&self, source: &str, index: &LineIndex) -> Option<Fix> {
+        let bytes = source.as_bytes();
+        let blank_run = |from: usize| from + bytes.get(from..).map_or(0, |rest| rest.iter().take_while(|&&b| b == b' ' || b == b'\t').count());
+        let (range, replacement) = match self {
+            Error::ForbiddenWhitespace { key, offset, location: "between key and equals" } => {
+                let start = offset + key.len();
+                (start..blank_run(start), String::new())
+            },
+            Error::ForbiddenWhitespace { offset, .. } => (*offset..blank_run(*offset), String::new()),
+            Error::DoubleEquals { offset } => (*offset..offset + 1, String::new()),
+            Error::InvalidKey { offset, reason: InvalidKeyReason::StartsWithDigit } => (*offset..*offset, "_".to_string()),
+            Error::UnclosedQuote { quote_type, offset, .. } => {
+                let line = index.line_range(index.position(*offset).line)?;
+                let end = offset + source.get(*offset..line.end)?.trim_end().len();
+                (end..end, if *quote_type == "single" { "'" } else { "\"" }.to_string())
+            },
+            _ => return None,
+        };
+        (range.start <= source.len() && range.end <= source.len()).then(|| Fix {
+            span: index.span(Span::from_offsets(range.start, range.end)),
+            replacement,
+        })
+    }
+}
+
+/// A suggested edit returned by `Error::fix`: replace `span` of the source
+/// with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Fix {
+    pub span: Span,
+    pub replacement: String,
+}
+
+impl Fix {
+    /// `source` with the fix applied.
+    pub fn apply(&self, source: &str) -> String {
+        let mut fixed = source.to_string();
+        fixed.replace_range(self.span.range(), &self.replacement);
+        fixed
+    }
+}
+
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Error::InvalidUtf8 { offset, reason } => write!(f, "Invalid UTF-8 at byte {}: {}", offset, reason),
-            Error::UnclosedQuote { quote_type, offset } => write!(f, "Unclosed {} quote starting at byte {}", quote_type, offset),
+            Error::UnclosedQuote { key, quote_type, offset } => write!(f, "Unclosed {} quote in value of {} starting at byte {}", quote_type, key, offset),
             Error::InvalidKey { offset, reason } => write!(f, "Invalid key at byte {}: {}", offset, reason),
-            Error::ForbiddenWhitespace { location, offset } => write!(f, "Whitespace not allowed {} at byte {}", location, offset),
+            Error::ForbiddenWhitespace { key, location, offset } => write!(f, "Whitespace not allowed {} for {} at byte {}", location, key, offset),
             Error::DoubleEquals { offset } => write!(f, "Double equals sign detected at byte {}. Use quotes: KEY=\"=val\"", offset),
             Error::InvalidBom { offset } => write!(f, "BOM found at invalid position (byte {})", offset),
             Error::Expected { offset, expected } => write!(f, "Expected {} at byte {}", expected, offset),
             Error::Generic { offset, message } => write!(f, "{} at byte {}", message, offset),
+            Error::DuplicateKey { key, first_span, second_span } => write!(f, "Duplicate key '{}' at byte {} shadows earlier definition at byte {}", key, second_span.start.offset, first_span.start.offset),
+            Error::TruncatedValue { key, dropped } => write!(f, "Value of {} truncated at whitespace; text from byte {} is ignored. Did you mean to quote it?", key, dropped.start.offset),
+            Error::ExportForbidden { offset } => write!(f, "'export' prefix is not allowed at byte {}", offset),
+            Error::ValueTooLong { key, len, limit, offset } => write!(f, "Value of {} at byte {} is {} bytes; truncated to {}", key, offset, len, limit),
+            Error::InsecurePermissions { path, mode } => write!(f, "{} is world-readable (mode {:o}); restrict it with chmod 600", path, mode),
+            Error::Deprecated { rule: Deprecation::TrailingContent, key, offset } => write!(f, "Text after the closing quote of {} at byte {} is ignored", key, offset),
+            Error::Deprecated { rule: Deprecation::CommentedPair, key, offset } => write!(f, "Commented-out pair {} at byte {}", key, offset),
+            Error::Deprecated { rule: Deprecation::ExportPrefix, key, offset } => write!(f, "'export' prefix on {} at byte {}", key, offset),
+            Error::ChecksumMismatch { expected: None, .. } => write!(f, "No korni-checksum found; the file may be truncated or unsigned"),
+            Error::ChecksumMismatch { expected: Some(expected), actual } => write!(f, "Checksum mismatch: expected sha256 {}, content has {}", expected, actual),
+            Error::PlatformLimit { key: Some(key), size, limit, platform } => write!(f, "Variable {} is {} {}, over the {} limit of {}", key, size, platform.unit(), platform.name(), limit),
+            Error::PlatformLimit { key: None, size, limit, platform } => write!(f, "Environment block is {} {}, over the {} limit of {}", size, platform.unit(), platform.name(), limit),
             Error::Io(msg) => write!(f, "IO Error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for Error {}
+
+/// Rules reported by `Error::Deprecated`, see `ParseOptions::deprecations`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Deprecation {
+    TrailingContent,
+    CommentedPair,
+    ExportPrefix,
+}
+
+/// Why a key was rejected, as reported by `Error::InvalidKey`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum InvalidKeyReason {
+    /// A non-ASCII character without `ParseOptions::unicode_keys`.
+    NonAscii,
+    /// The line does not start with a key character.
+    NotAKey,
+    StartsWithDigit,
+}
+
+impl fmt::Display for InvalidKeyReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidKeyReason::NonAscii => write!(f, "Non-ASCII character in key (enable unicode_keys to allow it)"),
+            InvalidKeyReason::NotAKey => write!(f, "Line does not start with a valid key"),
+            InvalidKeyReason::StartsWithDigit => write!(f, "Key starts with digit"),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use crate::bufread::BufReadIter;
+use crate::error::Error;
+use crate::types::{Entry, ParseOptions};
+
+/// Follows a file that is only ever appended to (like `tail -f`), returning
+/// the complete entries written since the previous poll.
+///
+/// A trailing line that is not yet terminated, or a quoted value that is
+/// still open, is held back until the rest of it has been written, so every
+/// entry is yielded exactly once and in its final form.
+pub struct FollowParser {
+    entries: BufReadIter<BufReader<File>>,
+}
+
+impl FollowParser {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::open_with_options(path, ParseOptions::default())
+    }
+
+    pub fn open_with_options(path: impl AsRef<Path>, options: ParseOptions) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path)
+            .map_err(|e| Error::Io(format!("Failed to open {}: {}", path.display(), e)))?;
+        Ok(Self { entries: BufReadIter::following(BufReader::new(file), options) })
+    }
+
+    /// Entries completed since the last call; empty if nothing new was
+    /// appended. Content read by earlier polls is never parsed again.
+    pub fn poll(&mut self) -> Vec<Entry<'static>> {
+        self.entries.by_ref().collect()
+    }
+}
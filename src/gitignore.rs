@@ -0,0 +1,107 @@
+//! Enough of git's ignore rules to tell whether an env file would be
+//! committed: `.gitignore` files from the work tree root down to the file,
+//! plus `.git/info/exclude`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::glob::glob_match;
+
+struct Rule {
+    /// Directory (relative to the work tree root) holding the ignore file.
+    base: String,
+    pattern: String,
+    negated: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+impl Rule {
+    fn parse(base: &str, line: &str) -> Option<Self> {
+        let line = line.trim_end_matches('\r');
+        let line = match line.trim_end_matches(' ') {
+            trimmed if trimmed.ends_with('\\') && trimmed.len() < line.len() => &line[..trimmed.len() + 1],
+            trimmed => trimmed,
+        };
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let line = line.strip_prefix('\\').unwrap_or(line);
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        let pattern = line.strip_prefix('/').unwrap_or(line);
+        if pattern.is_empty() {
+            return None;
+        }
+        Some(Rule { base: base.to_string(), pattern: pattern.to_string(), negated, dir_only, anchored })
+    }
+
+    /// `path` is relative to the work tree root.
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(self.base.as_str()).and_then(|p| p.strip_prefix('/')) {
+                Some(p) => p,
+                None => return false,
+            }
+        };
+        if self.anchored {
+            glob_match(&self.pattern, relative)
+        } else {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            glob_match(&self.pattern, name)
+        }
+    }
+}
+
+fn work_tree_root(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find(|dir| dir.join(".git").exists()).map(Path::to_path_buf)
+}
+
+fn read_rules(rules: &mut Vec<Rule>, base: &str, file: &Path) {
+    if let Ok(content) = fs::read_to_string(file) {
+        rules.extend(content.lines().filter_map(|line| Rule::parse(base, line)));
+    }
+}
+
+/// `Some(true)` if git would ignore `path`, `None` if it is not inside a git
+/// work tree.
+pub(crate) fn is_ignored(path: &Path) -> Option<bool> {
+    let path = std::path::absolute(path).ok()?;
+    let root = work_tree_root(path.parent()?)?;
+    let relative = path.strip_prefix(&root).ok()?;
+
+    let components: Vec<String> = relative.components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+
+    let mut rules = Vec::new();
+    read_rules(&mut rules, "", &root.join(".git").join("info").join("exclude"));
+    read_rules(&mut rules, "", &root.join(".gitignore"));
+
+    // A file inside an ignored directory is ignored whatever later rules say
+    for depth in 1..=components.len() {
+        let is_dir = depth < components.len();
+        let sub = components[..depth].join("/");
+        let ignored = rules.iter().rev()
+            .find(|rule| rule.matches(&sub, is_dir))
+            .is_some_and(|rule| !rule.negated);
+        if ignored {
+            return Some(true);
+        }
+        if is_dir {
+            read_rules(&mut rules, &sub, &root.join(&sub).join(".gitignore"));
+        }
+    }
+    Some(false)
+}
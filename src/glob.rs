@@ -0,0 +1,69 @@
+//! Minimal wildcard matching shared by `.gitignore` handling and key filters.
+
+/// Matches `text` against a glob: `?` is any single character and `*` any
+/// run of characters, neither crossing `/`; `**` also crosses `/`, and
+/// `**/` matches zero or more whole directories. `[abc]`, `[a-z]` and
+/// `[!abc]` match one character from (or not from) a set.
+pub(crate) fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+fn matches(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            if rest.first() == Some(&'/') && matches(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| matches(rest, &text[i..]))
+        },
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if matches(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        },
+        Some('?') => text.first().is_some_and(|&c| c != '/') && matches(&pattern[1..], &text[1..]),
+        Some('[') => match (class_match(&pattern[1..], text.first().copied()), text.first()) {
+            (Some((true, len)), Some(_)) => matches(&pattern[1 + len..], &text[1..]),
+            (Some((false, _)), _) | (_, None) => false,
+            // No closing `]`: treat `[` literally
+            (None, Some(&c)) => c == '[' && matches(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => text.first() == Some(&pattern[1]) && matches(&pattern[2..], &text[1..]),
+        Some(&p) => text.first() == Some(&p) && matches(&pattern[1..], &text[1..]),
+    }
+}
+
+/// Matches `c` against the class starting after `[`. Returns whether it
+/// matched and how many pattern characters the class used (including `]`),
+/// or `None` if the class is not closed.
+fn class_match(class: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let negated = matches!(class.first(), Some('!' | '^'));
+    let start = negated as usize;
+    let end = start + 1 + class.get(start + 1..)?.iter().position(|&ch| ch == ']')?;
+    let set = &class[start..end];
+
+    let c = c?;
+    let mut found = false;
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            found |= set[i] <= c && c <= set[i + 2];
+            i += 3;
+        } else {
+            found |= set[i] == c;
+            i += 1;
+        }
+    }
+    Some((found != negated, end + 1))
+}
@@ -0,0 +1,32 @@
+use std::sync::{Arc, OnceLock};
+use crate::env::Environment;
+use crate::loader::Korni;
+
+/// Environment variable naming the profile `global()` loads.
+pub const PROFILE_VAR: &str = "KORNI_PROFILE";
+
+static GLOBAL: OnceLock<Environment<'static>> = OnceLock::new();
+
+/// The process-wide environment, loaded on first use with
+/// `Korni::cascade` from the current directory and the profile named by
+/// `KORNI_PROFILE`, if set. Later calls, from any thread, return the same
+/// environment; files changed afterwards are not reread.
+///
+/// Loading errors never panic: a failure to read a file is recorded in
+/// `errors()` of an otherwise empty environment.
+pub fn global() -> &'static Environment<'static> {
+    GLOBAL.get_or_init(|| {
+        let profile = std::env::var(PROFILE_VAR).ok().filter(|p| !p.is_empty());
+        let dir = std::env::current_dir().unwrap_or_default();
+        Korni::cascade(dir, profile.as_deref()).unwrap_or_else(|e| {
+            let mut env = Environment::new();
+            Arc::make_mut(&mut env.errors).push(e);
+            env
+        })
+    })
+}
+
+/// Looks `key` up in `global()`.
+pub fn var(key: &str) -> Option<&'static str> {
+    global().get(key)
+}
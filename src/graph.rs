@@ -0,0 +1,150 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+/// Which keys reference which through `${VAR}`, as returned by
+/// `Environment::dependency_graph()`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DependencyGraph {
+    /// Every defined key and the names its value references, deduplicated,
+    /// in order of first reference.
+    edges: BTreeMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(edges: BTreeMap<String, Vec<String>>) -> Self {
+        Self { edges }
+    }
+
+    /// Defined keys in sorted order, with the names each one references.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        self.edges.iter().map(|(key, deps)| (key.as_str(), deps.as_slice()))
+    }
+
+    /// Names referenced by `key`'s value; empty if it references none or is
+    /// not defined.
+    pub fn dependencies(&self, key: &str) -> &[String] {
+        self.edges.get(key).map_or(&[], Vec::as_slice)
+    }
+
+    /// Keys whose values reference `key`, sorted.
+    pub fn dependents(&self, key: &str) -> Vec<&str> {
+        self.edges.iter()
+            .filter(|(_, deps)| deps.iter().any(|d| d == key))
+            .map(|(k, _)| k.as_str())
+            .collect()
+    }
+
+    /// Referenced names that no pair defines (e.g. expected from the process
+    /// environment), sorted.
+    pub fn undefined(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self.edges.values()
+            .flatten()
+            .map(String::as_str)
+            .filter(|name| !self.edges.contains_key(*name))
+            .collect();
+        names.into_iter().collect()
+    }
+
+    /// Groups of keys that reference each other in a loop, including keys
+    /// referencing themselves. Each group is sorted, as is the list.
+    pub fn cycles(&self) -> Vec<Vec<&str>> {
+        let mut tarjan = Tarjan { graph: self, index: BTreeMap::new(), low: BTreeMap::new(), stack: Vec::new(), cycles: Vec::new() };
+        for key in self.edges.keys() {
+            if !tarjan.index.contains_key(key.as_str()) {
+                tarjan.visit(key);
+            }
+        }
+        let mut cycles = tarjan.cycles;
+        for cycle in &mut cycles {
+            cycle.sort_unstable();
+        }
+        cycles.sort_unstable();
+        cycles
+    }
+
+    /// Defined keys ordered so every key comes after the keys it references,
+    /// i.e. an order in which they can be resolved. Ties are broken by key
+    /// name. Fails with `cycles()` if there are any.
+    pub fn topological_order(&self) -> Result<Vec<&str>, Vec<Vec<&str>>> {
+        let cycles = self.cycles();
+        if !cycles.is_empty() {
+            return Err(cycles);
+        }
+        fn visit<'g>(graph: &'g DependencyGraph, key: &'g str, done: &mut BTreeSet<&'g str>, order: &mut Vec<&'g str>) {
+            if !done.insert(key) {
+                return;
+            }
+            for dep in graph.dependencies(key) {
+                if graph.edges.contains_key(dep) {
+                    visit(graph, dep, done, order);
+                }
+            }
+            order.push(key);
+        }
+        let mut done = BTreeSet::new();
+        let mut order = Vec::with_capacity(self.edges.len());
+        for key in self.edges.keys() {
+            visit(self, key, &mut done, &mut order);
+        }
+        Ok(order)
+    }
+
+    /// The graph in Graphviz DOT format, with an edge from each key to every
+    /// name it references.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph env {\n");
+        for (key, deps) in &self.edges {
+            if deps.is_empty() {
+                let _ = writeln!(out, "    {:?};", key);
+            }
+            for dep in deps {
+                let _ = writeln!(out, "    {:?} -> {:?};", key, dep);
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Tarjan's strongly connected components, keeping only the cyclic ones.
+struct Tarjan<'g> {
+    graph: &'g DependencyGraph,
+    index: BTreeMap<&'g str, usize>,
+    low: BTreeMap<&'g str, usize>,
+    stack: Vec<&'g str>,
+    cycles: Vec<Vec<&'g str>>,
+}
+
+impl<'g> Tarjan<'g> {
+    fn visit(&mut self, key: &'g str) {
+        let index = self.index.len();
+        self.index.insert(key, index);
+        self.low.insert(key, index);
+        self.stack.push(key);
+
+        for dep in self.graph.dependencies(key) {
+            let Some((dep, _)) = self.graph.edges.get_key_value(dep.as_str()) else { continue };
+            let dep = dep.as_str();
+            match self.index.get(dep) {
+                None => {
+                    self.visit(dep);
+                    let low = self.low[key].min(self.low[dep]);
+                    self.low.insert(key, low);
+                },
+                Some(&dep_index) if self.stack.contains(&dep) => {
+                    let low = self.low[key].min(dep_index);
+                    self.low.insert(key, low);
+                },
+                Some(_) => {},
+            }
+        }
+
+        if self.low[key] == index {
+            let at = self.stack.iter().rposition(|&k| k == key).unwrap_or(0);
+            let component = self.stack.split_off(at);
+            if component.len() > 1 || self.graph.dependencies(key).iter().any(|d| d == key) {
+                self.cycles.push(component);
+            }
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use crate::env::Environment;
+use crate::schema::{parse_bool, KeySpec, Schema, ValueType};
+use crate::types::Span;
+
+/// What a value looks like, as guessed by `Environment::infer_types`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum InferredType {
+    Integer,
+    Float,
+    Bool,
+    Url,
+    /// A file system path such as `/var/log`, `./data` or `C:\temp`.
+    Path,
+    /// A JSON object or array.
+    Json,
+    /// Standard base64 of at least 16 characters.
+    Base64,
+    /// A number with a unit, or several: `30s`, `500ms`, `1h30m`.
+    Duration,
+    String,
+}
+
+impl InferredType {
+    /// Guesses the type of `value`, with a confidence from 0 to 1. `1` and
+    /// `0` are integers but might be flags, and something that parses as
+    /// nothing else is a string. Empty values are strings with confidence 0.
+    pub fn infer(value: &str) -> (InferredType, f64) {
+        if value.is_empty() {
+            return (InferredType::String, 0.0);
+        }
+        if value == "0" || value == "1" {
+            return (InferredType::Integer, 0.6);
+        }
+        if ValueType::Integer.matches(value) {
+            // `0755` or `02134` are more often codes than numbers
+            let leading_zero = value.trim_start_matches(['-', '+']).starts_with('0') && value.len() > 1;
+            return (InferredType::Integer, if leading_zero { 0.5 } else { 1.0 });
+        }
+        if let Some(flag) = parse_bool(value) {
+            let word = if flag { "true" } else { "false" };
+            return (InferredType::Bool, if value.eq_ignore_ascii_case(word) { 1.0 } else { 0.9 });
+        }
+        if value.bytes().all(|b| b.is_ascii_digit() || matches!(b, b'.' | b'-' | b'+' | b'e' | b'E'))
+            && value.bytes().any(|b| b.is_ascii_digit())
+            && ValueType::Float.matches(value)
+        {
+            return (InferredType::Float, 1.0);
+        }
+        if is_duration(value) {
+            return (InferredType::Duration, 0.9);
+        }
+        if ValueType::Url.matches(value) && !value.contains(char::is_whitespace) {
+            return (InferredType::Url, 1.0);
+        }
+        if value.starts_with(['{', '[']) && is_json(value) {
+            return (InferredType::Json, 1.0);
+        }
+        if let Some(confidence) = path_confidence(value) {
+            return (InferredType::Path, confidence);
+        }
+        if let Some(confidence) = base64_confidence(value) {
+            return (InferredType::Base64, confidence);
+        }
+        (InferredType::String, 1.0)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InferredType::Integer => "integer",
+            InferredType::Float => "float",
+            InferredType::Bool => "bool",
+            InferredType::Url => "URL",
+            InferredType::Path => "path",
+            InferredType::Json => "JSON",
+            InferredType::Base64 => "base64",
+            InferredType::Duration => "duration",
+            InferredType::String => "string",
+        }
+    }
+
+    /// The schema type checking values of this type; types a schema cannot
+    /// express are `ValueType::String`.
+    pub fn value_type(self) -> ValueType {
+        match self {
+            InferredType::Integer => ValueType::Integer,
+            InferredType::Float => ValueType::Float,
+            InferredType::Bool => ValueType::Bool,
+            InferredType::Url => ValueType::Url,
+            InferredType::Path | InferredType::Json | InferredType::Base64 | InferredType::Duration | InferredType::String => ValueType::String,
+        }
+    }
+}
+
+/// The type guessed for one key, as returned by `Environment::infer_types`.
+/// The value itself is not included.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeGuess {
+    pub key: String,
+    pub inferred: InferredType,
+    /// From 0 (a guess) to 1 (the value can only be read this way).
+    pub confidence: f64,
+    pub value_span: Option<Span>,
+}
+
+/// See `Environment::infer_types`.
+pub(crate) fn infer_types(env: &Environment<'_>) -> Vec<TypeGuess> {
+    env.iter_ordered()
+        .filter(|kv| !kv.is_comment)
+        .map(|kv| {
+            let (inferred, confidence) = InferredType::infer(&kv.value);
+            TypeGuess { key: kv.key.to_string(), inferred, confidence, value_span: kv.value_span }
+        })
+        .collect()
+}
+
+/// Schema types in the order `infer_schema` prefers them.
+const SPECIFICITY: &[ValueType] = &[ValueType::Integer, ValueType::Bool, ValueType::Float, ValueType::Url];
+
+/// See `Schema::infer_from_samples`.
+pub(crate) fn infer_schema(envs: &[Environment<'_>]) -> Schema {
+    // Per key: how many samples define it, and the types all its non-empty
+    // values fit, `None` before the first
+    let mut keys: Vec<(String, usize, Option<Vec<ValueType>>)> = Vec::new();
+    for env in envs {
+        for kv in env.iter_ordered().filter(|kv| !kv.is_comment) {
+            let index = match keys.iter().position(|(key, ..)| *key == kv.key) {
+                Some(index) => index,
+                None => {
+                    keys.push((kv.key.to_string(), 0, None));
+                    keys.len() - 1
+                },
+            };
+            let (_, seen, fits) = &mut keys[index];
+            *seen += 1;
+            if !kv.value.is_empty() {
+                let candidates = candidate_types(&kv.value);
+                fits.get_or_insert_with(|| SPECIFICITY.to_vec()).retain(|value_type| candidates.contains(value_type));
+            }
+        }
+    }
+    keys.into_iter().fold(Schema::new(), |schema, (key, seen, fits)| {
+        let mut spec = KeySpec::new(fits.and_then(|fits| fits.first().copied()).unwrap_or(ValueType::String));
+        spec.required = seen == envs.len();
+        schema.key(&key, spec)
+    })
+}
+
+/// The schema types of `compatible_types(value)`.
+fn candidate_types(value: &str) -> Vec<ValueType> {
+    let mut candidates: Vec<ValueType> = Vec::new();
+    for value_type in compatible_types(value).into_iter().map(InferredType::value_type) {
+        if !candidates.contains(&value_type) {
+            candidates.push(value_type);
+        }
+    }
+    candidates
+}
+
+/// The inferred types a value read as one of them would fit, for telling
+/// whether values from different files disagree: integers are floats too,
+/// and `0` and `1` flags as well.
+pub(crate) fn compatible_types(value: &str) -> Vec<InferredType> {
+    match InferredType::infer(value).0 {
+        InferredType::Integer if value == "0" || value == "1" => vec![InferredType::Integer, InferredType::Bool, InferredType::Float],
+        InferredType::Integer => vec![InferredType::Integer, InferredType::Float],
+        inferred => vec![inferred],
+    }
+}
+
+const DURATION_UNITS: &[&str] = &["ns", "us", "µs", "ms", "s", "m", "h", "d", "w"];
+
+/// One or more runs of digits (with an optional fraction) each followed by
+/// a unit, longest unit first so `500ms` is not `500m` and `s`.
+fn is_duration(value: &str) -> bool {
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+        let number = &rest[..digits];
+        if digits == 0 || number.starts_with('.') || number.ends_with('.') || number.matches('.').count() > 1 {
+            return false;
+        }
+        rest = &rest[digits..];
+        let Some(unit) = DURATION_UNITS.iter().filter(|unit| rest.starts_with(*unit)).max_by_key(|unit| unit.len()) else {
+            return false;
+        };
+        rest = &rest[unit.len()..];
+    }
+    true
+}
+
+fn path_confidence(value: &str) -> Option<f64> {
+    if value.contains(['\n', '\r']) {
+        return None;
+    }
+    let bytes = value.as_bytes();
+    let drive = bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && matches!(bytes[2], b'\\' | b'/');
+    if value.starts_with(['/', '~']) || value.starts_with("./") || value.starts_with("../") || value.starts_with(".\\") || drive {
+        Some(if value.contains(char::is_whitespace) { 0.6 } else { 0.9 })
+    } else if value.contains('/') && !value.contains(char::is_whitespace) {
+        Some(0.5)
+    } else {
+        None
+    }
+}
+
+/// Standard base64 with its padding, mixing letter cases and digits as
+/// encoded bytes do; words and identifiers rarely pass.
+fn base64_confidence(value: &str) -> Option<f64> {
+    let body = value.trim_end_matches('=');
+    let padding = value.len() - body.len();
+    let alphabet = body.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'/');
+    if value.len() < 16 || !value.len().is_multiple_of(4) || padding > 2 || !alphabet {
+        return None;
+    }
+    let mixed = body.bytes().any(|b| b.is_ascii_uppercase())
+        && body.bytes().any(|b| b.is_ascii_lowercase())
+        && body.bytes().any(|b| b.is_ascii_digit());
+    match (mixed, padding > 0 || body.contains(['+', '/'])) {
+        (true, true) => Some(0.9),
+        (true, false) => Some(0.7),
+        (false, _) => None,
+    }
+}
+
+/// Whether `value` is one JSON value, per RFC 8259.
+fn is_json(value: &str) -> bool {
+    let mut json = Json { bytes: value.as_bytes(), at: 0, depth: 0 };
+    json.value() && {
+        json.whitespace();
+        json.at == json.bytes.len()
+    }
+}
+
+struct Json<'s> {
+    bytes: &'s [u8],
+    at: usize,
+    depth: usize,
+}
+
+impl Json<'_> {
+    fn whitespace(&mut self) {
+        while self.bytes.get(self.at).is_some_and(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        self.whitespace();
+        let found = self.bytes.get(self.at) == Some(&b);
+        self.at += found as usize;
+        found
+    }
+
+    fn value(&mut self) -> bool {
+        self.whitespace();
+        match self.bytes.get(self.at) {
+            Some(b'{') => self.nested(b'}', |json| json.string() && json.eat(b':') && json.value()),
+            Some(b'[') => self.nested(b']', Json::value),
+            Some(b'"') => self.string(),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(_) => self.number(),
+            None => false,
+        }
+    }
+
+    /// An object or array, whose members `member` reads.
+    fn nested(&mut self, close: u8, member: fn(&mut Self) -> bool) -> bool {
+        self.depth += 1;
+        self.at += 1;
+        if self.depth > 64 {
+            return false;
+        }
+        let ok = self.eat(close) || loop {
+            if !member(self) {
+                break false;
+            }
+            if self.eat(close) {
+                break true;
+            }
+            if !self.eat(b',') {
+                break false;
+            }
+        };
+        self.depth -= 1;
+        ok
+    }
+
+    fn string(&mut self) -> bool {
+        if !self.eat(b'"') {
+            return false;
+        }
+        while let Some(&b) = self.bytes.get(self.at) {
+            self.at += 1;
+            match b {
+                b'"' => return true,
+                b'\\' => match self.bytes.get(self.at) {
+                    Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => self.at += 1,
+                    Some(b'u') if self.bytes.get(self.at + 1..self.at + 5).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) => self.at += 5,
+                    _ => return false,
+                },
+                0..=0x1f => return false,
+                _ => {},
+            }
+        }
+        false
+    }
+
+    fn literal(&mut self, word: &str) -> bool {
+        let found = self.bytes[self.at..].starts_with(word.as_bytes());
+        self.at += if found { word.len() } else { 0 };
+        found
+    }
+
+    fn number(&mut self) -> bool {
+        let digits = |json: &mut Self| {
+            let from = json.at;
+            while json.bytes.get(json.at).is_some_and(u8::is_ascii_digit) {
+                json.at += 1;
+            }
+            json.at > from
+        };
+        self.at += (self.bytes.get(self.at) == Some(&b'-')) as usize;
+        let int_start = self.at;
+        if !digits(self) || (self.bytes[int_start] == b'0' && self.at - int_start > 1) {
+            return false;
+        }
+        if self.bytes.get(self.at) == Some(&b'.') {
+            self.at += 1;
+            if !digits(self) {
+                return false;
+            }
+        }
+        if matches!(self.bytes.get(self.at), Some(b'e' | b'E')) {
+            self.at += 1;
+            self.at += matches!(self.bytes.get(self.at), Some(b'+' | b'-')) as usize;
+            if !digits(self) {
+                return false;
+            }
+        }
+        true
+    }
+}
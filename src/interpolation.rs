@@ -0,0 +1,29 @@
+//! Finding `${VAR}` references in values. korni does not expand them; this
+//! is what rename and dependency tooling needs to know where they are.
+
+use std::ops::Range;
+
+/// Byte ranges of the names in `${NAME}` references in `text`, including
+/// shell-style forms like `${NAME:-default}`. With `escapes`, a `$` after a
+/// backslash (as in double-quoted source text) does not start a reference.
+pub(crate) fn references(text: &str, escapes: bool) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if escapes => i += 2,
+            b'$' if bytes.get(i + 1) == Some(&b'{') => {
+                let start = i + 2;
+                let len = text[start..].find(|c| !crate::spec::is_key_char(c, true)).unwrap_or(text.len() - start);
+                let end = start + len;
+                if len > 0 && matches!(bytes.get(end), Some(b'}' | b':' | b'-' | b'?' | b'+')) {
+                    found.push(start..end);
+                }
+                i = end;
+            },
+            _ => i += 1,
+        }
+    }
+    found
+}
@@ -3,17 +3,97 @@ mod types;
 mod parser;
 mod env;
 mod loader;
+mod line_index;
+mod writer;
+mod bufread;
+mod follow;
+mod date;
+mod document;
+mod diff;
+mod audit_log;
+mod preview;
+mod metadata;
+mod locate;
+mod table;
+mod snapshot;
+mod codegen;
+mod graph;
+mod tracked;
+mod metrics;
+mod global;
+mod glob;
+mod gitignore;
+mod interpolation;
+mod infer;
+mod limits;
+pub mod audit;
+pub mod checksum;
+pub mod drift;
+pub mod lint;
+pub mod schema;
+pub mod spec;
+#[cfg(feature = "fuzz-compat")]
+pub mod compat;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+#[cfg(feature = "test-util")]
+pub mod test;
 
-pub use error::Error;
-pub use types::{Entry, KeyValuePair, ParseOptions, QuoteType, Span, Position};
-pub use env::Environment;
-pub use parser::{Parser, EnvIterator};
+pub use error::{Deprecation, Error, Fix, InvalidKeyReason};
+pub use types::{Annotation, Comment, Entry, EntriesExt, KeyValuePair, Occurrence, ParseOptions, Deprecations, QuoteType, Span, Position};
+pub use env::{Environment, FrozenEnvironment, OverridePolicy, Stats};
+pub use parser::{BorrowedPair, BorrowedPairs, Checkpoint, Parser, EnvIterator, Visitor};
+pub use bufread::BufReadIter;
+pub use follow::FollowParser;
+pub use line_index::LineIndex;
+pub use date::Date;
+pub use document::{Document, Edit, KeyOccurrence, OccurrenceKind, SaveOptions, Section};
+pub use diff::{Change, Diff, REDACTED};
+pub use audit_log::AuditLog;
+pub use preview::{preview, PreviewEntry};
+pub use metadata::Metadata;
+pub use limits::Platform;
+pub use snapshot::{snapshot, snapshot_to_file};
+pub use locate::{Located, Part};
+pub use graph::DependencyGraph;
+pub use tracked::{Access, AccessReport, TrackedEnvironment};
+pub use metrics::{ParseMetrics, ParseObserver};
+pub use global::{global, var, PROFILE_VAR};
+pub use writer::{encode_value, minimal_quote, CommentPolicy, EnvDisplay, ExportPolicy, Fold, SortedDisplay, WriteOptions};
 pub use loader::{Korni, KorniBuilder, OwnedKorniBuilder};
+pub use spec::{validate_key, validate_key_with, validate_value_for};
 
-pub fn parse(input: &str) -> Vec<Entry> {
+pub fn parse(input: &str) -> Vec<Entry<'_>> {
     Parser::new(input).parse()
 }
 
-pub fn parse_with_options(input: &str, options: ParseOptions) -> Vec<Entry> {
+pub fn parse_with_options(input: &str, options: ParseOptions) -> Vec<Entry<'_>> {
     Parser::with_options(input, options).parse()
 }
+
+/// Parses `input` and returns the number of entries, without collecting
+/// them. Meant for micro-benchmarks: the result depends on every entry, so
+/// the work cannot be optimized away behind `black_box`.
+pub fn parse_entries_count(input: &str) -> usize {
+    Parser::new(input).iter().count()
+}
+
+/// Finds the first definition of `key` without parsing the rest of the input.
+pub fn find_key<'a>(input: &'a str, key: &str) -> Option<KeyValuePair<'a>> {
+    find_key_with(input, key, Occurrence::First)
+}
+
+pub fn find_key_with<'a>(input: &'a str, key: &str, occurrence: Occurrence) -> Option<KeyValuePair<'a>> {
+    let mut found = None;
+    for entry in Parser::new(input).iter() {
+        if let Entry::Pair(kv) = entry {
+            if kv.key == key {
+                found = Some(*kv);
+                if occurrence == Occurrence::First {
+                    break;
+                }
+            }
+        }
+    }
+    found
+}
@@ -0,0 +1,103 @@
+use std::ffi::OsStr;
+use crate::env::Environment;
+use crate::error::Error;
+
+/// An operating system whose limits on environment blocks
+/// `Environment::check_platform_limits` checks against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Platform {
+    /// A variable, `KEY=value`, holds at most 32,767 UTF-16 characters.
+    Windows,
+    /// A variable, `KEY=value` and its terminating NUL, holds at most
+    /// `MAX_ARG_STRLEN` (128 KiB) bytes, and the block at most the usual
+    /// `ARG_MAX` of 2 MiB.
+    Linux,
+    /// The block holds at most `ARG_MAX`, 1 MiB.
+    MacOs,
+}
+
+impl Platform {
+    /// The platform this crate was compiled for, `None` on others.
+    pub fn current() -> Option<Platform> {
+        if cfg!(windows) {
+            Some(Platform::Windows)
+        } else if cfg!(target_os = "linux") {
+            Some(Platform::Linux)
+        } else if cfg!(target_os = "macos") {
+            Some(Platform::MacOs)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Windows => "Windows",
+            Platform::Linux => "Linux",
+            Platform::MacOs => "macOS",
+        }
+    }
+
+    /// The largest single variable, measured as `size` measures it.
+    pub fn variable_limit(self) -> Option<usize> {
+        match self {
+            Platform::Windows => Some(32_767),
+            Platform::Linux => Some(128 * 1024),
+            Platform::MacOs => None,
+        }
+    }
+
+    /// The largest environment block, measured as `block_size` measures it.
+    pub fn block_limit(self) -> Option<usize> {
+        match self {
+            Platform::Windows => None,
+            Platform::Linux => Some(2 * 1024 * 1024),
+            Platform::MacOs => Some(1024 * 1024),
+        }
+    }
+
+    /// What a variable counts against `variable_limit()`: UTF-16
+    /// characters of `KEY=value` on Windows, bytes of `KEY=value` and its
+    /// NUL elsewhere.
+    pub fn size(self, key: &OsStr, value: &OsStr) -> usize {
+        match self {
+            Platform::Windows => key.to_string_lossy().encode_utf16().count() + 1 + value.to_string_lossy().encode_utf16().count(),
+            Platform::Linux | Platform::MacOs => key.as_encoded_bytes().len() + 1 + value.as_encoded_bytes().len() + 1,
+        }
+    }
+
+    /// What a variable counts against `block_limit()`: its `size` plus the
+    /// pointer to it that the kernel also keeps.
+    pub fn block_size(self, key: &OsStr, value: &OsStr) -> usize {
+        self.size(key, value) + std::mem::size_of::<usize>()
+    }
+
+    pub(crate) fn unit(self) -> &'static str {
+        match self {
+            Platform::Windows => "characters",
+            Platform::Linux | Platform::MacOs => "bytes",
+        }
+    }
+}
+
+/// See `Environment::check_platform_limits`.
+pub(crate) fn check_limits(env: &Environment<'_>, platform: Platform) -> Vec<Error> {
+    let vars = env.iter_ordered()
+        .filter(|kv| !kv.is_comment)
+        .map(|kv| (OsStr::new(kv.key.as_ref()), OsStr::new(kv.value.as_ref())))
+        .chain(env.os_vars.iter().map(|(key, value)| (key.as_os_str(), value.as_os_str())));
+    let mut errors = Vec::new();
+    let mut block = 0;
+    for (key, value) in vars {
+        let size = platform.size(key, value);
+        if let Some(limit) = platform.variable_limit().filter(|&limit| size > limit) {
+            errors.push(Error::PlatformLimit { key: Some(key.to_string_lossy().into_owned()), size, limit, platform });
+        }
+        block += platform.block_size(key, value);
+    }
+    if let Some(limit) = platform.block_limit().filter(|&limit| block > limit) {
+        errors.push(Error::PlatformLimit { key: None, size: block, limit, platform });
+    }
+    errors
+}
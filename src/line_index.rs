@@ -0,0 +1,99 @@
+use crate::types::{Position, Span};
+
+/// Maps byte offsets to 0-indexed line/column positions and back.
+///
+/// Columns are byte-based, matching `Position::col`. Use `utf16_col` when
+/// talking to clients that count UTF-16 code units (e.g. LSP).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(source: &str) -> Self {
+        let mut line_starts = Vec::with_capacity(source.len() / 32 + 1);
+        line_starts.push(0);
+        line_starts.extend(
+            source.bytes()
+                .enumerate()
+                .filter(|&(_, b)| b == b'\n')
+                .map(|(i, _)| i + 1),
+        );
+        Self { line_starts, len: source.len() }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Offsets past the end of the source are clamped to its length.
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        Position { line, col: offset - self.line_starts[line], offset }
+    }
+
+    pub fn span(&self, span: Span) -> Span {
+        Span::new(self.position(span.start.offset), self.position(span.end.offset))
+    }
+
+    /// Returns `None` when the line does not exist or the column runs past it.
+    pub fn offset(&self, line: usize, col: usize) -> Option<usize> {
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+        let offset = start + col;
+        (offset <= end).then_some(offset)
+    }
+
+    /// Byte range of a line, excluding its line terminator.
+    pub fn line_range(&self, line: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line)?;
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) => next - 1,
+            None => self.len,
+        };
+        Some(start..end)
+    }
+
+    pub fn line_text<'s>(&self, source: &'s str, line: usize) -> Option<&'s str> {
+        let text = source.get(self.line_range(line)?)?;
+        Some(text.strip_suffix('\r').unwrap_or(text))
+    }
+
+    /// Column of `offset` counted in UTF-16 code units.
+    pub fn utf16_col(&self, source: &str, offset: usize) -> usize {
+        let pos = self.position(offset);
+        let start = self.line_starts[pos.line];
+        source.get(start..pos.offset)
+            .map_or(pos.col, |prefix| prefix.encode_utf16().count())
+    }
+
+    /// Renders `message` followed by the line containing `span` and a marker
+    /// under the spanned text (clipped to that line).
+    pub(crate) fn render_snippet(&self, source: &str, message: &str, span: Span) -> String {
+        let pos = self.position(span.start.offset);
+        let line = self.line_text(source, pos.line).unwrap_or("");
+        let col_of = |byte_col: usize| line.get(..byte_col).map_or(byte_col, |prefix| prefix.chars().count());
+        let caret_col = col_of(pos.col);
+        let end_col = col_of((span.end.offset.saturating_sub(span.start.offset) + pos.col).min(line.len()));
+        let width = end_col.saturating_sub(caret_col).max(1);
+        let gutter = (pos.line + 1).to_string();
+        let pad = " ".repeat(gutter.len());
+        format!(
+            "{message}\n{pad}--> {}:{}\n{pad} |\n{gutter} | {line}\n{pad} | {}{}",
+            pos.line + 1,
+            pos.col + 1,
+            " ".repeat(caret_col),
+            "^".repeat(width),
+        )
+    }
+
+    pub(crate) fn resolve(&self, pos: &mut Position) {
+        *pos = self.position(pos.offset);
+    }
+
+    pub(crate) fn resolve_span(&self, span: &mut Span) {
+        *span = self.span(*span);
+    }
+}
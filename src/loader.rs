@@ -1,17 +1,18 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::Read;
-use crate::{Environment, ParseOptions, Error};
+use std::sync::Arc;
+use crate::{Deprecations, Entry, Environment, OverridePolicy, ParseObserver, ParseOptions, Parser, Error};
 
 pub struct Korni;
 
 impl Korni {
     #[allow(clippy::should_implement_trait)]
-    pub fn from_str(input: &str) -> KorniBuilder {
+    pub fn from_str(input: &str) -> KorniBuilder<'_> {
         KorniBuilder::new(input)
     }
     
-    pub fn from_bytes(input: &[u8]) -> KorniBuilder {
+    pub fn from_bytes(input: &[u8]) -> KorniBuilder<'_> {
         KorniBuilder::from_bytes(input)
     }
     
@@ -23,6 +24,28 @@ impl Korni {
         OwnedKorniBuilder::from_reader(reader)
     }
 
+    /// Loads the files of a profile from `dir`, each overriding the ones
+    /// before it: `.env`, `.env.local`, then with a profile `.env.<profile>`
+    /// and `.env.<profile>.local`. Missing files are skipped; parse errors
+    /// and warnings of every file are collected on the result.
+    pub fn cascade(dir: impl AsRef<Path>, profile: Option<&str>) -> Result<Environment<'static>, Error> {
+        let dir = dir.as_ref();
+        let mut names = vec![".env".to_string(), ".env.local".to_string()];
+        if let Some(profile) = profile {
+            names.push(format!(".env.{}", profile));
+            names.push(format!(".env.{}.local", profile));
+        }
+
+        let mut env = Environment::new();
+        for name in names {
+            let path = dir.join(name);
+            if path.is_file() {
+                env.extend(OwnedKorniBuilder::from_file(path).parse()?);
+            }
+        }
+        Ok(env)
+    }
+
     pub fn find_file(filename: &str) -> Result<OwnedKorniBuilder, Error> {
         let current = std::env::current_dir()
             .map_err(|e| Error::Io(format!("Failed to get current directory: {}", e)))?;
@@ -52,6 +75,10 @@ enum Source<'a> {
 pub struct KorniBuilder<'a> {
     source: Source<'a>,
     options: ParseOptions,
+    retain_source: bool,
+    observer: Option<Arc<dyn ParseObserver + Send + Sync>>,
+    policy: OverridePolicy,
+    verify_checksum: bool,
 }
 
 impl<'a> KorniBuilder<'a> {
@@ -59,6 +86,10 @@ impl<'a> KorniBuilder<'a> {
         Self {
             source: Source::Str(source_str),
             options: ParseOptions::default(),
+            retain_source: false,
+            observer: None,
+            policy: OverridePolicy::default(),
+            verify_checksum: false,
         }
     }
 
@@ -66,6 +97,10 @@ impl<'a> KorniBuilder<'a> {
         Self {
             source: Source::Bytes(bytes),
             options: ParseOptions::default(),
+            retain_source: false,
+            observer: None,
+            policy: OverridePolicy::default(),
+            verify_checksum: false,
         }
     }
 
@@ -78,6 +113,91 @@ impl<'a> KorniBuilder<'a> {
         self.options.track_positions = true;
         self
     }
+
+    pub fn warn_duplicate_keys(mut self) -> Self {
+        self.options.warn_duplicate_keys = true;
+        self
+    }
+
+    pub fn warn_truncated_values(mut self) -> Self {
+        self.options.warn_truncated_values = true;
+        self
+    }
+
+    pub fn forbid_export(mut self) -> Self {
+        self.options.forbid_export = true;
+        self
+    }
+
+    pub fn comment_chars(mut self, chars: &'static [u8]) -> Self {
+        self.options.comment_chars = chars;
+        self
+    }
+
+    pub fn unicode_keys(mut self) -> Self {
+        self.options.unicode_keys = true;
+        self
+    }
+
+    pub fn unquoted_to_eol(mut self) -> Self {
+        self.options.unquoted_to_eol = true;
+        self
+    }
+
+    pub fn trim_quoted_values(mut self) -> Self {
+        self.options.trim_quoted_values = true;
+        self
+    }
+
+    pub fn normalize_newlines(mut self) -> Self {
+        self.options.normalize_newlines = true;
+        self
+    }
+
+    pub fn strip_trailing_newline(mut self) -> Self {
+        self.options.strip_trailing_newline = true;
+        self
+    }
+
+    /// Cut values longer than `limit` bytes, e.g. when previewing untrusted
+    /// uploads; see `ParseOptions::max_value_len`.
+    pub fn max_value_len(mut self, limit: usize) -> Self {
+        self.options.max_value_len = Some(limit);
+        self
+    }
+
+    /// Warn about the discouraged constructs enabled in `rules`, e.g.
+    /// `Deprecations::ALL`.
+    pub fn warn_deprecated(mut self, rules: Deprecations) -> Self {
+        self.options.deprecations = rules;
+        self
+    }
+
+    /// Keeps a copy of the source on the `Environment` for later diagnostics.
+    pub fn retain_source(mut self) -> Self {
+        self.retain_source = true;
+        self
+    }
+
+    /// Reports the parse's `ParseMetrics` to `observer`.
+    pub fn observer(mut self, observer: Arc<dyn ParseObserver + Send + Sync>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// How `Environment::apply()` treats variables already set in the
+    /// process; `ProcessEnvWins` by default.
+    pub fn override_policy(mut self, policy: OverridePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Makes `parse` fail with `Error::ChecksumMismatch` unless the input
+    /// ends with a valid `# korni-checksum:` line (see `checksum::sign`).
+    pub fn verify_checksum(mut self) -> Self {
+        self.verify_checksum = true;
+        self
+    }
     
     pub fn parse(self) -> Result<Environment<'a>, Error> {
         let input = match self.source {
@@ -88,8 +208,14 @@ impl<'a> KorniBuilder<'a> {
             })?,
         };
         
-        let entries = crate::parse_with_options(input, self.options);
-        Ok(Environment::from_entries(entries))
+        if self.verify_checksum {
+            crate::checksum::verify(input)?;
+        }
+        let entries = parse_observed(input, self.options, self.observer.as_deref());
+        let mut env = Environment::from_entries(entries);
+        env.policy = self.policy;
+        env.read_metadata(input);
+        Ok(if self.retain_source { env.with_source(input) } else { env })
     }
 }
 
@@ -97,6 +223,10 @@ pub struct OwnedKorniBuilder {
     path: Option<PathBuf>,
     reader: Option<Box<dyn Read>>,
     options: ParseOptions,
+    retain_source: bool,
+    observer: Option<Arc<dyn ParseObserver + Send + Sync>>,
+    policy: OverridePolicy,
+    verify_checksum: bool,
 }
 
 impl OwnedKorniBuilder {
@@ -105,6 +235,10 @@ impl OwnedKorniBuilder {
             path: Some(path.into()),
             reader: None,
             options: ParseOptions::default(),
+            retain_source: false,
+            observer: None,
+            policy: OverridePolicy::default(),
+            verify_checksum: false,
         }
     }
 
@@ -113,6 +247,10 @@ impl OwnedKorniBuilder {
             path: None,
             reader: Some(Box::new(reader)),
             options: ParseOptions::default(),
+            retain_source: false,
+            observer: None,
+            policy: OverridePolicy::default(),
+            verify_checksum: false,
         }
     }
 
@@ -125,11 +263,112 @@ impl OwnedKorniBuilder {
         self.options.track_positions = true;
         self
     }
+
+    pub fn warn_duplicate_keys(mut self) -> Self {
+        self.options.warn_duplicate_keys = true;
+        self
+    }
+
+    pub fn warn_truncated_values(mut self) -> Self {
+        self.options.warn_truncated_values = true;
+        self
+    }
+
+    pub fn forbid_export(mut self) -> Self {
+        self.options.forbid_export = true;
+        self
+    }
+
+    pub fn comment_chars(mut self, chars: &'static [u8]) -> Self {
+        self.options.comment_chars = chars;
+        self
+    }
+
+    pub fn unicode_keys(mut self) -> Self {
+        self.options.unicode_keys = true;
+        self
+    }
+
+    pub fn unquoted_to_eol(mut self) -> Self {
+        self.options.unquoted_to_eol = true;
+        self
+    }
+
+    pub fn trim_quoted_values(mut self) -> Self {
+        self.options.trim_quoted_values = true;
+        self
+    }
+
+    pub fn normalize_newlines(mut self) -> Self {
+        self.options.normalize_newlines = true;
+        self
+    }
+
+    pub fn strip_trailing_newline(mut self) -> Self {
+        self.options.strip_trailing_newline = true;
+        self
+    }
+
+    /// Cut values longer than `limit` bytes, e.g. when previewing untrusted
+    /// uploads; see `ParseOptions::max_value_len`.
+    pub fn max_value_len(mut self, limit: usize) -> Self {
+        self.options.max_value_len = Some(limit);
+        self
+    }
+
+    /// Warn about the discouraged constructs enabled in `rules`, e.g.
+    /// `Deprecations::ALL`.
+    pub fn warn_deprecated(mut self, rules: Deprecations) -> Self {
+        self.options.deprecations = rules;
+        self
+    }
+
+    /// Keeps a copy of the source on the `Environment` for later diagnostics.
+    pub fn retain_source(mut self) -> Self {
+        self.retain_source = true;
+        self
+    }
+
+    /// Reports the parse's `ParseMetrics` to `observer`.
+    pub fn observer(mut self, observer: Arc<dyn ParseObserver + Send + Sync>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// How `Environment::apply()` treats variables already set in the
+    /// process; `ProcessEnvWins` by default.
+    pub fn override_policy(mut self, policy: OverridePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Makes `parse` fail with `Error::ChecksumMismatch` unless the content
+    /// ends with a valid `# korni-checksum:` line (see `checksum::sign`).
+    pub fn verify_checksum(mut self) -> Self {
+        self.verify_checksum = true;
+        self
+    }
+
+    /// The file this builder reads, e.g. the one `Korni::find_file` found.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// See `audit::is_gitignored`; `None` for readers.
+    pub fn is_gitignored(&self) -> Option<bool> {
+        self.path().and_then(crate::audit::is_gitignored)
+    }
     
+    /// Files that define secrets (see `audit::is_secret_key`) but are
+    /// world-readable get an `Error::InsecurePermissions` warning.
     pub fn parse(self) -> Result<Environment<'static>, Error> {
-        let content = if let Some(path) = self.path {
-            fs::read_to_string(&path)
-                .map_err(|e| Error::Io(format!("Failed to read file {}: {}", path.display(), e)))?
+        let mut permission_warning = None;
+        let content = if let Some(path) = &self.path {
+            let content = fs::read_to_string(path)
+                .map_err(|e| Error::Io(format!("Failed to read file {}: {}", path.display(), e)))?;
+            permission_warning = fs::metadata(path).ok()
+                .and_then(|meta| crate::audit::check_metadata(path, &meta).err());
+            content
         } else if let Some(mut reader) = self.reader {
             let mut s = String::new();
             reader.read_to_string(&mut s)
@@ -139,8 +378,26 @@ impl OwnedKorniBuilder {
              return Err(Error::Generic { offset: 0, message: "No source provided".into() });
         };
 
-        let entries = crate::parse_with_options(&content, self.options);
-        let env_local = Environment::from_entries(entries);
-        Ok(env_local.into_owned())
+        if self.verify_checksum {
+            crate::checksum::verify(&content)?;
+        }
+        let entries = parse_observed(&content, self.options, self.observer.as_deref());
+        let mut env_local = Environment::from_entries(entries).into_owned();
+        env_local.policy = self.policy;
+        env_local.read_metadata(&content);
+        if let Some(warning) = permission_warning {
+            if env_local.iter().any(|kv| crate::audit::is_secret_key(&kv.key)) {
+                Arc::make_mut(&mut env_local.warnings).push(warning);
+            }
+        }
+        Ok(if self.retain_source { env_local.with_source(content) } else { env_local })
+    }
+}
+
+fn parse_observed<'a>(input: &'a str, options: ParseOptions, observer: Option<&(dyn ParseObserver + Send + Sync)>) -> Vec<Entry<'a>> {
+    let mut parser = Parser::with_options(input, options);
+    match observer {
+        Some(observer) => parser.parse_observed(observer),
+        None => parser.parse(),
     }
 }
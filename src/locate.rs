@@ -0,0 +1,69 @@
+use crate::types::{Comment, KeyValuePair};
+
+/// The part of a pair a source position falls in, see `Located`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Part {
+    /// The `export` prefix and the whitespace after it.
+    Export,
+    Key,
+    Equals,
+    /// The value as written, quotes included.
+    Value,
+    /// The comment character of a commented-out pair, or an inline comment
+    /// after the value.
+    Comment,
+}
+
+/// The entry containing a source position, as returned by
+/// `Environment::entry_at` and `Document::entry_at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Located<'e, 'a> {
+    Pair { pair: &'e KeyValuePair<'a>, part: Part },
+    Comment(&'e Comment),
+}
+
+/// Finds the entry containing `offset`. Inline comments can only be found
+/// when `source` is given; entries without positions are never found.
+pub(crate) fn locate<'e, 'a>(
+    pairs: impl IntoIterator<Item = &'e KeyValuePair<'a>>,
+    comments: impl IntoIterator<Item = &'e Comment>,
+    source: Option<&str>,
+    offset: usize,
+) -> Option<Located<'e, 'a>>
+where
+    'a: 'e,
+{
+    if let Some(comment) = comments.into_iter().find(|c| c.span.range().contains(&offset)) {
+        return Some(Located::Comment(comment));
+    }
+    pairs.into_iter().find_map(|pair| Some(Located::Pair { pair, part: part_at(pair, source, offset)? }))
+}
+
+fn part_at(pair: &KeyValuePair<'_>, source: Option<&str>, offset: usize) -> Option<Part> {
+    let key = pair.key_span?;
+    if key.range().contains(&offset) {
+        return Some(Part::Key);
+    }
+    // Columns are bytes, so the line starts `col` bytes before the key
+    let line_start = key.start.offset - key.start.col;
+    if (line_start..key.start.offset).contains(&offset) {
+        return match (pair.is_comment, pair.is_exported) {
+            (true, _) => Some(Part::Comment),
+            (false, true) => Some(Part::Export),
+            (false, false) => None,
+        };
+    }
+    if pair.equals_pos.is_some_and(|equals| equals.offset == offset) {
+        return Some(Part::Equals);
+    }
+    let raw = pair.raw_span?;
+    if raw.range().contains(&offset) {
+        return Some(Part::Value);
+    }
+
+    let rest = source?.get(raw.end.offset..)?;
+    let rest = &rest[..rest.find('\n').unwrap_or(rest.len())];
+    let comment = raw.end.offset + rest.find('#')?;
+    let inline = rest[..comment - raw.end.offset].trim().is_empty();
+    (inline && (comment..raw.end.offset + rest.len()).contains(&offset)).then_some(Part::Comment)
+}
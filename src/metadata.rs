@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+use crate::error::Error;
+
+/// Self-description from a leading `# korni: version=1, profile=prod`
+/// comment, available as `Environment::metadata()`.
+///
+/// Only comment lines before the first pair are considered; several
+/// `# korni:` lines are merged, later entries winning.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Metadata {
+    /// Schema version of the file's contents.
+    pub version: Option<u32>,
+    /// The profile the file is meant for, e.g. `prod`.
+    pub profile: Option<String>,
+    /// Any other `name=value` entries.
+    pub extra: BTreeMap<String, String>,
+}
+
+impl Metadata {
+    /// Extracts the metadata block of `input`; `Ok(None)` if it has none.
+    /// Entries without `=` or a `version` that is not a number are errors.
+    pub fn from_source(input: &str) -> Result<Option<Metadata>, Error> {
+        let mut metadata: Option<Metadata> = None;
+        let mut offset = if input.starts_with('\u{feff}') { 3 } else { 0 };
+        for line in input[offset..].split_inclusive('\n') {
+            let start = offset;
+            offset += line.len();
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                continue;
+            }
+            let Some(comment) = trimmed.strip_prefix('#') else { break };
+            let Some(block) = comment.trim_start().strip_prefix("korni:") else { continue };
+
+            let metadata = metadata.get_or_insert_with(Metadata::default);
+            for item in block.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+                let invalid = |message: String| Error::Generic { offset: start, message };
+                let (name, value) = item.split_once('=')
+                    .ok_or_else(|| invalid(format!("Metadata entry '{}' is not name=value", item)))?;
+                let (name, value) = (name.trim(), value.trim());
+                match name {
+                    "version" => metadata.version = Some(value.parse()
+                        .map_err(|_| invalid(format!("Metadata version '{}' is not a number", value)))?),
+                    "profile" => metadata.profile = Some(value.to_string()),
+                    _ => { metadata.extra.insert(name.to_string(), value.to_string()); },
+                }
+            }
+        }
+        Ok(metadata)
+    }
+}
@@ -0,0 +1,50 @@
+use std::borrow::Cow;
+use std::time::Duration;
+use crate::types::Entry;
+
+/// Size and timing of one parse, passed to a `ParseObserver`. Contains no
+/// keys or values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ParseMetrics {
+    /// Input length in bytes.
+    pub bytes: usize,
+    pub entries: usize,
+    /// Pairs, including commented-out ones.
+    pub pairs: usize,
+    pub errors: usize,
+    pub warnings: usize,
+    /// Keys and values that needed their own allocation (escapes, line
+    /// continuations); everything else borrows from the input.
+    pub owned_strings: usize,
+    pub duration: Duration,
+}
+
+/// Receives `ParseMetrics` after each observed parse, e.g. to feed
+/// production telemetry. Closures taking `&ParseMetrics` implement it.
+pub trait ParseObserver {
+    fn on_parse(&self, metrics: &ParseMetrics);
+}
+
+impl<F: Fn(&ParseMetrics)> ParseObserver for F {
+    fn on_parse(&self, metrics: &ParseMetrics) {
+        self(metrics)
+    }
+}
+
+impl ParseMetrics {
+    pub(crate) fn measure(bytes: usize, entries: &[Entry<'_>], duration: Duration) -> Self {
+        let mut metrics = ParseMetrics { bytes, entries: entries.len(), duration, ..ParseMetrics::default() };
+        for entry in entries {
+            match entry {
+                Entry::Pair(kv) => {
+                    metrics.pairs += 1;
+                    metrics.owned_strings += [&kv.key, &kv.value].into_iter().filter(|s| matches!(s, Cow::Owned(_))).count();
+                },
+                Entry::Error(_) => metrics.errors += 1,
+                Entry::Warning(_) => metrics.warnings += 1,
+                Entry::Comment(_) => {},
+            }
+        }
+        metrics
+    }
+}
@@ -1,6 +1,15 @@
 use std::borrow::Cow;
-use crate::types::{Entry, KeyValuePair, ParseOptions, QuoteType, Span};
-use crate::error::Error;
+use std::collections::{HashMap, VecDeque};
+use std::io::BufRead;
+use std::ops::ControlFlow;
+use std::ops::Range;
+use std::time::Instant;
+use crate::types::{Annotation, Comment, Deprecations, Entry, KeyValuePair, ParseOptions, QuoteType, Span};
+use crate::bufread::BufReadIter;
+use crate::error::{Deprecation, Error, InvalidKeyReason};
+use crate::line_index::LineIndex;
+use crate::metrics::{ParseMetrics, ParseObserver};
+use crate::spec::{self, key_start_error};
 
 struct ParsedValue<'a> {
     value: Cow<'a, str>,
@@ -9,12 +18,60 @@ struct ParsedValue<'a> {
     quote: QuoteType,
 }
 
+/// Receives entries from `Parser::parse_with` as they are parsed.
+///
+/// Every method defaults to continuing; return `ControlFlow::Break(())` to
+/// stop parsing early.
+pub trait Visitor<'a> {
+    fn on_pair(&mut self, _pair: KeyValuePair<'a>) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_comment(&mut self, _comment: Comment) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_error(&mut self, _error: Error) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+
+    fn on_warning(&mut self, _warning: Error) -> ControlFlow<()> {
+        ControlFlow::Continue(())
+    }
+}
+
+/// Saved `Parser` state; see `Parser::checkpoint`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint {
+    cursor: usize,
+    options: ParseOptions,
+    bom_checked: bool,
+    entry_index: usize,
+    pending: Vec<Entry<'static>>,
+    /// Key spans (`warn_duplicate_keys`); the keys are re-read from the input.
+    seen_keys: Vec<Span>,
+    pending_annotations: Vec<Annotation<'static>>,
+}
+
+impl Checkpoint {
+    /// Byte offset where parsing resumes.
+    pub fn offset(&self) -> usize {
+        self.cursor
+    }
+}
+
 pub struct Parser<'a> {
     input: &'a str,
     bytes: &'a [u8],
     cursor: usize,
     options: ParseOptions,
     bom_checked: bool,
+    entry_index: usize,
+    /// Warnings about the entry just returned, yielded next.
+    pending: VecDeque<Entry<'a>>,
+    seen_keys: HashMap<&'a str, Span>,
+    line_index: Option<LineIndex>,
+    pending_annotations: Vec<Annotation<'a>>,
 }
 
 impl<'a> Parser<'a> {
@@ -31,14 +88,108 @@ impl<'a> Parser<'a> {
             cursor: 0,
             options,
             bom_checked: false,
+            entry_index: 0,
+            pending: VecDeque::new(),
+            seen_keys: HashMap::new(),
+            line_index: if options.track_positions { Some(LineIndex::new(input)) } else { None },
+            pending_annotations: Vec::new(),
         }
     }
 
     pub fn parse(&mut self) -> Vec<Entry<'a>> {
-        let mut entries = Vec::with_capacity(32);
+        let mut entries = Vec::new();
+        self.parse_into(&mut entries);
+        entries
+    }
+
+    /// Appends the remaining entries to `entries`, e.g. to reuse one buffer
+    /// across many inputs.
+    pub fn parse_into(&mut self, entries: &mut Vec<Entry<'a>>) {
+        entries.reserve(self.estimated_entries());
         while let Some(entry) = self.next_entry() {
             entries.push(entry);
         }
+    }
+
+    /// One entry per remaining line, an upper bound for most files.
+    fn estimated_entries(&self) -> usize {
+        let rest = &self.bytes[self.cursor..];
+        rest.iter().filter(|&&b| b == b'\n').count() + !rest.ends_with(b"\n") as usize
+    }
+
+    /// Feeds each entry to `visitor` without collecting them. Returns
+    /// `ControlFlow::Break(())` if the visitor stopped early.
+    pub fn parse_with<V: Visitor<'a> + ?Sized>(&mut self, visitor: &mut V) -> ControlFlow<()> {
+        while let Some(entry) = self.next_entry() {
+            match entry {
+                Entry::Pair(kv) => visitor.on_pair(*kv)?,
+                Entry::Comment(c) => visitor.on_comment(c)?,
+                Entry::Error(e) => visitor.on_error(e)?,
+                Entry::Warning(w) => visitor.on_warning(w)?,
+            }
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Captures the parser's state between two entries. Pass it to `resume`
+    /// to continue from here later, possibly on a longer version of the same
+    /// input (e.g. after more data was appended to a log).
+    ///
+    /// An entry on the last line of the input may still change when more
+    /// text is appended, so checkpoint after a line that ended in a newline.
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            cursor: self.cursor,
+            options: self.options,
+            bom_checked: self.bom_checked,
+            entry_index: self.entry_index,
+            pending: self.pending.iter().cloned().map(Entry::into_owned).collect(),
+            seen_keys: self.seen_keys.values().copied().collect(),
+            pending_annotations: self.pending_annotations.iter().cloned().map(Annotation::into_owned).collect(),
+        }
+    }
+
+    /// Restores a checkpoint taken on this input or on a prefix of it; the
+    /// text before `checkpoint.offset()` must be unchanged.
+    pub fn resume(&mut self, checkpoint: Checkpoint) {
+        let input = self.input;
+        self.cursor = checkpoint.cursor.min(input.len());
+        if self.options.track_positions != checkpoint.options.track_positions {
+            self.line_index = checkpoint.options.track_positions.then(|| LineIndex::new(input));
+        }
+        self.options = checkpoint.options;
+        self.bom_checked = checkpoint.bom_checked;
+        self.entry_index = checkpoint.entry_index;
+        self.pending = checkpoint.pending.into();
+        self.seen_keys = checkpoint.seen_keys.into_iter()
+            .filter_map(|span| Some((input.get(span.range())?, span)))
+            .collect();
+        self.pending_annotations = checkpoint.pending_annotations;
+    }
+
+    /// Iterates active pairs as plain slices of the input, so a file made of
+    /// unquoted, single-quoted and escape-free double-quoted values is parsed
+    /// without any heap allocation. Values that need unescaping (escape
+    /// sequences, line continuations) are reported as errors, as are invalid
+    /// lines; such values are still decoded before being rejected, so they
+    /// allocate like the errors do.
+    ///
+    /// Comments and annotations are skipped and no warnings are produced.
+    /// Create the parser without `track_positions`, which allocates a line
+    /// index up front.
+    pub fn parse_borrowed(mut self) -> BorrowedPairs<'a> {
+        self.options.warn_duplicate_keys = false;
+        self.options.warn_truncated_values = false;
+        self.options.deprecations = Deprecations::default();
+        BorrowedPairs { parser: self }
+    }
+
+    /// `parse()`, reporting its metrics to `observer` once it finishes.
+    pub fn parse_observed(&mut self, observer: &dyn ParseObserver) -> Vec<Entry<'a>> {
+        let bytes = self.input.len() - self.cursor;
+        let start = Instant::now();
+        let entries = self.parse();
+        observer.on_parse(&ParseMetrics::measure(bytes, &entries, start.elapsed()));
         entries
     }
 
@@ -47,6 +198,21 @@ impl<'a> Parser<'a> {
     }
 
     pub fn next_entry(&mut self) -> Option<Entry<'a>> {
+        let mut entry = match self.pending.pop_front() {
+            Some(entry) => entry,
+            None => self.scan_entry()?,
+        };
+        if let Entry::Pair(kv) = &mut entry {
+            kv.index = self.entry_index;
+        }
+        if let Some(index) = &self.line_index {
+            entry.resolve_positions(index);
+        }
+        self.entry_index += 1;
+        Some(entry)
+    }
+
+    fn scan_entry(&mut self) -> Option<Entry<'a>> {
         if !self.bom_checked {
             if let Some(err) = self.check_bom() {
                 return Some(err);
@@ -58,16 +224,19 @@ impl<'a> Parser<'a> {
             self.skip_horizontal_whitespace();
             if self.is_eof() { return None; }
 
-            if self.peek() == b'\n' {
+            if self.peek() == b'\n' || self.bytes[self.cursor..].starts_with(b"\r\n") || self.bytes[self.cursor..] == *b"\r" {
                 self.cursor += 1;
                 continue;
             }
 
-            if self.peek() == b'#' {
+            if self.is_comment_start(self.peek()) {
                 return self.handle_comment();
             }
 
             if let Some(entry) = self.parse_pair() {
+                if !matches!(entry, Entry::Pair(_)) {
+                    self.pending_annotations.clear();
+                }
                 return Some(entry);
             }
             // If None, loop continues (ignoring the skipped line)
@@ -89,107 +258,183 @@ impl<'a> Parser<'a> {
     }
 
     fn handle_comment(&mut self) -> Option<Entry<'a>> {
+        if self.bytes.get(self.cursor + 1..).is_some_and(|rest| {
+            let ws = rest.iter().position(|&b| b != b' ' && b != b'\t').unwrap_or(rest.len());
+            rest.get(ws) == Some(&b'@')
+        }) {
+            self.record_annotation();
+        }
+
         if self.options.include_comments {
             let comment_start = self.cursor;
             self.cursor += 1; 
             self.skip_horizontal_whitespace();
+            let content_start = self.cursor;
 
             if let Some(pair) = self.try_parse_commented_pair() {
+                 self.deprecated(Deprecation::CommentedPair, &pair.key, comment_start);
                  return Some(Entry::Pair(Box::new(pair)));
             } else {
                 self.skip_to_newline();
-                return Some(Entry::Comment(Span::from_offsets(comment_start, self.cursor)));
+                let mut content_end = self.cursor;
+                if content_end > content_start && self.bytes[content_end - 1] == b'\r' {
+                    content_end -= 1;
+                }
+                return Some(Entry::Comment(Comment {
+                    span: Span::from_offsets(comment_start, self.cursor),
+                    content_span: Span::from_offsets(content_start, content_end),
+                }));
             }
-        } else {
-            self.skip_to_newline();
         }
+
+        // Without comments in the output, a commented pair is only probed for
+        // and reported in its place
+        let comment_start = self.cursor;
+        let mut commented_key = None;
+        if self.options.deprecations.commented_pairs {
+            self.cursor += 1;
+            self.skip_horizontal_whitespace();
+            commented_key = self.try_parse_commented_pair().map(|pair| pair.key.to_string());
+            self.cursor = comment_start;
+        }
+        self.skip_to_newline();
         
         if !self.is_eof() && self.peek() == b'\n' { 
             self.cursor += 1; 
         }
         
-        self.next_entry()
+        match commented_key {
+            Some(key) => Some(Entry::Warning(Error::Deprecated { rule: Deprecation::CommentedPair, key, offset: comment_start })),
+            None => self.scan_entry(),
+        }
+    }
+
+    /// Parses `# @name: value` (the cursor is at the comment character)
+    /// without moving the cursor.
+    fn record_annotation(&mut self) {
+        let rest = &self.input[self.cursor + 1..];
+        let line = &rest[..rest.find('\n').unwrap_or(rest.len())];
+        let line = line.trim_end_matches('\r').trim_start_matches([' ', '\t']);
+        let body = &line[1..];
+        let (name, value) = match body.find([':', ' ', '\t']) {
+            Some(i) => (&body[..i], body[i..].trim_start_matches([' ', '\t']).trim_start_matches(':')),
+            None => (body, ""),
+        };
+        if !name.is_empty() {
+            self.pending_annotations.push(Annotation {
+                name: Cow::Borrowed(name),
+                value: Cow::Borrowed(value.trim()),
+            });
+        }
+    }
+
+    fn parse_pair(&mut self) -> Option<Entry<'a>> {
+        Some(match self.scan_pair() {
+            Ok(kv) => Entry::Pair(Box::new(kv)),
+            Err(e) => Entry::Error(e),
+        })
     }
 
-fn parse_pair(&mut self) -> Option<Entry<'a>> {
+    fn scan_pair(&mut self) -> Result<KeyValuePair<'a>, Error> {
+        let line_start = self.cursor;
         let is_exported = self.consume_export_keyword();
+        if is_exported && self.options.forbid_export {
+            return Err(self.error_and_recover(Error::ExportForbidden { offset: line_start }));
+        }
+        let export_start = is_exported.then_some(line_start);
 
         let key_start = self.cursor;
         self.consume_key_chars();
         let key_end = self.cursor;
         let key_str = &self.input[key_start..key_end];
 
+        if !self.options.unicode_keys && !self.is_eof() && !self.peek().is_ascii() {
+            return Err(self.error_and_recover(Error::InvalidKey {
+                offset: self.cursor,
+                reason: InvalidKeyReason::NonAscii,
+            }));
+        }
+
         if key_start == key_end {
             self.skip_horizontal_whitespace();
             if !self.is_eof() && self.peek() == b'=' {
-                return Some(self.error_and_recover(Error::Generic { 
+                return Err(self.error_and_recover(Error::Generic { 
                     offset: key_start, 
                     message: "Empty key".into() 
                 }));
             }
 
             if is_exported {
-                self.skip_to_newline();
-                if !self.is_eof() && self.peek() == b'\n' { self.cursor += 1; }
-                return None; 
+                return Err(self.error_and_recover(Error::Expected { offset: key_start, expected: "key after 'export'" }));
             }
 
-            self.skip_to_newline();
-            if !self.is_eof() && self.peek() == b'\n' { self.cursor += 1; }
-            return None;
+            return Err(self.error_and_recover(Error::InvalidKey {
+                offset: key_start,
+                reason: InvalidKeyReason::NotAKey,
+            }));
         }
-        if key_str.as_bytes()[0].is_ascii_digit() {
-            return Some(self.error_and_recover(Error::InvalidKey { offset: key_start, reason: "Key starts with digit".into() }));
+        if let Some(reason) = key_start_error(key_str, self.options.unicode_keys) {
+            return Err(self.error_and_recover(Error::InvalidKey { offset: key_start, reason }));
         }
 
         // Space before equals
         if !self.is_eof() && matches!(self.peek(), b' ' | b'\t') {
             self.skip_horizontal_whitespace();
             if !self.is_eof() && self.peek() == b'=' {
-                return Some(self.error_and_recover(Error::ForbiddenWhitespace { offset: key_start, location: "between key and equals" }));
+                return Err(self.error_and_recover(Error::ForbiddenWhitespace { key: key_str.to_string(), offset: key_start, location: "between key and equals" }));
             }
         }
 
         // Expect Equals
         if self.is_eof() || self.peek() != b'=' {
-            return Some(self.error_and_recover(Error::Expected { offset: self.cursor, expected: "'='" }));
+            return Err(self.error_and_recover(Error::Expected { offset: self.cursor, expected: "'='" }));
         }
         self.cursor += 1; // consume '='
 
         // Double equals check
         if !self.is_eof() && self.peek() == b'=' {
-            return Some(self.error_and_recover(Error::DoubleEquals { offset: self.cursor }));
+            return Err(self.error_and_recover(Error::DoubleEquals { offset: self.cursor }));
         }
         // Space after equals
         if !self.is_eof() && matches!(self.peek(), b' ' | b'\t') {
-            return Some(self.error_and_recover(Error::ForbiddenWhitespace { offset: self.cursor, location: "after equals" }));
+            return Err(self.error_and_recover(Error::ForbiddenWhitespace { key: key_str.to_string(), offset: self.cursor, location: "after equals" }));
         }
 
         // Parse Value
-        let value_start = self.cursor;
-        let parsed_value = if !self.is_eof() && self.peek() == b'\'' {
-            self.parse_single_quoted_value(value_start)
-        } else if !self.is_eof() && self.peek() == b'"' {
-            self.parse_double_quoted_value(value_start)
-        } else {
-            self.parse_unquoted_value(value_start)
-        };
+        let parsed_value = self.parse_value(key_str);
 
-        let entry = match parsed_value {
+        let result = match parsed_value {
             Ok(pv) => {
                 let pair = if self.options.track_positions {
                     KeyValuePair::new(key_str, key_start, pv.value, pv.value_start, pv.raw_len, pv.quote, is_exported, false)
                 } else {
                     KeyValuePair::new_fast(key_str, pv.value, pv.quote, is_exported, false)
                 };
-                Entry::Pair(Box::new(pair))
+                if pv.quote == QuoteType::None && self.options.warn_truncated_values {
+                    self.check_truncated(key_str);
+                }
+                if let Some(offset) = export_start {
+                    self.deprecated(Deprecation::ExportPrefix, key_str, offset);
+                }
+                if pv.quote != QuoteType::None && self.options.deprecations.trailing_content {
+                    if let Some(junk) = self.trailing_text() {
+                        self.deprecated(Deprecation::TrailingContent, key_str, junk.start);
+                    }
+                }
+                if self.options.warn_duplicate_keys {
+                    self.check_duplicate(key_str, Span::from_offsets(key_start, key_end));
+                }
+                let mut pair = pair;
+                self.limit_value(&mut pair, pv.value_start);
+                pair.annotations = std::mem::take(&mut self.pending_annotations);
+                Ok(pair)
             },
-            Err(e) => Entry::Error(e),
+            Err(e) => Err(e),
         };
 
         self.skip_to_newline();
         if !self.is_eof() && self.peek() == b'\n' { self.cursor += 1; }
-        Some(entry)
+        result
     }
 
     fn try_parse_commented_pair(&mut self) -> Option<KeyValuePair<'a>> {
@@ -206,21 +451,13 @@ fn parse_pair(&mut self) -> Option<Entry<'a>> {
         }
 
         let key_str = &self.input[key_start..key_end];
-        if key_str.as_bytes()[0].is_ascii_digit() {
+        if key_start_error(key_str, self.options.unicode_keys).is_some() {
             self.cursor = saved;
             return None;
         }
 
         self.cursor += 1;
-        let value_start = self.cursor;
-        
-        let parsed_value = if !self.is_eof() && self.peek() == b'\'' {
-            self.parse_single_quoted_value(value_start)
-        } else if !self.is_eof() && self.peek() == b'"' {
-            self.parse_double_quoted_value(value_start)
-        } else {
-            self.parse_unquoted_value(value_start)
-        };
+        let parsed_value = self.parse_value(key_str);
 
         match parsed_value {
             Ok(pv) => {
@@ -241,8 +478,31 @@ fn parse_pair(&mut self) -> Option<Entry<'a>> {
 }
 
 impl<'a> Parser<'a> {
+    /// Parses the value at the cursor and applies the configured
+    /// normalizations to quoted values.
     #[inline]
-    fn parse_single_quoted_value(&mut self, start: usize) -> Result<ParsedValue<'a>, Error> {
+    fn parse_value(&mut self, key: &str) -> Result<ParsedValue<'a>, Error> {
+        let value_start = self.cursor;
+        let mut parsed = match self.bytes.get(self.cursor) {
+            Some(b'\'') => self.parse_single_quoted_value(key, value_start)?,
+            Some(b'"') => self.parse_double_quoted_value(key, value_start)?,
+            _ => return self.parse_unquoted_value(value_start),
+        };
+        let options = &self.options;
+        if options.trim_quoted_values {
+            parsed.value = map_slice(parsed.value, str::trim);
+        }
+        if options.normalize_newlines && parsed.value.contains("\r\n") {
+            parsed.value = Cow::Owned(parsed.value.replace("\r\n", "\n"));
+        }
+        if options.strip_trailing_newline {
+            parsed.value = map_slice(parsed.value, strip_trailing_newline);
+        }
+        Ok(parsed)
+    }
+
+    #[inline]
+    fn parse_single_quoted_value(&mut self, key: &str, start: usize) -> Result<ParsedValue<'a>, Error> {
         self.cursor += 1; // '
         let content_start = self.cursor;
         let remaining = &self.bytes[self.cursor..];
@@ -259,67 +519,62 @@ impl<'a> Parser<'a> {
             })
         } else {
             self.cursor = self.bytes.len();
-            Err(Error::UnclosedQuote { offset: start, quote_type: "single" })
+            Err(Error::UnclosedQuote { key: key.to_string(), offset: start, quote_type: "single" })
         }
     }
 
     #[inline]
-    fn parse_double_quoted_value(&mut self, start: usize) -> Result<ParsedValue<'a>, Error> {
+    fn parse_double_quoted_value(&mut self, key: &str, start: usize) -> Result<ParsedValue<'a>, Error> {
         self.cursor += 1;
-        let content_start = self.cursor;
-        let remaining = &self.bytes[self.cursor..];
+        // Unescaped runs are copied whole, so multi-byte characters stay intact
+        let mut chunk_start = self.cursor;
+        let mut owned: Option<String> = None;
 
-        if let Some(pos) = remaining.iter().position(|&b| b == b'"' || b == b'\\') {
-            if remaining[pos] == b'"' {
-                self.cursor += pos;
-                let content = &self.input[content_start..self.cursor];
-                self.cursor += 1;
-                return Ok(ParsedValue {
-                    value: Cow::Borrowed(content),
-                    value_start: start,
-                    raw_len: self.cursor - start,
-                    quote: QuoteType::Double,
-                });
-            }
+        loop {
+            let Some(pos) = self.bytes[self.cursor..].iter().position(|&b| b == b'"' || b == b'\\') else {
+                self.cursor = self.bytes.len();
+                return Err(Error::UnclosedQuote { key: key.to_string(), offset: start, quote_type: "double" });
+            };
             self.cursor += pos;
-        } else {
-            self.cursor += remaining.len();
-        }
+            let chunk = &self.input[chunk_start..self.cursor];
 
-        self.cursor = content_start;
-        let remaining_len = self.bytes.len() - self.cursor;
-        let mut value = String::with_capacity(remaining_len.saturating_sub(1));
-        
-        loop {
-            if self.is_eof() {
-                return Err(Error::UnclosedQuote { offset: start, quote_type: "double" });
-            }
-            let b = self.peek();
-            if b == b'\\' && self.cursor + 1 < self.bytes.len() {
-                self.cursor += 1;
-                let c = self.peek();
-                match c {
-                    b'n' => value.push('\n'),
-                    b'r' => value.push('\r'),
-                    b't' => value.push('\t'),
-                    b'\\' => value.push('\\'),
-                    b'"' => value.push('"'),
-                    b'$' => value.push('$'),
-                    _ => { value.push('\\'); value.push(c as char); }
-                }
-                self.cursor += 1;
-            } else if b == b'"' {
+            if self.peek() == b'"' {
                 self.cursor += 1;
+                let value = match owned {
+                    None => Cow::Borrowed(chunk),
+                    Some(mut value) => {
+                        value.push_str(chunk);
+                        Cow::Owned(value)
+                    },
+                };
                 return Ok(ParsedValue {
-                    value: Cow::Owned(value),
+                    value,
                     value_start: start,
                     raw_len: self.cursor - start,
                     quote: QuoteType::Double,
                 });
-            } else {
-                value.push(b as char);
-                self.cursor += 1;
             }
+
+            let Some(&escaped) = self.bytes.get(self.cursor + 1) else {
+                self.cursor = self.bytes.len();
+                return Err(Error::UnclosedQuote { key: key.to_string(), offset: start, quote_type: "double" });
+            };
+            let value = owned.get_or_insert_with(|| String::with_capacity(chunk.len() + 16));
+            value.push_str(chunk);
+            let unescaped = match spec::unescape(escaped as char) {
+                Some(unescaped) => unescaped,
+                None => {
+                    // Unknown escape: keep the backslash, and leave the
+                    // following character in the next chunk
+                    value.push('\\');
+                    self.cursor += 1;
+                    chunk_start = self.cursor;
+                    continue;
+                },
+            };
+            value.push(unescaped);
+            self.cursor += 2;
+            chunk_start = self.cursor;
         }
     }
 
@@ -332,14 +587,7 @@ impl<'a> Parser<'a> {
         loop {
             if self.is_eof() { break; }
             let line_start = self.cursor;
-            
-            let remaining = &self.bytes[self.cursor..];
-            let (limit, stop_char) = match remaining.iter().position(|&b| matches!(b, b' ' | b'\t' | b'\n' | b'\r')) {
-                Some(pos) => (self.cursor + pos, Some(remaining[pos])),
-                None => (self.cursor + remaining.len(), None)
-            };
-
-            let stopped_at_eol = matches!(stop_char, Some(b'\n') | Some(b'\r') | None);
+            let (limit, stopped_at_eol) = self.unquoted_value_end();
             let is_continuation = stopped_at_eol && limit > line_start && self.bytes[limit - 1] == b'\\';
 
             if is_continuation {
@@ -365,15 +613,8 @@ impl<'a> Parser<'a> {
             loop {
                 if self.is_eof() { break; }
                 let line_start = self.cursor;
-                
-                let remaining = &self.bytes[self.cursor..];
-                let (limit, stop_char) = match remaining.iter().position(|&b| matches!(b, b' ' | b'\t' | b'\n' | b'\r')) {
-                    Some(pos) => (self.cursor + pos, Some(remaining[pos])),
-                    None => (self.cursor + remaining.len(), None)
-                };
-
+                let (limit, stopped_at_eol) = self.unquoted_value_end();
                 let chunk = &self.input[self.cursor..limit];
-                let stopped_at_eol = matches!(stop_char, Some(b'\n') | Some(b'\r') | None);
                 let is_continuation = stopped_at_eol && limit > line_start && self.bytes[limit - 1] == b'\\';
 
                 if is_continuation {
@@ -409,6 +650,38 @@ impl<'a> Parser<'a> {
 }
 
 impl<'a> Parser<'a> {
+    /// End of the unquoted value text on the current line, and whether the
+    /// line ends right after it. Normally the value stops at the first
+    /// whitespace; with `unquoted_to_eol` it runs to the end of the line,
+    /// minus trailing whitespace and an inline comment.
+    #[inline]
+    fn unquoted_value_end(&self) -> (usize, bool) {
+        let remaining = &self.bytes[self.cursor..];
+        let end = if self.options.unquoted_to_eol {
+            let mut end = 0;
+            let mut i = 0;
+            while let Some(&b) = remaining.get(i) {
+                match b {
+                    b'\n' | b'\r' => break,
+                    b' ' | b'\t' => {
+                        i += remaining[i..].iter().position(|&b| b != b' ' && b != b'\t').unwrap_or(remaining.len() - i);
+                        if remaining.get(i).is_some_and(|&b| self.is_comment_start(b)) {
+                            break;
+                        }
+                    },
+                    _ => {
+                        i += 1;
+                        end = i;
+                    },
+                }
+            }
+            end
+        } else {
+            remaining.iter().position(|&b| matches!(b, b' ' | b'\t' | b'\n' | b'\r')).unwrap_or(remaining.len())
+        };
+        (self.cursor + end, matches!(remaining.get(end), None | Some(b'\n' | b'\r')))
+    }
+
     #[inline(always)]
     fn peek(&self) -> u8 {
         debug_assert!(self.cursor < self.bytes.len(), "peek() called when at EOF");
@@ -418,6 +691,11 @@ impl<'a> Parser<'a> {
     #[inline(always)]
     fn is_eof(&self) -> bool { self.cursor >= self.bytes.len() }
 
+    #[inline(always)]
+    fn is_comment_start(&self, b: u8) -> bool {
+        self.options.comment_chars.contains(&b)
+    }
+
     #[inline]
     fn skip_horizontal_whitespace(&mut self) {
         if self.cursor < self.bytes.len() {
@@ -429,7 +707,13 @@ impl<'a> Parser<'a> {
 
     #[inline]
     fn consume_key_chars(&mut self) {
-        if self.cursor < self.bytes.len() {
+        if self.options.unicode_keys {
+            let remaining = &self.input[self.cursor..];
+            let advance = remaining.char_indices()
+                .find(|&(_, c)| !spec::is_key_char(c, true))
+                .map_or(remaining.len(), |(i, _)| i);
+            self.cursor += advance;
+        } else if self.cursor < self.bytes.len() {
             let remaining = &self.bytes[self.cursor..];
             let advance = remaining.iter().position(|&b| !b.is_ascii_alphanumeric() && b != b'_').unwrap_or(remaining.len());
             self.cursor += advance;
@@ -457,10 +741,82 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn error_and_recover(&mut self, err: Error) -> Entry<'a> {
+    /// Warns if text other than an inline comment follows the unquoted
+    /// value that ended at the cursor.
+    fn check_truncated(&mut self, key: &str) {
+        if let Some(dropped) = self.trailing_text() {
+            self.pending.push_back(Entry::Warning(Error::TruncatedValue {
+                key: key.to_string(),
+                dropped: Span::from_offsets(dropped.start, dropped.end),
+            }));
+        }
+    }
+
+    /// Byte range of text other than an inline comment between the cursor
+    /// and the end of the line, trimmed of whitespace.
+    fn trailing_text(&self) -> Option<Range<usize>> {
+        let line = &self.bytes[self.cursor..];
+        let line = &line[..line.iter().position(|&b| b == b'\n').unwrap_or(line.len())];
+        let start = line.iter().position(|&b| b != b' ' && b != b'\t').unwrap_or(line.len());
+        let end = line.iter().rposition(|&b| !matches!(b, b' ' | b'\t' | b'\r')).map_or(0, |i| i + 1);
+        (start < end && !self.is_comment_start(line[start])).then(|| self.cursor + start..self.cursor + end)
+    }
+
+    fn deprecated(&mut self, rule: Deprecation, key: &str, offset: usize) {
+        if self.options.deprecations.is_enabled(rule) {
+            self.pending.push_back(Entry::Warning(Error::Deprecated { rule, key: key.to_string(), offset }));
+        }
+    }
+
+    /// Applies `max_value_len`, warning about the cut.
+    fn limit_value(&mut self, pair: &mut KeyValuePair<'a>, offset: usize) {
+        let Some(limit) = self.options.max_value_len.filter(|&limit| pair.value.len() > limit) else { return };
+        let len = pair.value.len();
+        let end = (0..=limit).rev().find(|&i| pair.value.is_char_boundary(i)).unwrap_or(0);
+        pair.value = match std::mem::take(&mut pair.value) {
+            Cow::Borrowed(value) => Cow::Borrowed(&value[..end]),
+            Cow::Owned(mut value) => {
+                value.truncate(end);
+                value.shrink_to_fit();
+                Cow::Owned(value)
+            },
+        };
+        pair.truncated = true;
+        self.pending.push_back(Entry::Warning(Error::ValueTooLong { key: pair.key.to_string(), len, limit, offset }));
+    }
+
+    fn check_duplicate(&mut self, key: &'a str, span: Span) {
+        if let Some(first_span) = self.seen_keys.insert(key, span) {
+            self.pending.push_back(Entry::Warning(Error::DuplicateKey {
+                key: key.to_string(),
+                first_span,
+                second_span: span,
+            }));
+        }
+    }
+
+    fn error_and_recover(&mut self, err: Error) -> Error {
         self.skip_to_newline();
         if !self.is_eof() { self.cursor += 1; }
-        Entry::Error(err)
+        err
+    }
+}
+
+#[inline]
+/// Narrows `value` to a subslice of itself, copying only if it was owned
+/// and actually shrank.
+fn map_slice<'a>(value: Cow<'a, str>, f: fn(&str) -> &str) -> Cow<'a, str> {
+    match value {
+        Cow::Borrowed(s) => Cow::Borrowed(f(s)),
+        Cow::Owned(s) if f(&s).len() == s.len() => Cow::Owned(s),
+        Cow::Owned(s) => Cow::Owned(f(&s).to_string()),
+    }
+}
+
+fn strip_trailing_newline(s: &str) -> &str {
+    match s.strip_suffix('\n') {
+        Some(rest) => rest.strip_suffix('\r').unwrap_or(rest),
+        None => s,
     }
 }
 
@@ -468,9 +824,93 @@ pub struct EnvIterator<'a> {
     parser: Parser<'a>,
 }
 
+impl<'a> EnvIterator<'a> {
+    pub fn new(parser: Parser<'a>) -> Self {
+        Self { parser }
+    }
+
+    /// Parses `reader` incrementally, holding only the current logical line
+    /// in memory and yielding owned entries.
+    pub fn from_bufread<R: BufRead>(reader: R) -> BufReadIter<R> {
+        BufReadIter::new(reader, ParseOptions::default())
+    }
+
+    pub fn from_bufread_with_options<R: BufRead>(reader: R, options: ParseOptions) -> BufReadIter<R> {
+        BufReadIter::new(reader, options)
+    }
+
+    /// Yields every pair, including commented-out ones.
+    pub fn pairs(self) -> impl Iterator<Item = KeyValuePair<'a>> {
+        self.filter_map(Entry::into_pair)
+    }
+
+    /// Yields only active pairs, skipping commented-out ones.
+    pub fn ok_pairs(self) -> impl Iterator<Item = KeyValuePair<'a>> {
+        self.pairs().filter(|kv| !kv.is_comment)
+    }
+
+    pub fn errors(self) -> impl Iterator<Item = Error> + 'a {
+        self.filter_map(|e| match e {
+            Entry::Error(err) => Some(err),
+            _ => None,
+        })
+    }
+}
+
 impl<'a> Iterator for EnvIterator<'a> {
     type Item = Entry<'a>;
     fn next(&mut self) -> Option<Self::Item> {
         self.parser.next_entry()
     }
 }
+
+/// A pair whose key and value borrow from the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorrowedPair<'a> {
+    pub key: &'a str,
+    pub value: &'a str,
+    pub quote: QuoteType,
+    pub is_exported: bool,
+}
+
+/// Iterator returned by `Parser::parse_borrowed`. Only borrowed pairs are
+/// free of allocation; see there.
+pub struct BorrowedPairs<'a> {
+    parser: Parser<'a>,
+}
+
+impl<'a> Iterator for BorrowedPairs<'a> {
+    type Item = Result<BorrowedPair<'a>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let p = &mut self.parser;
+        if !p.bom_checked {
+            if let Some(Entry::Error(e)) = p.check_bom() {
+                return Some(Err(e));
+            }
+        }
+
+        loop {
+            p.skip_horizontal_whitespace();
+            if p.is_eof() { return None; }
+
+            if p.peek() == b'\n' || p.bytes[p.cursor..].starts_with(b"\r\n") || p.bytes[p.cursor..] == *b"\r" {
+                p.cursor += 1;
+                continue;
+            }
+            if p.is_comment_start(p.peek()) {
+                p.skip_to_newline();
+                continue;
+            }
+
+            let line_start = p.cursor;
+            let pair = p.scan_pair();
+            // Borrowed parsing reports no warnings (e.g. `ValueTooLong`)
+            p.pending.clear();
+            return Some(pair.and_then(|kv| match (kv.key, kv.value) {
+                (Cow::Borrowed(key), Cow::Borrowed(value)) => Ok(BorrowedPair { key, value, quote: kv.quote, is_exported: kv.is_exported }),
+                _ => Err(Error::Generic { offset: line_start, message: "Value needs unescaping and cannot be borrowed".into() }),
+            }));
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use crate::audit::is_secret_key;
+use crate::spec;
+use crate::types::QuoteType;
+
+/// One pair as listed by `preview()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewEntry<'a> {
+    pub key: &'a str,
+    /// The value as written, without quotes and with escapes left as they
+    /// are, cut to the requested length at a character boundary.
+    pub value: &'a str,
+    /// Length in bytes of the whole value as written.
+    pub len: usize,
+    pub truncated: bool,
+    pub quote: QuoteType,
+    /// The key looks like it holds a secret (see `audit::is_secret_key`);
+    /// UIs should mask the value.
+    pub secret: bool,
+}
+
+/// Lists the pairs of `input` for display, with values cut to
+/// `max_value_len` bytes.
+///
+/// This is a quick scan rather than a full parse: escapes are not decoded,
+/// unquoted values end at the first whitespace, line continuations are not
+/// followed and invalid lines are skipped without errors. Use the parser
+/// for actual values.
+pub fn preview(input: &str, max_value_len: usize) -> Vec<PreviewEntry<'_>> {
+    let bytes = input.as_bytes();
+    let mut entries = Vec::new();
+    let mut pos = if input.starts_with('\u{feff}') { 3 } else { 0 };
+
+    while pos < bytes.len() {
+        let line_end = find(bytes, pos, b'\n');
+        let mut next = line_end + 1;
+        let line = &input[pos..line_end];
+        let indent = line.len() - line.trim_start().len();
+        let mut start = pos + indent;
+        if input[start..line_end].starts_with("export ") {
+            start += "export ".len();
+            start += input[start..line_end].len() - input[start..line_end].trim_start().len();
+        }
+        let key_len = bytes[start..line_end].iter().take_while(|&&b| spec::is_key_char(b as char, false)).count();
+        let key = &input[start..start + key_len];
+        let value_start = start + key_len + 1;
+
+        if key_len > 0 && !key.starts_with(|c: char| c.is_ascii_digit()) && bytes.get(start + key_len) == Some(&b'=') {
+            let (quote, content, end) = match bytes.get(value_start) {
+                Some(b'\'') => {
+                    let close = find(bytes, value_start + 1, b'\'').min(line_end);
+                    (QuoteType::Single, value_start + 1..close, line_end)
+                },
+                Some(b'"') => {
+                    let mut i = value_start + 1;
+                    while i < bytes.len() && bytes[i] != b'"' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                    let close = i.min(bytes.len());
+                    (QuoteType::Double, value_start + 1..close, find(bytes, close, b'\n'))
+                },
+                _ => {
+                    let len = bytes[value_start.min(line_end)..line_end].iter()
+                        .take_while(|b| !matches!(b, b' ' | b'\t' | b'\r'))
+                        .count();
+                    (QuoteType::None, value_start..value_start + len, line_end)
+                },
+            };
+            let value = &input[content.clone()];
+            let cut = (0..=max_value_len.min(value.len())).rev().find(|&i| value.is_char_boundary(i)).unwrap_or(0);
+            entries.push(PreviewEntry {
+                key,
+                value: &value[..cut],
+                len: value.len(),
+                truncated: cut < value.len(),
+                quote,
+                secret: is_secret_key(key),
+            });
+            next = end + 1;
+        }
+        pos = next;
+    }
+    entries
+}
+
+fn find(bytes: &[u8], from: usize, needle: u8) -> usize {
+    bytes[from.min(bytes.len())..].iter().position(|&b| b == needle).map_or(bytes.len(), |i| from + i)
+}
@@ -0,0 +1,486 @@
+//! Declared keys and the rules between them, checked against a parsed
+//! environment.
+//!
+//! ```
+//! use korni::schema::{KeySpec, Schema, ValueType};
+//!
+//! let schema = Schema::new()
+//!     .key("DB_HOST", KeySpec::new(ValueType::String).required())
+//!     .key("DB_PORT", KeySpec::new(ValueType::Integer))
+//!     .range("DB_PORT", 1, 65535)
+//!     .all_or_none(["TLS_CERT", "TLS_KEY"]);
+//!
+//! let env = korni::Korni::from_str("DB_HOST=db\nDB_PORT=70000\nTLS_CERT=a.pem").parse().unwrap();
+//! for violation in schema.validate(&env) {
+//!     eprintln!("{}", violation);
+//! }
+//! ```
+//!
+//! Like the audit reports, violations name keys but never include values.
+
+use std::fmt::{self, Write};
+use crate::codegen;
+use crate::diff::json_string;
+use crate::env::Environment;
+use crate::error::Error;
+use crate::infer;
+use crate::types::{KeyValuePair, Span};
+
+pub use crate::infer::{InferredType, TypeGuess};
+
+/// The type a key's value must parse as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ValueType {
+    /// Any value.
+    #[default]
+    String,
+    /// A signed 64-bit integer.
+    Integer,
+    Float,
+    /// `true`/`false`, `1`/`0`, `yes`/`no` or `on`/`off`, in any case.
+    Bool,
+    /// `scheme://rest` with a non-empty scheme.
+    Url,
+}
+
+impl ValueType {
+    /// Whether `value` parses as this type.
+    pub fn matches(self, value: &str) -> bool {
+        match self {
+            ValueType::String => true,
+            ValueType::Integer => value.parse::<i64>().is_ok(),
+            ValueType::Float => value.parse::<f64>().is_ok(),
+            ValueType::Bool => parse_bool(value).is_some(),
+            ValueType::Url => value.split_once("://").is_some_and(|(scheme, rest)| {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+                    && !rest.is_empty()
+            }),
+        }
+    }
+
+    /// The type named `name` (as returned by `name()`, in any case), also
+    /// accepting `int`, `str`, `boolean` and `number`.
+    pub fn from_name(name: &str) -> Option<ValueType> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "string" | "str" => ValueType::String,
+            "integer" | "int" => ValueType::Integer,
+            "float" | "number" => ValueType::Float,
+            "bool" | "boolean" => ValueType::Bool,
+            "url" => ValueType::Url,
+            _ => return None,
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::String => "string",
+            ValueType::Integer => "integer",
+            ValueType::Float => "float",
+            ValueType::Bool => "bool",
+            ValueType::Url => "URL",
+        }
+    }
+
+    /// A regular expression (ECMA-262, as JSON Schema uses) accepting the
+    /// values `matches` accepts, except integers outside the `i64` range;
+    /// `None` for `String`.
+    pub fn pattern(self) -> Option<&'static str> {
+        match self {
+            ValueType::String => None,
+            ValueType::Integer => Some(r"^[+-]?[0-9]+$"),
+            ValueType::Float => Some(r"^[+-]?(?:(?:[0-9]+\.?[0-9]*|\.[0-9]+)(?:[eE][+-]?[0-9]+)?|[iI][nN][fF](?:[iI][nN][iI][tT][yY])?|[nN][aA][nN])$"),
+            ValueType::Bool => Some(r"^(?:[tT][rR][uU][eE]|[fF][aA][lL][sS][eE]|[yY][eE][sS]|[nN][oO]|[oO][nN]|[oO][fF][fF]|1|0)$"),
+            ValueType::Url => Some(r"^[A-Za-z][A-Za-z0-9+.\-]*://[\s\S]"),
+        }
+    }
+}
+
+/// Parses a value accepted by `ValueType::Bool`.
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Some(true),
+        "false" | "0" | "no" | "off" => Some(false),
+        _ => None,
+    }
+}
+
+/// What a schema declares about one key.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KeySpec {
+    pub value_type: ValueType,
+    pub required: bool,
+    /// Used for the key when it is not set, see `Environment::with_defaults`.
+    pub default: Option<String>,
+    pub description: Option<String>,
+}
+
+impl KeySpec {
+    pub fn new(value_type: ValueType) -> Self {
+        Self { value_type, ..Self::default() }
+    }
+
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    pub fn with_default(mut self, value: &str) -> Self {
+        self.default = Some(value.to_string());
+        self
+    }
+
+    pub fn describe(mut self, description: &str) -> Self {
+        self.description = Some(description.to_string());
+        self
+    }
+}
+
+/// A constraint on the values of one or more keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// If set, `key` is an integer in `min..=max`.
+    Range { key: String, min: i64, max: i64 },
+    /// Either every one of `keys` is set or none is, e.g. a certificate and
+    /// its private key.
+    AllOrNone(Vec<String>),
+    /// `key` must be set when `condition` is set to `equals`, e.g. a bucket
+    /// name when `STORAGE=s3`.
+    RequiredIf { key: String, condition: String, equals: String },
+}
+
+/// Declared keys, in declaration order, and rules between them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Schema {
+    keys: Vec<(String, KeySpec)>,
+    rules: Vec<Rule>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares `key`, replacing an earlier declaration of it.
+    pub fn key(mut self, key: &str, spec: KeySpec) -> Self {
+        match self.keys.iter_mut().find(|(k, _)| k == key) {
+            Some((_, existing)) => *existing = spec,
+            None => self.keys.push((key.to_string(), spec)),
+        }
+        self
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// Shorthand for `Rule::Range`.
+    pub fn range(self, key: &str, min: i64, max: i64) -> Self {
+        self.rule(Rule::Range { key: key.to_string(), min, max })
+    }
+
+    /// Shorthand for `Rule::AllOrNone`.
+    pub fn all_or_none<I, S>(self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.rule(Rule::AllOrNone(keys.into_iter().map(|k| k.as_ref().to_string()).collect()))
+    }
+
+    /// Shorthand for `Rule::RequiredIf`.
+    pub fn requires_if(self, key: &str, condition: &str, equals: &str) -> Self {
+        self.rule(Rule::RequiredIf { key: key.to_string(), condition: condition.to_string(), equals: equals.to_string() })
+    }
+
+    /// A schema from an annotated file such as `.env.example`: every active
+    /// pair declares its key, with `@type` (see `ValueType::from_name`),
+    /// `@required`, `@default` and `@description` annotations. Values are
+    /// ignored. An unknown `@type` is an error.
+    pub fn from_annotated(env: &Environment<'_>) -> Result<Schema, Error> {
+        let mut schema = Schema::new();
+        for kv in env.iter_ordered().filter(|kv| !kv.is_comment) {
+            let mut spec = KeySpec::default();
+            if let Some(name) = kv.annotation("type") {
+                spec.value_type = ValueType::from_name(name).ok_or_else(|| Error::Generic {
+                    offset: kv.key_span.map_or(0, |span| span.start.offset),
+                    message: format!("Unknown @type '{}' for {}", name, kv.key),
+                })?;
+            }
+            spec.required = kv.annotation("required").is_some_and(|value| value != "false");
+            spec.default = kv.annotation("default").map(str::to_string);
+            spec.description = kv.annotation("description").map(str::to_string);
+            schema = schema.key(&kv.key, spec);
+        }
+        Ok(schema)
+    }
+
+    /// A starter schema from an existing file, to refine by hand: every
+    /// active key is declared, required, with the type its value is
+    /// inferred as (see `InferredType::infer`). See `infer_from_samples`
+    /// to learn from several files.
+    pub fn infer_from(env: &Environment<'_>) -> Schema {
+        Self::infer_from_samples(std::slice::from_ref(env))
+    }
+
+    /// Like `infer_from`, from several files for the same application,
+    /// e.g. one per deployment. Keys are declared in the order they are
+    /// first seen and are required only if every sample defines them. A
+    /// key gets the most specific type all of its non-empty values fit:
+    /// `8080` and `0.5` make a float, `1` and `true` a bool, and anything
+    /// else mixed a string.
+    pub fn infer_from_samples(envs: &[Environment<'_>]) -> Schema {
+        infer::infer_schema(envs)
+    }
+
+    /// Rust source for a struct `name` with one typed field per declared
+    /// key and a `load(&Environment)` constructor; see `korni codegen`.
+    pub fn to_rust_struct(&self, name: &str) -> String {
+        codegen::rust_struct(self, name)
+    }
+
+    /// The schema as a JSON Schema (draft 2020-12) for a JSON object of
+    /// the variables, so other tools can check submissions by the same
+    /// rules. Every value is a string, as in the file; typed keys carry the
+    /// `ValueType::pattern` of their type, and ranges, which patterns cannot
+    /// express, a `$comment`. Undeclared keys may be any string.
+    pub fn to_json_schema(&self) -> String {
+        let mut out = String::from("{\"$schema\":\"https://json-schema.org/draft/2020-12/schema\",\"type\":\"object\",\"properties\":{");
+        let mut keys: Vec<&str> = self.keys.iter().map(|(key, _)| key.as_str()).collect();
+        for rule in &self.rules {
+            let named = match rule {
+                Rule::Range { key, .. } => vec![key],
+                Rule::AllOrNone(keys) => keys.iter().collect(),
+                Rule::RequiredIf { key, condition, .. } => vec![key, condition],
+            };
+            for key in named {
+                if !keys.contains(&key.as_str()) {
+                    keys.push(key);
+                }
+            }
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            json_string(&mut out, key);
+            out.push(':');
+            self.json_property(&mut out, key);
+        }
+        out.push_str("},\"additionalProperties\":{\"type\":\"string\"}");
+
+        let required: Vec<&str> = self.keys.iter().filter(|(_, spec)| spec.required).map(|(key, _)| key.as_str()).collect();
+        if !required.is_empty() {
+            out.push_str(",\"required\":");
+            json_array(&mut out, required);
+        }
+
+        let groups: Vec<&Vec<String>> = self.rules.iter()
+            .filter_map(|rule| match rule { Rule::AllOrNone(keys) => Some(keys), _ => None })
+            .collect();
+        if !groups.is_empty() {
+            out.push_str(",\"dependentRequired\":{");
+            let mut first = true;
+            for keys in groups {
+                for key in keys {
+                    if !first {
+                        out.push(',');
+                    }
+                    first = false;
+                    json_string(&mut out, key);
+                    out.push(':');
+                    json_array(&mut out, keys.iter().filter(|other| *other != key).map(String::as_str));
+                }
+            }
+            out.push('}');
+        }
+
+        let conditions: Vec<(&String, &String, &String)> = self.rules.iter()
+            .filter_map(|rule| match rule {
+                Rule::RequiredIf { key, condition, equals } => Some((key, condition, equals)),
+                _ => None,
+            })
+            .collect();
+        if !conditions.is_empty() {
+            out.push_str(",\"allOf\":[");
+            for (i, (key, condition, equals)) in conditions.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str("{\"if\":{\"properties\":{");
+                json_string(&mut out, condition);
+                out.push_str(":{\"const\":");
+                json_string(&mut out, equals);
+                out.push_str("}},\"required\":");
+                json_array(&mut out, [condition.as_str()]);
+                out.push_str("},\"then\":{\"required\":");
+                json_array(&mut out, [key.as_str()]);
+                out.push_str("}}");
+            }
+            out.push(']');
+        }
+        out.push('}');
+        out
+    }
+
+    fn json_property(&self, out: &mut String, key: &str) {
+        let spec = self.get(key).cloned().unwrap_or_default();
+        let range = self.rules.iter().find_map(|rule| match rule {
+            Rule::Range { key: k, min, max } if k == key => Some((*min, *max)),
+            _ => None,
+        });
+        // Values are strings; a range also needs an integer
+        let value_type = if range.is_some() { ValueType::Integer } else { spec.value_type };
+        out.push_str("{\"type\":\"string\"");
+        if let Some(pattern) = value_type.pattern() {
+            out.push_str(",\"pattern\":");
+            json_string(out, pattern);
+        }
+        if let Some((min, max)) = range {
+            let _ = write!(out, ",\"$comment\":\"an integer from {} to {}\"", min, max);
+        }
+        if let Some(description) = &spec.description {
+            out.push_str(",\"description\":");
+            json_string(out, description);
+        }
+        if let Some(default) = &spec.default {
+            out.push_str(",\"default\":");
+            json_string(out, default);
+        }
+        out.push('}');
+    }
+
+    pub fn get(&self, key: &str) -> Option<&KeySpec> {
+        self.keys.iter().find(|(k, _)| k == key).map(|(_, spec)| spec)
+    }
+
+    /// The declared keys in declaration order.
+    pub fn keys(&self) -> impl Iterator<Item = (&str, &KeySpec)> {
+        self.keys.iter().map(|(key, spec)| (key.as_str(), spec))
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Checks `env` against the declared keys, then against the rules.
+    /// Commented-out pairs count as not set.
+    pub fn validate(&self, env: &Environment<'_>) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for (key, spec) in &self.keys {
+            let violation = match active(env, key) {
+                None if spec.required => ViolationKind::Missing,
+                Some(kv) if !spec.value_type.matches(&kv.value) => ViolationKind::InvalidType(spec.value_type),
+                _ => continue,
+            };
+            violations.push(Violation::new(env, key, violation));
+        }
+
+        for rule in &self.rules {
+            match rule {
+                Rule::Range { key, min, max } => {
+                    let Some(kv) = active(env, key) else { continue };
+                    let in_range = kv.value.parse::<i64>().is_ok_and(|n| (*min..=*max).contains(&n));
+                    if !in_range {
+                        violations.push(Violation::new(env, key, ViolationKind::OutOfRange { min: *min, max: *max }));
+                    }
+                },
+                Rule::AllOrNone(keys) => {
+                    let (set, unset): (Vec<&String>, Vec<&String>) = keys.iter().partition(|key| active(env, key).is_some());
+                    if set.is_empty() {
+                        continue;
+                    }
+                    for key in unset {
+                        let mut violation = Violation::new(env, key, ViolationKind::Incomplete);
+                        violation.related = set.iter().map(|key| (key.to_string(), span_of(env, key))).collect();
+                        violations.push(violation);
+                    }
+                },
+                Rule::RequiredIf { key, condition, equals } => {
+                    let holds = active(env, condition).is_some_and(|kv| kv.value == equals.as_str());
+                    if holds && active(env, key).is_none() {
+                        let kind = ViolationKind::RequiredIf { condition: condition.clone(), equals: equals.clone() };
+                        let mut violation = Violation::new(env, key, kind);
+                        violation.related = vec![(condition.clone(), span_of(env, condition))];
+                        violations.push(violation);
+                    }
+                },
+            }
+        }
+        violations
+    }
+}
+
+fn json_array<'s>(out: &mut String, items: impl IntoIterator<Item = &'s str>) {
+    out.push('[');
+    for (i, item) in items.into_iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        json_string(out, item);
+    }
+    out.push(']');
+}
+
+fn active<'e, 'a>(env: &'e Environment<'a>, key: &str) -> Option<&'e KeyValuePair<'a>> {
+    env.get_entry(key).filter(|kv| !kv.is_comment)
+}
+
+fn span_of(env: &Environment<'_>, key: &str) -> Option<Span> {
+    active(env, key).and_then(|kv| kv.key_span)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// A required key is not set.
+    Missing,
+    /// The value does not parse as the declared type.
+    InvalidType(ValueType),
+    /// The value is not an integer in `min..=max`.
+    OutOfRange { min: i64, max: i64 },
+    /// The key is not set although the `related` keys of its
+    /// `Rule::AllOrNone` are.
+    Incomplete,
+    /// The key is not set although `condition` is set to `equals`; the
+    /// condition key is also in `related`.
+    RequiredIf { condition: String, equals: String },
+}
+
+/// A key that breaks the schema, as returned by `Schema::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub key: String,
+    pub kind: ViolationKind,
+    /// The definition of `key`, if it is set and positions were tracked.
+    pub key_span: Option<Span>,
+    /// Other keys the violation depends on, with their definitions, e.g.
+    /// the condition of a `Rule::RequiredIf`.
+    pub related: Vec<(String, Option<Span>)>,
+}
+
+impl Violation {
+    /// A violation of `key`, pointing at its definition in `env`.
+    pub fn new(env: &Environment<'_>, key: &str, kind: ViolationKind) -> Self {
+        Self { key: key.to_string(), kind, key_span: span_of(env, key), related: Vec::new() }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ViolationKind::Missing => write!(f, "{} is required but not set", self.key)?,
+            ViolationKind::InvalidType(value_type) => write!(f, "{} is not a valid {}", self.key, value_type.name())?,
+            ViolationKind::OutOfRange { min, max } => write!(f, "{} must be an integer from {} to {}", self.key, min, max)?,
+            ViolationKind::Incomplete => {
+                let related: Vec<&str> = self.related.iter().map(|(key, _)| key.as_str()).collect();
+                write!(f, "{} must be set together with {}", self.key, related.join(", "))?;
+            },
+            ViolationKind::RequiredIf { condition, equals } => write!(f, "{} is required when {}={}", self.key, condition, equals)?,
+        }
+        if let Some(span) = self.key_span {
+            write!(f, " (byte {})", span.start.offset)?;
+        }
+        Ok(())
+    }
+}
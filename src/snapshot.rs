@@ -0,0 +1,43 @@
+use std::path::Path;
+use crate::audit::is_secret_key;
+use crate::diff::REDACTED;
+use crate::document::Document;
+use crate::error::Error;
+use crate::glob::glob_match;
+use crate::spec::is_valid_key;
+
+/// The current process environment as a document, sorted by key.
+///
+/// Only variables matching one of `patterns` (names or globs such as
+/// `APP_*`) are kept; no patterns keeps them all. Values of keys that look
+/// like secrets (see `audit::is_secret_key`) are replaced with `REDACTED`.
+/// Variables that are not valid UTF-8 or whose names are not valid keys
+/// cannot be written and are left out.
+pub fn snapshot<I, S>(patterns: I) -> Document
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let patterns: Vec<S> = patterns.into_iter().collect();
+    let mut vars: Vec<(String, String)> = std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .filter(|(key, _)| is_valid_key(key))
+        .filter(|(key, _)| patterns.is_empty() || patterns.iter().any(|p| glob_match(p.as_ref(), key)))
+        .map(|(key, value)| {
+            let value = if is_secret_key(&key) { REDACTED.to_string() } else { value };
+            (key, value)
+        })
+        .collect();
+    vars.sort();
+    Document::from_pairs(vars).expect("keys are validated")
+}
+
+/// Writes `snapshot(patterns)` to `path`, e.g. to record the configuration
+/// of a run. The file is written as `Document::save` writes it.
+pub fn snapshot_to_file<I, S>(path: impl AsRef<Path>, patterns: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    snapshot(patterns).save(path)
+}
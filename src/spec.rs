@@ -0,0 +1,163 @@
+//! The lexical rules of the format, as the parser and writer apply them.
+//!
+//! Validators and generators should use these rather than restating the
+//! rules, so that what they accept is exactly what parses.
+
+use crate::error::{Error, InvalidKeyReason};
+use crate::types::QuoteType;
+
+/// The escape sequences of double-quoted values: the character after the
+/// backslash and the character it stands for. Any other character after a
+/// backslash is kept as written, backslash included.
+pub const ESCAPES: &[(char, char)] = &[
+    ('n', '\n'),
+    ('r', '\r'),
+    ('t', '\t'),
+    ('\\', '\\'),
+    ('"', '"'),
+    ('$', '$'),
+];
+
+/// The character `\<escaped>` stands for in a double-quoted value, or
+/// `None` if it is not an escape sequence.
+pub const fn unescape(escaped: char) -> Option<char> {
+    let mut i = 0;
+    while i < ESCAPES.len() {
+        if ESCAPES[i].0 == escaped {
+            return Some(ESCAPES[i].1);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// The character to write after a backslash for `c` in a double-quoted
+/// value, or `None` if `c` can be written as is.
+pub const fn escape(c: char) -> Option<char> {
+    let mut i = 0;
+    while i < ESCAPES.len() {
+        if ESCAPES[i].1 == c {
+            return Some(ESCAPES[i].0);
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Whether `c` may appear in a key: ASCII letters, digits and `_`, or any
+/// `XID_Continue` character with `ParseOptions::unicode_keys`.
+pub fn is_key_char(c: char, unicode_keys: bool) -> bool {
+    c == '_' || if unicode_keys { unicode_ident::is_xid_continue(c) } else { c.is_ascii_alphanumeric() }
+}
+
+/// Whether a key may start with `c`: ASCII letters and `_`, or any
+/// `XID_Start` character with `ParseOptions::unicode_keys`.
+pub fn is_key_start(c: char, unicode_keys: bool) -> bool {
+    c == '_' || if unicode_keys { unicode_ident::is_xid_start(c) } else { c.is_ascii_alphabetic() }
+}
+
+/// Whether the parser accepts `key` as a key by default: non-empty, made of
+/// ASCII letters, digits and `_`, and not starting with a digit.
+pub fn is_valid_key(key: &str) -> bool {
+    is_valid_key_with(key, false)
+}
+
+/// `is_valid_key`, optionally with the wider rules of
+/// `ParseOptions::unicode_keys`.
+pub fn is_valid_key_with(key: &str, unicode_keys: bool) -> bool {
+    key.starts_with(|c| is_key_start(c, unicode_keys)) && key.chars().all(|c| is_key_char(c, unicode_keys))
+}
+
+/// Checks `key` the way the parser checks the key of a line, returning the
+/// error it would report with offsets relative to the start of `key`.
+pub fn validate_key(key: &str) -> Result<(), Error> {
+    validate_key_with(key, false)
+}
+
+/// `validate_key`, optionally with the wider rules of
+/// `ParseOptions::unicode_keys`.
+pub fn validate_key_with(key: &str, unicode_keys: bool) -> Result<(), Error> {
+    let end = key.find(|c| !is_key_char(c, unicode_keys)).unwrap_or(key.len());
+    let rest = &key[end..];
+    if !unicode_keys && rest.starts_with(|c: char| !c.is_ascii()) {
+        return Err(Error::InvalidKey {
+            offset: end,
+            reason: InvalidKeyReason::NonAscii,
+        });
+    }
+    if key.is_empty() {
+        return Err(Error::Generic { offset: 0, message: "Empty key".into() });
+    }
+    if end == 0 {
+        return Err(Error::InvalidKey { offset: 0, reason: InvalidKeyReason::NotAKey });
+    }
+    if let Some(reason) = key_start_error(key, unicode_keys) {
+        return Err(Error::InvalidKey { offset: 0, reason });
+    }
+    // The `=` that follows the key is found after any whitespace
+    let after = rest.trim_start_matches([' ', '\t']);
+    match (rest.len() - after.len(), after.is_empty()) {
+        (0, true) => Ok(()),
+        (_, true) => Err(Error::ForbiddenWhitespace {
+            key: key[..end].to_string(),
+            offset: 0,
+            location: "between key and equals",
+        }),
+        (_, false) => Err(Error::Expected { offset: key.len() - after.len(), expected: "'='" }),
+    }
+}
+
+/// Checks that `value` can be written with `quote` and read back unchanged,
+/// as `encode_value` would write it. Offsets are relative to the start of
+/// `value`; double quotes can represent any value.
+pub fn validate_value_for(quote: QuoteType, value: &str) -> Result<(), Error> {
+    let invalid = |offset: usize, message: &str| Err(Error::Generic { offset, message: message.into() });
+    match quote {
+        QuoteType::None => {
+            if value.starts_with('=') {
+                return Err(Error::DoubleEquals { offset: 0 });
+            }
+            if value.starts_with(['\'', '"']) {
+                return invalid(0, "Unquoted value starts with a quote");
+            }
+            if let Some(i) = value.find([' ', '\t', '\n', '\r']) {
+                return invalid(i, "Unquoted value contains whitespace");
+            }
+            if value.ends_with('\\') {
+                return invalid(value.len() - 1, "Unquoted value ends with a line continuation");
+            }
+            Ok(())
+        },
+        QuoteType::Single => match value.find(['\'', '\n', '\r']) {
+            Some(i) if value[i..].starts_with('\'') => invalid(i, "Single-quoted value contains a single quote"),
+            Some(i) => invalid(i, "Single-quoted value contains a line break"),
+            None => Ok(()),
+        },
+        QuoteType::Double => Ok(()),
+    }
+}
+
+/// Why the non-empty run of key characters `key` cannot start a key, e.g.
+/// a digit or, with `unicode_keys`, a combining mark.
+pub(crate) fn key_start_error(key: &str, unicode_keys: bool) -> Option<InvalidKeyReason> {
+    match key.chars().next() {
+        Some(c) if !is_key_start(c, unicode_keys) && c.is_numeric() => Some(InvalidKeyReason::StartsWithDigit),
+        Some(c) if !is_key_start(c, unicode_keys) => Some(InvalidKeyReason::NotAKey),
+        _ => None,
+    }
+}
+
+/// Whether `value` reads back unchanged without quotes: no whitespace or
+/// line breaks, no leading quote or `=`, and no trailing backslash, which
+/// would continue the line.
+pub fn can_be_unquoted(value: &str) -> bool {
+    !value.bytes().any(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+        && !value.starts_with(['\'', '"', '='])
+        && !value.ends_with('\\')
+}
+
+/// Whether `value` can be written in single quotes, which take everything
+/// literally: it must contain no `'` and no line breaks.
+pub fn can_be_single_quoted(value: &str) -> bool {
+    !value.contains(['\'', '\n', '\r'])
+}
@@ -0,0 +1,239 @@
+use std::borrow::{Borrow, Cow};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use crate::types::KeyValuePair;
+
+/// A pair hashed and compared by its key, so that a set of them is a map
+/// from key to pair that needs no key of its own per pair. Boxed as parsed
+/// entries are, so pairs move in without being copied.
+#[derive(Debug, Clone)]
+struct ByKey<'a>(Box<KeyValuePair<'a>>);
+
+impl Hash for ByKey<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.key.as_ref().hash(state);
+    }
+}
+
+impl PartialEq for ByKey<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.key == other.0.key
+    }
+}
+
+impl Eq for ByKey<'_> {}
+
+impl Borrow<str> for ByKey<'_> {
+    fn borrow(&self) -> &str {
+        &self.0.key
+    }
+}
+
+/// Pairs defined in one layer, and keys it removes from the layers below.
+/// A key is never in both.
+#[derive(Debug, Clone, Default)]
+struct Layer<'a> {
+    pairs: HashSet<ByKey<'a>>,
+    removed: HashSet<Cow<'a, str>>,
+}
+
+impl<'a> Layer<'a> {
+    fn is_empty(&self) -> bool {
+        self.pairs.is_empty() && self.removed.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.pairs.len() + self.removed.len()
+    }
+
+    /// `Some(None)` if this layer removes `key`, `None` if it leaves it to
+    /// the layers below.
+    fn lookup(&self, key: &str) -> Option<Option<&KeyValuePair<'a>>> {
+        match self.pairs.get(key) {
+            Some(pair) => Some(Some(&*pair.0)),
+            None => self.removed.contains(key).then_some(None),
+        }
+    }
+
+    fn insert(&mut self, pair: Box<KeyValuePair<'a>>) {
+        if !self.removed.is_empty() {
+            self.removed.remove(pair.key.as_ref());
+        }
+        self.pairs.replace(ByKey(pair));
+    }
+
+    /// Removes `key` from this layer and hides it in `below`, returning
+    /// the pair it had.
+    fn remove(&mut self, key: &str, below: Option<&Shared<'a>>) -> Option<KeyValuePair<'a>> {
+        let below = below.and_then(|shared| shared.get(key));
+        let current = match self.lookup(key) {
+            Some(None) => return None,
+            Some(Some(_)) => self.pairs.take(key).map(|pair| *pair.0),
+            None => below.cloned(),
+        };
+        if below.is_some() {
+            self.removed.insert(Cow::Owned(key.to_string()));
+        }
+        current
+    }
+}
+
+/// An immutable layer shared between tables, over its own parent layers.
+#[derive(Debug, Default)]
+struct Shared<'a> {
+    layer: Layer<'a>,
+    parent: Option<Arc<Shared<'a>>>,
+}
+
+impl<'a> Shared<'a> {
+    fn get(&self, key: &str) -> Option<&KeyValuePair<'a>> {
+        let mut shared = self;
+        loop {
+            if let Some(found) = shared.layer.lookup(key) {
+                return found;
+            }
+            shared = shared.parent.as_deref()?;
+        }
+    }
+}
+
+/// The pairs of an `Environment`: shared, immutable layers plus a small
+/// overlay of changes made since, so that cloning an environment copies no
+/// pairs. Changes go straight to the layer below while no other table
+/// shares it. A full overlay is merged into that layer once it is no longer
+/// shared, and otherwise becomes a shared layer of its own.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PairTable<'a> {
+    base: Arc<Shared<'a>>,
+    overlay: Layer<'a>,
+}
+
+impl<'a> PairTable<'a> {
+    pub(crate) fn with_capacity(capacity: usize) -> Self {
+        let layer = Layer { pairs: HashSet::with_capacity(capacity), removed: HashSet::new() };
+        Self { base: Arc::new(Shared { layer, parent: None }), overlay: Layer::default() }
+    }
+
+    /// Keeps the last pair of each key.
+    pub(crate) fn from_pairs(pairs: impl IntoIterator<Item = KeyValuePair<'a>>) -> Self {
+        let mut table = Self::default();
+        for pair in pairs {
+            table.insert(pair);
+        }
+        table
+    }
+
+    /// A table over this one's current pairs that records its changes on
+    /// its own; only this table's overlay is copied.
+    pub(crate) fn layer(&self) -> Self {
+        let base = if self.overlay.is_empty() {
+            Arc::clone(&self.base)
+        } else {
+            Arc::new(Shared { layer: self.overlay.clone(), parent: Some(Arc::clone(&self.base)) })
+        };
+        Self { base, overlay: Layer::default() }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&KeyValuePair<'a>> {
+        match self.overlay.lookup(key) {
+            Some(found) => found,
+            None => self.base.get(key),
+        }
+    }
+
+    /// Adds `pair` under its key, replacing any pair with the same key.
+    pub(crate) fn insert(&mut self, pair: KeyValuePair<'a>) {
+        self.insert_boxed(Box::new(pair));
+    }
+
+    pub(crate) fn insert_boxed(&mut self, pair: Box<KeyValuePair<'a>>) {
+        match Arc::get_mut(&mut self.base) {
+            Some(shared) if self.overlay.is_empty() => shared.layer.insert(pair),
+            _ => {
+                self.overlay.insert(pair);
+                self.settle();
+            },
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &str) -> Option<KeyValuePair<'a>> {
+        if self.overlay.is_empty() {
+            if let Some(shared) = Arc::get_mut(&mut self.base) {
+                return shared.layer.remove(key, shared.parent.as_deref());
+            }
+        }
+        let removed = self.overlay.remove(key, Some(&self.base));
+        self.settle();
+        removed
+    }
+
+    /// Moves a full overlay down into the shared layers.
+    fn settle(&mut self) {
+        if self.overlay.len() <= (self.base.layer.len() / 8).max(32) {
+            return;
+        }
+        let overlay = std::mem::take(&mut self.overlay);
+        match Arc::get_mut(&mut self.base) {
+            Some(shared) => {
+                for key in overlay.removed {
+                    shared.layer.pairs.remove(key.as_ref());
+                    if shared.parent.is_some() {
+                        shared.layer.removed.insert(key);
+                    }
+                }
+                for pair in overlay.pairs {
+                    shared.layer.insert(pair.0);
+                }
+            },
+            None => {
+                let parent = Arc::clone(&self.base);
+                self.base = Arc::new(Shared { layer: overlay, parent: Some(parent) });
+            },
+        }
+    }
+
+    fn layers(&self) -> Vec<&Layer<'a>> {
+        let mut layers = vec![&self.overlay];
+        let mut shared = Some(&*self.base);
+        while let Some(current) = shared {
+            layers.push(&current.layer);
+            shared = current.parent.as_deref();
+        }
+        layers.retain(|layer| !layer.is_empty());
+        layers
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        match self.layers().as_slice() {
+            [] => 0,
+            [layer] => layer.pairs.len(),
+            _ => self.values().count(),
+        }
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &KeyValuePair<'a>> {
+        let layers = self.layers();
+        // A key in an upper layer hides the same key below it
+        let layered = layers.len() > 1;
+        let mut seen = HashSet::new();
+        layers.into_iter()
+            .flat_map(|layer| {
+                let removed = layer.removed.iter().map(|key| (key.as_ref(), None));
+                removed.chain(layer.pairs.iter().map(|pair| (pair.0.key.as_ref(), Some(&*pair.0))))
+            })
+            .filter(move |(key, _)| !layered || seen.insert(*key))
+            .filter_map(|(_, pair)| pair)
+    }
+
+    /// The pairs, copied only if they are shared.
+    pub(crate) fn into_pairs(self) -> Vec<KeyValuePair<'a>> {
+        if !self.overlay.is_empty() || self.base.parent.is_some() {
+            return self.values().cloned().collect();
+        }
+        match Arc::try_unwrap(self.base) {
+            Ok(shared) => shared.layer.pairs.into_iter().map(|pair| *pair.0).collect(),
+            Err(shared) => shared.layer.pairs.iter().map(|pair| (*pair.0).clone()).collect(),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Helpers for tests of code that reads the environment (`test-util`
+//! feature).
+
+use std::cell::Cell;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, PoisonError};
+use crate::env::Environment;
+use crate::loader::Korni;
+use crate::types::{ParseOptions, QuoteType};
+use crate::writer::{encode_value, CommentPolicy, WriteOptions};
+
+/// Held by `with_env` so concurrently running tests don't see each other's
+/// variables.
+static PROCESS_ENV: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    static HOLDS_LOCK: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Variables `with_env` can apply: `.env` text, or an `Environment`'s active
+/// pairs.
+pub trait TestVars {
+    fn test_vars(&self) -> Vec<(String, String)>;
+}
+
+impl TestVars for &str {
+    /// Lines that fail to parse are ignored.
+    fn test_vars(&self) -> Vec<(String, String)> {
+        Korni::from_str(self).parse().map(|env| (&env).test_vars()).unwrap_or_default()
+    }
+}
+
+impl TestVars for &Environment<'_> {
+    fn test_vars(&self) -> Vec<(String, String)> {
+        self.iter_ordered()
+            .filter(|kv| !kv.is_comment)
+            .map(|kv| (kv.key.to_string(), kv.value.to_string()))
+            .collect()
+    }
+}
+
+/// Sets `vars` in the process environment, runs `f`, then restores every
+/// touched variable to its previous value (or removes it), even if `f`
+/// panics.
+///
+/// Calls are serialized by a process-wide lock, and may be nested. Only
+/// code that goes through `with_env` is serialized: direct `std::env`
+/// access from other tests can still race.
+pub fn with_env<R>(vars: impl TestVars, f: impl FnOnce() -> R) -> R {
+    let _lock = match HOLDS_LOCK.get() {
+        true => None,
+        false => Some(PROCESS_ENV.lock().unwrap_or_else(PoisonError::into_inner)),
+    };
+    let _restore = Restore { saved: Vec::new(), owns_lock: _lock.is_some() }.apply(vars.test_vars());
+    f()
+}
+
+struct Restore {
+    saved: Vec<(String, Option<OsString>)>,
+    owns_lock: bool,
+}
+
+impl Restore {
+    fn apply(mut self, vars: Vec<(String, String)>) -> Self {
+        HOLDS_LOCK.set(true);
+        for (key, value) in vars {
+            if !self.saved.iter().any(|(k, _)| *k == key) {
+                self.saved.push((key.clone(), std::env::var_os(&key)));
+            }
+            std::env::set_var(key, value);
+        }
+        self
+    }
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        for (key, value) in self.saved.drain(..).rev() {
+            match value {
+                Some(value) => std::env::set_var(key, value),
+                None => std::env::remove_var(key),
+            }
+        }
+        if self.owns_lock {
+            HOLDS_LOCK.set(false);
+        }
+    }
+}
+
+/// A `.env` file in its own temporary directory, removed with the directory
+/// when dropped.
+///
+/// ```no_run
+/// # use korni::test::EnvFile;
+/// let file = EnvFile::builder().comment("database").key("DB_URL", "postgres://x").create();
+/// let env = korni::Korni::from_file(file.path()).parse().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct EnvFile {
+    dir: PathBuf,
+    path: PathBuf,
+}
+
+impl EnvFile {
+    pub fn builder() -> EnvFileBuilder {
+        EnvFileBuilder { name: ".env".to_string(), content: String::new() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The temporary directory holding the file, e.g. for `Korni::cascade`.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl Drop for EnvFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Builds the content of an `EnvFile`, one line per call.
+#[derive(Debug, Clone)]
+pub struct EnvFileBuilder {
+    name: String,
+    content: String,
+}
+
+impl EnvFileBuilder {
+    /// Adds `KEY=value`, quoting and escaping the value as needed.
+    pub fn key(self, key: &str, value: &str) -> Self {
+        let (_, encoded) = encode_value(value, QuoteType::None);
+        self.line(&format!("{}={}", key, encoded))
+    }
+
+    /// Adds `# text`.
+    pub fn comment(self, text: &str) -> Self {
+        self.line(&format!("# {}", text))
+    }
+
+    /// Adds `text` verbatim, e.g. a malformed line.
+    pub fn line(mut self, text: &str) -> Self {
+        self.content.push_str(text);
+        self.content.push('\n');
+        self
+    }
+
+    /// The file name; `.env` by default.
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    /// Writes the file. Panics if it cannot be written.
+    pub fn create(self) -> EnvFile {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!("korni-fixture-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::Relaxed)));
+        let path = dir.join(&self.name);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, &self.content))
+            .unwrap_or_else(|e| panic!("failed to write fixture {}: {}", path.display(), e));
+        EnvFile { dir, path }
+    }
+}
+
+/// A parser and writer configuration, as checked by
+/// `dialect_roundtrip_check`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Dialect {
+    pub parse: ParseOptions,
+    pub write: WriteOptions,
+}
+
+/// Parses `input` with `dialect`, writes it back out and parses that again,
+/// panicking unless both parses define the same pairs (key, value and
+/// whether they are commented out) and writing the second gives the same
+/// text. Quoting and layout may change; values may not. Commented-out
+/// pairs are only compared when the dialect writes them
+/// (`CommentPolicy::Keep`).
+pub fn dialect_roundtrip_check(dialect: Dialect, input: &str) {
+    let first = Environment::from_entries(crate::parse_with_options(input, dialect.parse)).with_source(input);
+    let written = first.display_with(dialect.write).to_string();
+    let second = Environment::from_entries(crate::parse_with_options(&written, dialect.parse)).with_source(written.as_str());
+
+    let keep_commented = dialect.write.comments == CommentPolicy::Keep;
+    let (before, after) = (pairs(&first, keep_commented), pairs(&second, keep_commented));
+    if before != after {
+        let changed = before.iter().zip(&after).find(|(a, b)| a != b);
+        panic!(
+            "round trip changed the pairs of {:?}{}\nwritten as:\n{}",
+            input,
+            match changed {
+                Some((a, b)) => format!(": {:?} became {:?}", a, b),
+                None => format!(": {} pairs became {}", before.len(), after.len()),
+            },
+            written,
+        );
+    }
+
+    let rewritten = second.display_with(dialect.write).to_string();
+    if rewritten != written {
+        panic!("writing {:?} is not stable:\nfirst:\n{}\nthen:\n{}", input, written, rewritten);
+    }
+}
+
+fn pairs<'e>(env: &'e Environment<'_>, keep_commented: bool) -> Vec<(&'e str, &'e str, bool)> {
+    let mut pairs: Vec<_> = env.iter()
+        .filter(|kv| keep_commented || !kv.is_comment)
+        .map(|kv| (kv.key.as_ref(), kv.value.as_ref(), kv.is_comment))
+        .collect();
+    pairs.sort();
+    pairs
+}
@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+use crate::env::Environment;
+
+/// One `TrackedEnvironment::get` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Access {
+    pub key: String,
+    pub at: SystemTime,
+    /// Whether the key was defined.
+    pub hit: bool,
+}
+
+/// An `Environment` that records every lookup made through it, to find out
+/// which keys an application actually reads. Values are never recorded.
+///
+/// Lookups take `&self` and the log is behind a mutex, so a tracked
+/// environment can be shared between threads.
+#[derive(Debug, Default)]
+pub struct TrackedEnvironment<'a> {
+    env: Environment<'a>,
+    accesses: Mutex<Vec<Access>>,
+}
+
+impl<'a> TrackedEnvironment<'a> {
+    pub fn new(env: Environment<'a>) -> Self {
+        Self { env, accesses: Mutex::new(Vec::new()) }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let value = self.env.get(key);
+        self.accesses.lock().unwrap_or_else(PoisonError::into_inner).push(Access {
+            key: key.to_string(),
+            at: SystemTime::now(),
+            hit: value.is_some(),
+        });
+        value
+    }
+
+    pub fn get_or<'b>(&'b self, key: &str, default: &'b str) -> &'b str {
+        self.get(key).unwrap_or(default)
+    }
+
+    /// The wrapped environment; lookups made on it are not recorded.
+    pub fn inner(&self) -> &Environment<'a> {
+        &self.env
+    }
+
+    pub fn into_inner(self) -> Environment<'a> {
+        self.env
+    }
+
+    /// Every lookup so far, oldest first.
+    pub fn accesses(&self) -> Vec<Access> {
+        self.accesses.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    pub fn clear(&self) {
+        self.accesses.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+
+    pub fn report(&self) -> AccessReport {
+        let accesses = self.accesses();
+        let read: BTreeSet<&str> = accesses.iter().map(|a| a.key.as_str()).collect();
+        let mut unused: Vec<String> = self.env.iter()
+            .filter(|kv| !kv.is_comment && !read.contains(kv.key.as_ref()))
+            .map(|kv| kv.key.to_string())
+            .collect();
+        unused.sort_unstable();
+        let used = accesses.iter().filter(|a| a.hit).map(|a| a.key.clone()).collect::<BTreeSet<_>>();
+        let missing = accesses.iter().filter(|a| !a.hit).map(|a| a.key.clone()).collect::<BTreeSet<_>>();
+        AccessReport {
+            used: used.into_iter().collect(),
+            missing: missing.into_iter().collect(),
+            unused,
+            accesses,
+        }
+    }
+}
+
+/// Summary of the lookups made on a `TrackedEnvironment`. Key lists are
+/// sorted and free of duplicates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessReport {
+    /// Defined keys that were read.
+    pub used: Vec<String>,
+    /// Keys that were read but are not defined.
+    pub missing: Vec<String>,
+    /// Defined keys that were never read.
+    pub unused: Vec<String>,
+    pub accesses: Vec<Access>,
+}
@@ -1,15 +1,93 @@
 use std::borrow::Cow;
 use std::ops::Range;
-use crate::error::Error;
+use crate::error::{Deprecation, Error};
+use crate::date::Date;
+use crate::line_index::LineIndex;
 
 // ==================================================================================
 //  Configuration
 // ==================================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct ParseOptions {
     pub include_comments: bool,
     pub track_positions: bool,
+    /// Emit `Error::DuplicateKey` warnings when a key is defined more than once.
+    pub warn_duplicate_keys: bool,
+    /// Emit `Error::TruncatedValue` warnings when an unquoted value is cut
+    /// off by whitespace, as in `KEY=hello world`.
+    pub warn_truncated_values: bool,
+    /// Reject lines using the `export` prefix with `Error::ExportForbidden`.
+    pub forbid_export: bool,
+    /// Bytes that start a comment line. Defaults to `#` only; INI-flavored
+    /// files can use `b"#;"`.
+    pub comment_chars: &'static [u8],
+    /// Accept Unicode identifier keys: `_` or an `XID_Start` character, then
+    /// `XID_Continue` characters, so combining marks and non-ASCII digits
+    /// may follow the first character. When disabled, non-ASCII key
+    /// characters are an error.
+    pub unicode_keys: bool,
+    /// Let unquoted values run to the end of the line (minus trailing
+    /// whitespace and an inline comment), as most dotenv implementations
+    /// do, instead of stopping at the first whitespace.
+    pub unquoted_to_eol: bool,
+    /// Trim leading and trailing whitespace inside quoted values.
+    pub trim_quoted_values: bool,
+    /// Turn `\r\n` line breaks inside quoted values into `\n`.
+    pub normalize_newlines: bool,
+    /// Drop one trailing line break from quoted values, e.g. the newline
+    /// before the closing quote of a PEM block.
+    pub strip_trailing_newline: bool,
+    /// Cut values longer than this many bytes (at a character boundary),
+    /// setting `KeyValuePair::truncated` and emitting an
+    /// `Error::ValueTooLong` warning.
+    pub max_value_len: Option<usize>,
+    /// Constructs to report with `Error::Deprecated` warnings.
+    pub deprecations: Deprecations,
+}
+
+/// Allowed but discouraged constructs, each reported as an
+/// `Error::Deprecated` warning when enabled. All are off by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Deprecations {
+    /// Text after a closing quote, as in `KEY="value" junk`.
+    pub trailing_content: bool,
+    /// Commented-out pairs, as in `# KEY=value`.
+    pub commented_pairs: bool,
+    /// The `export` prefix.
+    pub export_prefix: bool,
+}
+
+impl Deprecations {
+    pub const ALL: Deprecations = Deprecations { trailing_content: true, commented_pairs: true, export_prefix: true };
+
+    pub fn is_enabled(&self, rule: Deprecation) -> bool {
+        match rule {
+            Deprecation::TrailingContent => self.trailing_content,
+            Deprecation::CommentedPair => self.commented_pairs,
+            Deprecation::ExportPrefix => self.export_prefix,
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            include_comments: false,
+            track_positions: false,
+            warn_duplicate_keys: false,
+            warn_truncated_values: false,
+            forbid_export: false,
+            comment_chars: b"#",
+            unicode_keys: false,
+            unquoted_to_eol: false,
+            trim_quoted_values: false,
+            normalize_newlines: false,
+            strip_trailing_newline: false,
+            max_value_len: None,
+            deprecations: Deprecations::default(),
+        }
+    }
 }
 
 impl ParseOptions {
@@ -21,15 +99,27 @@ impl ParseOptions {
         Self {
             include_comments: true,
             track_positions: true,
+            ..Self::default()
         }
     }
 }
 
+/// Which definition of a repeated key a lookup should return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Occurrence {
+    /// Stop scanning at the first definition.
+    #[default]
+    First,
+    /// Scan the whole input and return the last definition (later-wins semantics).
+    Last,
+}
+
 // ==================================================================================
 //  Position & Spans
 // ==================================================================================
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Position {
     pub line: usize,
     pub col: usize,
@@ -44,6 +134,7 @@ impl Position {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Span {
     pub start: Position,
     pub end: Position,
@@ -81,6 +172,7 @@ impl Span {
 // ==================================================================================
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum QuoteType {
     Single, // '
     Double, // "
@@ -88,37 +180,196 @@ pub enum QuoteType {
     None,   // No quotes
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Comment {
+    /// The whole comment, starting at the comment character.
+    pub span: Span,
+    /// The comment text, after the comment character and leading whitespace.
+    pub content_span: Span,
+}
+
+impl Comment {
+    pub fn content<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.content_span.range()]
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Entry<'a> {
-    Comment(Span),
+    Comment(Comment),
     Pair(Box<KeyValuePair<'a>>),
     Error(Error),
+    Warning(Error),
 }
 
 impl<'a> Entry<'a> {
-    pub fn as_pair(&self) -> Option<&KeyValuePair> {
+    pub fn as_pair(&self) -> Option<&KeyValuePair<'a>> {
         match self {
             Entry::Pair(kv) => Some(kv),
             _ => None,
         }
     }
 
+    pub fn into_pair(self) -> Option<KeyValuePair<'a>> {
+        match self {
+            Entry::Pair(kv) => Some(*kv),
+            _ => None,
+        }
+    }
+
+    pub fn as_comment(&self) -> Option<&Comment> {
+        match self {
+            Entry::Comment(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    pub fn as_error(&self) -> Option<&Error> {
+        match self {
+            Entry::Error(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn as_warning(&self) -> Option<&Error> {
+        match self {
+            Entry::Warning(w) => Some(w),
+            _ => None,
+        }
+    }
+
+    pub fn is_pair(&self) -> bool {
+        matches!(self, Entry::Pair(_))
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self, Entry::Comment(_))
+    }
+
+    pub fn is_error(&self) -> bool {
+        matches!(self, Entry::Error(_))
+    }
+
+    pub fn is_warning(&self) -> bool {
+        matches!(self, Entry::Warning(_))
+    }
+
+    /// The key of a pair entry (active or commented-out).
+    pub fn key(&self) -> Option<&str> {
+        self.as_pair().map(|kv| kv.key.as_ref())
+    }
+
+    pub(crate) fn resolve_positions(&mut self, index: &LineIndex) {
+        match self {
+            Entry::Pair(kv) => kv.resolve_positions(index),
+            Entry::Comment(c) => {
+                index.resolve_span(&mut c.span);
+                index.resolve_span(&mut c.content_span);
+            },
+            Entry::Error(e) | Entry::Warning(e) => match e {
+                Error::DuplicateKey { first_span, second_span, .. } => {
+                    index.resolve_span(first_span);
+                    index.resolve_span(second_span);
+                },
+                Error::TruncatedValue { dropped, .. } => index.resolve_span(dropped),
+                _ => {},
+            },
+        }
+    }
+
+    /// Calls `f` on every position the entry carries, including those of a
+    /// `DuplicateKey` or `TruncatedValue` diagnostic. Error offsets are not positions; see
+    /// `Error::shift`.
+    pub(crate) fn for_each_position(&mut self, mut f: impl FnMut(&mut Position)) {
+        let spans: Vec<&mut Span> = match self {
+            Entry::Pair(kv) => {
+                for pos in [&mut kv.open_quote_pos, &mut kv.close_quote_pos, &mut kv.equals_pos].into_iter().flatten() {
+                    f(pos);
+                }
+                [&mut kv.key_span, &mut kv.value_span, &mut kv.raw_span, &mut kv.content_span].into_iter().flatten().collect()
+            },
+            Entry::Comment(c) => vec![&mut c.span, &mut c.content_span],
+            Entry::Error(Error::DuplicateKey { first_span, second_span, .. })
+            | Entry::Warning(Error::DuplicateKey { first_span, second_span, .. }) => vec![first_span, second_span],
+            Entry::Error(Error::TruncatedValue { dropped, .. })
+            | Entry::Warning(Error::TruncatedValue { dropped, .. }) => vec![dropped],
+            Entry::Error(_) | Entry::Warning(_) => Vec::new(),
+        };
+        for span in spans {
+            f(&mut span.start);
+            f(&mut span.end);
+        }
+    }
+
     pub fn into_owned(self) -> Entry<'static> {
         match self {
             Entry::Pair(kv) => Entry::Pair(Box::new(kv.into_owned())),
-            Entry::Comment(span) => Entry::Comment(span),
+            Entry::Comment(c) => Entry::Comment(c),
             Entry::Error(e) => Entry::Error(e),
+            Entry::Warning(w) => Entry::Warning(w),
+        }
+    }
+}
+
+/// Metadata from a `# @name: value` comment, attached to the next pair.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Annotation<'a> {
+    pub name: Cow<'a, str>,
+    pub value: Cow<'a, str>,
+}
+
+impl Annotation<'_> {
+    pub fn into_owned(self) -> Annotation<'static> {
+        Annotation {
+            name: Cow::Owned(self.name.into_owned()),
+            value: Cow::Owned(self.value.into_owned()),
         }
     }
 }
 
+/// Filtering helpers for collected entries, e.g. the output of `parse()`.
+pub trait EntriesExt<'a> {
+    /// Every pair, including commented-out ones.
+    fn pairs<'s>(&'s self) -> impl Iterator<Item = &'s KeyValuePair<'a>> where 'a: 's;
+    /// Only active pairs, skipping commented-out ones.
+    fn ok_pairs<'s>(&'s self) -> impl Iterator<Item = &'s KeyValuePair<'a>> where 'a: 's;
+    fn errors(&self) -> impl Iterator<Item = &Error>;
+}
+
+impl<'a> EntriesExt<'a> for [Entry<'a>] {
+    fn pairs<'s>(&'s self) -> impl Iterator<Item = &'s KeyValuePair<'a>> where 'a: 's {
+        self.iter().filter_map(Entry::as_pair)
+    }
+
+    fn ok_pairs<'s>(&'s self) -> impl Iterator<Item = &'s KeyValuePair<'a>> where 'a: 's {
+        self.pairs().filter(|kv| !kv.is_comment)
+    }
+
+    fn errors(&self) -> impl Iterator<Item = &Error> {
+        self.iter().filter_map(Entry::as_error)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct KeyValuePair<'a> {
     pub key: Cow<'a, str>,
     pub key_span: Option<Span>,
 
     pub value: Cow<'a, str>,
     pub value_span: Option<Span>,
+    /// The value as written, including quotes.
+    pub raw_span: Option<Span>,
+    /// The value as written, excluding quotes.
+    pub content_span: Option<Span>,
+    /// Length in bytes of the decoded `value`, before any `max_value_len`
+    /// truncation.
+    pub decoded_len: usize,
+    /// Whether `value` was cut to `ParseOptions::max_value_len`.
+    pub truncated: bool,
 
     pub quote: QuoteType,
     pub open_quote_pos: Option<Position>,
@@ -127,6 +378,12 @@ pub struct KeyValuePair<'a> {
 
     pub is_exported: bool,
     pub is_comment: bool,
+
+    /// Position of this pair's entry in the parser's output sequence.
+    pub index: usize,
+
+    /// `# @name: value` comment lines directly preceding the pair.
+    pub annotations: Vec<Annotation<'a>>,
 }
 
 impl<'a> KeyValuePair<'a> {
@@ -141,14 +398,20 @@ impl<'a> KeyValuePair<'a> {
         Self {
             key: key.into(),
             key_span: None,
+            decoded_len: value.len(),
+            truncated: false,
             value,
             value_span: None,
+            raw_span: None,
+            content_span: None,
             quote,
             open_quote_pos: None,
             close_quote_pos: None,
             equals_pos: None,
             is_exported,
             is_comment,
+            index: 0,
+            annotations: Vec::new(),
         }
     }
 
@@ -166,18 +429,140 @@ impl<'a> KeyValuePair<'a> {
     ) -> Self {
         let key_end = key_start + key.len();
         let value_end = value_start + raw_len;
+        let quote_len = if quote != QuoteType::None { 1 } else { 0 };
 
         Self {
             key: Cow::Borrowed(key),
             key_span: Some(Span::from_offsets(key_start, key_end)),
+            decoded_len: value.len(),
+            truncated: false,
             value,
             value_span: Some(Span::from_offsets(value_start, value_end)),
+            raw_span: Some(Span::from_offsets(value_start, value_end)),
+            content_span: Some(Span::from_offsets(value_start + quote_len, value_end - quote_len)),
             quote,
             open_quote_pos: if quote != QuoteType::None { Some(Position::from_offset(value_start)) } else { None },
             close_quote_pos: if quote != QuoteType::None { Some(Position::from_offset(value_end - 1)) } else { None },
             equals_pos: Some(Position::from_offset(key_end)), // '=' is right after key
             is_exported,
             is_comment,
+            index: 0,
+            annotations: Vec::new(),
+        }
+    }
+
+    pub fn annotation(&self, name: &str) -> Option<&str> {
+        self.annotations.iter()
+            .find(|a| a.name == name)
+            .map(|a| a.value.as_ref())
+    }
+
+    /// The `@rotate-by` annotation, if present and a valid `YYYY-MM-DD` date.
+    pub fn rotate_by(&self) -> Option<Date> {
+        self.annotation("rotate-by")?.parse().ok()
+    }
+
+    /// Drops every span and position, as when parsing without
+    /// `track_positions`.
+    pub(crate) fn clear_positions(&mut self) {
+        self.key_span = None;
+        self.value_span = None;
+        self.raw_span = None;
+        self.content_span = None;
+        self.open_quote_pos = None;
+        self.close_quote_pos = None;
+        self.equals_pos = None;
+    }
+
+    pub(crate) fn resolve_positions(&mut self, index: &LineIndex) {
+        for span in [&mut self.key_span, &mut self.value_span, &mut self.raw_span, &mut self.content_span].into_iter().flatten() {
+            index.resolve_span(span);
+        }
+        for pos in [&mut self.open_quote_pos, &mut self.close_quote_pos, &mut self.equals_pos].into_iter().flatten() {
+            index.resolve(pos);
+        }
+    }
+
+    /// Maps a byte offset inside the decoded `value` back to a byte offset in
+    /// `source`, accounting for escapes, line continuations and the
+    /// `trim_quoted_values`, `normalize_newlines` and `strip_trailing_newline`
+    /// options.
+    ///
+    /// Requires positions to have been tracked.
+    pub fn source_offset(&self, source: &str, decoded_offset: usize) -> Option<usize> {
+        let content = self.content_span?;
+        let raw = source.as_bytes().get(content.range())?;
+        // The content decoded before any option applied, and the offset in
+        // `raw` each of its bytes came from
+        let mut decoded = Vec::with_capacity(raw.len());
+        let mut offsets = Vec::with_capacity(raw.len());
+        let mut src = 0;
+
+        while src < raw.len() {
+            let b = raw[src];
+            let next = raw.get(src + 1).copied();
+            let escape = next.filter(|_| self.quote == QuoteType::Double && b == b'\\').and_then(|n| crate::spec::unescape(n as char));
+            match (self.quote, b, next) {
+                _ if escape.is_some() => {
+                    decoded.push(escape? as u8);
+                    offsets.push(src);
+                    src += 2;
+                },
+                (QuoteType::None, b'\\', Some(b'\r')) if raw.get(src + 2) == Some(&b'\n') => src += 3,
+                (QuoteType::None, b'\\', Some(b'\r' | b'\n')) => src += 2,
+                _ => {
+                    let end = (src + utf8_len(b)).min(raw.len());
+                    decoded.extend_from_slice(&raw[src..end]);
+                    offsets.extend(src..end);
+                    src = end;
+                },
+            }
+        }
+
+        // Walk the value along the decoded content, skipping what trimming
+        // dropped in front and the `\r` of each normalized `\r\n`; what was
+        // dropped at the end is never reached
+        if decoded_offset > self.value.len() {
+            return None;
+        }
+        let full = std::str::from_utf8(&decoded).ok()?;
+        let mut i = if self.value.starts_with(char::is_whitespace) { 0 } else { full.len() - full.trim_start().len() };
+        for &b in &self.value.as_bytes()[..decoded_offset] {
+            if decoded.get(i) == Some(&b'\r') && b == b'\n' && decoded.get(i + 1) == Some(&b'\n') {
+                i += 1;
+            }
+            if decoded.get(i) != Some(&b) {
+                return None;
+            }
+            i += 1;
+        }
+        Some(offsets.get(i).map_or(content.end.offset, |offset| content.start.offset + offset))
+    }
+
+    /// The span in the source of bytes `range` of the decoded value.
+    ///
+    /// With the source and its index, positions are exact: escapes, line
+    /// continuations and the options that trim or normalize quoted values
+    /// are all accounted for (see `source_offset`). Without them, only a
+    /// value written exactly as it decodes can be mapped, and only up to its
+    /// first line break. Requires positions to have been tracked.
+    pub fn decoded_span(&self, range: Range<usize>, source: Option<(&str, &LineIndex)>) -> Option<Span> {
+        match source {
+            Some((source, index)) => {
+                let start = self.source_offset(source, range.start)?;
+                let end = self.source_offset(source, range.end)?;
+                Some(index.span(Span::from_offsets(start, end)))
+            },
+            None => {
+                // Decoding and every option only ever shorten a value, so one
+                // as long as its content was written exactly as it decodes
+                let content = self.content_span.filter(|span| span.len() == self.value.len())?;
+                if self.value.get(..range.end)?.contains(['\n', '\r']) {
+                    return None;
+                }
+                let at = |i: usize| Position { col: content.start.col + i, offset: content.start.offset + i, ..content.start };
+                Some(Span::new(at(range.start), at(range.end)))
+            },
         }
     }
 
@@ -187,12 +572,28 @@ impl<'a> KeyValuePair<'a> {
             key_span: self.key_span,
             value: Cow::Owned(self.value.into_owned()),
             value_span: self.value_span,
+            raw_span: self.raw_span,
+            content_span: self.content_span,
+            decoded_len: self.decoded_len,
+            truncated: self.truncated,
             quote: self.quote,
             open_quote_pos: self.open_quote_pos,
             close_quote_pos: self.close_quote_pos,
             equals_pos: self.equals_pos,
             is_exported: self.is_exported,
             is_comment: self.is_comment,
+            index: self.index,
+            annotations: self.annotations.into_iter().map(Annotation::into_owned).collect(),
         }
     }
 }
+
+#[inline]
+fn utf8_len(first_byte: u8) -> usize {
+    match first_byte {
+        0xF0..=0xFF => 4,
+        0xE0..=0xEF => 3,
+        0xC0..=0xDF => 2,
+        _ => 1,
+    }
+}
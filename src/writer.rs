@@ -0,0 +1,231 @@
+use std::fmt;
+use crate::env::Environment;
+use crate::spec::{self, can_be_single_quoted, can_be_unquoted};
+use crate::types::{Entry, KeyValuePair, QuoteType};
+
+/// Encodes `value` so that parsing it back yields the same string.
+///
+/// The `preferred` quote style is kept whenever it can represent the value.
+/// Otherwise an unquoted value escalates to single quotes if it needs no
+/// escapes, and anything else to double quotes.
+pub fn encode_value(value: &str, preferred: QuoteType) -> (QuoteType, String) {
+    let quote = match preferred {
+        QuoteType::None => minimal_quote(value),
+        QuoteType::Single if can_be_single_quoted(value) => QuoteType::Single,
+        _ => QuoteType::Double,
+    };
+    let encoded = match quote {
+        QuoteType::None => value.to_string(),
+        QuoteType::Single => format!("'{}'", value),
+        QuoteType::Double => double_quote(value),
+    };
+    (quote, encoded)
+}
+
+/// The most readable quote style that can represent `value`: none if it
+/// needs no quoting, single quotes if it contains no `'` or line breaks,
+/// and double quotes with escapes otherwise.
+pub fn minimal_quote(value: &str) -> QuoteType {
+    if can_be_unquoted(value) {
+        QuoteType::None
+    } else if can_be_single_quoted(value) {
+        QuoteType::Single
+    } else {
+        QuoteType::Double
+    }
+}
+
+pub(crate) fn double_quote(value: &str) -> String {
+    quote_double(value, false)
+}
+
+/// Double quotes `value`; with `keep_newlines` its newlines stay real line
+/// breaks instead of `\n` escapes.
+fn quote_double(value: &str, keep_newlines: bool) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '\n' if keep_newlines => out.push('\n'),
+            _ => match spec::escape(c) {
+                Some(escaped) => {
+                    out.push('\\');
+                    out.push(escaped);
+                },
+                None => out.push(c),
+            },
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Whether rendered pairs get an `export ` prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportPolicy {
+    /// As each pair was written (`is_exported`).
+    #[default]
+    Preserve,
+    /// On every pair, e.g. for files that are `source`d by a shell.
+    Always,
+    /// On no pair, e.g. for `docker --env-file`, which rejects it.
+    Never,
+}
+
+/// How values that are long or contain newlines are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Fold {
+    /// One line per pair; newlines are written as `\n` escapes.
+    #[default]
+    SingleLine,
+    /// Values containing newlines become double-quoted blocks spanning
+    /// several lines.
+    Multiline,
+    /// Unquoted values whose line would be longer than this many
+    /// characters are split across lines ending in a continuation `\`.
+    /// Values that need quotes stay on one line.
+    Continuation(usize),
+}
+
+/// How `Environment::display_with` renders pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    pub export: ExportPolicy,
+    /// Sort pairs by key instead of keeping source order.
+    pub sort_keys: bool,
+    pub fold: Fold,
+    /// Comments are written only when the environment carries its source
+    /// (`retain_source()` and `preserve_comments()`); placing them between
+    /// pairs also needs `track_positions()`.
+    pub comments: CommentPolicy,
+}
+
+/// Which comments `Environment::display_with` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CommentPolicy {
+    /// Every comment and commented-out pair. With `sort_keys`, comments
+    /// other than the header have no place and are dropped.
+    #[default]
+    Keep,
+    /// Only the comments above the first pair, e.g. a license or
+    /// "generated by" header.
+    HeaderOnly,
+    /// No comments and no commented-out pairs.
+    Strip,
+}
+
+impl fmt::Display for KeyValuePair<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_pair(f, self, &WriteOptions::default())
+    }
+}
+
+fn write_pair(f: &mut fmt::Formatter<'_>, kv: &KeyValuePair<'_>, options: &WriteOptions) -> fmt::Result {
+    if kv.is_comment {
+        f.write_str("# ")?;
+    }
+    let export = match options.export {
+        ExportPolicy::Preserve => kv.is_exported,
+        ExportPolicy::Always => true,
+        ExportPolicy::Never => false,
+    };
+    let prefix = format!("{}{}=", if export { "export " } else { "" }, kv.key);
+    f.write_str(&prefix)?;
+    match options.fold {
+        // Commented-out pairs must stay on one line to be recognized
+        _ if kv.is_comment => {},
+        Fold::Multiline if kv.value.contains('\n') => return f.write_str(&quote_double(&kv.value, true)),
+        Fold::Continuation(width) if can_be_unquoted(&kv.value) && prefix.chars().count() + kv.value.chars().count() > width => {
+            let first = width.saturating_sub(prefix.chars().count() + 1);
+            return write_continued(f, &kv.value, first, width.saturating_sub(1));
+        },
+        _ => {},
+    }
+    f.write_str(&encode_value(&kv.value, kv.quote).1)
+}
+
+/// Writes an unquoted value as lines ending in a continuation backslash, the
+/// first holding up to `first` characters and the rest up to `rest`.
+fn write_continued(f: &mut fmt::Formatter<'_>, value: &str, first: usize, rest: usize) -> fmt::Result {
+    let mut chars = value.chars().peekable();
+    let mut limit = first.max(1);
+    while chars.peek().is_some() {
+        let line: String = chars.by_ref().take(limit).collect();
+        f.write_str(&line)?;
+        if chars.peek().is_some() {
+            f.write_str("\\\n")?;
+        }
+        limit = rest.max(1);
+    }
+    Ok(())
+}
+
+/// Comments only carry spans, so they render as a bare `#`; diagnostics
+/// render as comments so the output stays valid.
+impl fmt::Display for Entry<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Entry::Pair(kv) => kv.fmt(f),
+            Entry::Comment(_) => f.write_str("#"),
+            Entry::Error(e) => write!(f, "# error: {}", e),
+            Entry::Warning(w) => write!(f, "# warning: {}", w),
+        }
+    }
+}
+
+/// Renders pairs in source order, one per line.
+impl fmt::Display for Environment<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        EnvDisplay(self, WriteOptions::default()).fmt(f)
+    }
+}
+
+/// Display adapter rendering an environment with keys sorted alphabetically.
+pub struct SortedDisplay<'e, 'a>(pub(crate) &'e Environment<'a>);
+
+impl fmt::Display for SortedDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = WriteOptions { sort_keys: true, ..WriteOptions::default() };
+        EnvDisplay(self.0, options).fmt(f)
+    }
+}
+
+/// Display adapter rendering an environment with `WriteOptions`.
+pub struct EnvDisplay<'e, 'a>(pub(crate) &'e Environment<'a>, pub(crate) WriteOptions);
+
+impl fmt::Display for EnvDisplay<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let EnvDisplay(env, options) = self;
+        let keep_commented = options.comments == CommentPolicy::Keep;
+        let mut pairs: Vec<_> = env.iter_ordered().filter(|kv| keep_commented || !kv.is_comment).collect();
+        if options.sort_keys {
+            pairs.sort_by(|a, b| a.key.cmp(&b.key));
+        }
+
+        // Comment text lives in the source; without it only pairs are written
+        let offset = |kv: &KeyValuePair<'_>| kv.key_span.map_or(usize::MAX, |span| span.start.offset);
+        let header_end = env.iter().map(offset).min().unwrap_or(usize::MAX);
+        let mut comments: Vec<(usize, &str)> = match (options.comments, env.source()) {
+            (CommentPolicy::Strip, _) | (_, None) => Vec::new(),
+            (policy, Some(source)) => env.comments().iter()
+                .map(|c| (c.span.start.offset, &source[c.span.range()]))
+                .filter(|&(at, _)| at < header_end || (policy == CommentPolicy::Keep && !options.sort_keys))
+                .collect(),
+        };
+        comments.sort_by_key(|&(at, _)| at);
+
+        let mut comments = comments.into_iter().peekable();
+        for kv in pairs {
+            let at = if options.sort_keys { header_end } else { offset(kv) };
+            while let Some((_, text)) = comments.next_if(|&(c, _)| c < at) {
+                writeln!(f, "{}", text)?;
+            }
+            write_pair(f, kv, options)?;
+            f.write_str("\n")?;
+        }
+        for (_, text) in comments {
+            writeln!(f, "{}", text)?;
+        }
+        Ok(())
+    }
+}
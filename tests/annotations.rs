@@ -0,0 +1,54 @@
+use korni::{Date, Korni};
+
+#[test]
+fn test_date_parse_and_display() {
+    let date: Date = "2025-01-01".parse().unwrap();
+    assert_eq!(date, Date::new(2025, 1, 1).unwrap());
+    assert_eq!(date.to_string(), "2025-01-01");
+
+    assert!("2024-02-29".parse::<Date>().is_ok());
+    assert!("2023-02-29".parse::<Date>().is_err());
+    assert!("2025-13-01".parse::<Date>().is_err());
+    assert!("2025-1".parse::<Date>().is_err());
+    assert!("soon".parse::<Date>().is_err());
+}
+
+#[test]
+fn test_date_from_unix_days() {
+    assert_eq!(Date::from_unix_days(0), Date::new(1970, 1, 1).unwrap());
+    assert_eq!(Date::from_unix_days(19_723), Date::new(2024, 1, 1).unwrap());
+    assert_eq!(Date::from_unix_days(-1), Date::new(1969, 12, 31).unwrap());
+}
+
+#[test]
+fn test_annotations_attach_to_next_pair() {
+    let input = "# @rotate-by: 2025-01-01\n# @owner platform team\nTOKEN=abc\nOTHER=1\n";
+    let env = Korni::from_str(input).parse().unwrap();
+
+    let token = env.get_entry("TOKEN").unwrap();
+    assert_eq!(token.annotations.len(), 2);
+    assert_eq!(token.annotation("rotate-by"), Some("2025-01-01"));
+    assert_eq!(token.annotation("owner"), Some("platform team"));
+    assert_eq!(token.rotate_by(), Date::new(2025, 1, 1));
+
+    assert!(env.get_entry("OTHER").unwrap().annotations.is_empty());
+}
+
+#[test]
+fn test_annotations_dropped_on_error() {
+    let input = "# @rotate-by: 2025-01-01\nBAD KEY=1\nGOOD=1\n";
+    let env = Korni::from_str(input).parse().unwrap();
+    assert!(env.get_entry("GOOD").unwrap().annotations.is_empty());
+}
+
+#[test]
+fn test_expired() {
+    let input = "# @rotate-by: 2025-01-01\nOLD=1\n# @rotate-by: 2030-06-01\nNEW=2\n# @rotate-by: never\nODD=3\nPLAIN=4\n";
+    let env = Korni::from_str(input).parse().unwrap();
+    let today = Date::new(2026, 1, 1).unwrap();
+
+    let expired: Vec<_> = env.expired(today).iter().map(|kv| kv.key.as_ref()).collect();
+    assert_eq!(expired, vec!["OLD"]);
+
+    assert!(env.expired(Date::new(2025, 1, 1).unwrap()).is_empty());
+}
@@ -0,0 +1,218 @@
+use korni::audit::{classify_secret, cross_check, is_secret_key, secrets, shannon_entropy, shell_injection, weak_secrets, CrossCheckIssue, CrossCheckKind, SecretStrength};
+use korni::schema::InferredType;
+use korni::{Korni, KorniBuilder};
+
+#[test]
+fn test_is_secret_key() {
+    assert!(is_secret_key("DB_PASSWORD"));
+    assert!(is_secret_key("api_key"));
+    assert!(is_secret_key("GITHUB_TOKEN"));
+    assert!(is_secret_key("AWS_SECRET_ACCESS_KEY"));
+    assert!(!is_secret_key("DB_HOST"));
+    assert!(!is_secret_key("KEYBOARD_LAYOUT"));
+    assert!(!is_secret_key("PASSAGE"));
+    assert!(is_secret_key("DB_PWD"));
+    assert!(!is_secret_key("PWD"));
+    assert!(!is_secret_key("OLDPWD"));
+}
+
+#[test]
+fn test_shannon_entropy() {
+    assert_eq!(shannon_entropy(""), 0.0);
+    assert_eq!(shannon_entropy("aaaa"), 0.0);
+    assert_eq!(shannon_entropy("abcd"), 2.0);
+}
+
+#[test]
+fn test_classify_secret() {
+    assert_eq!(classify_secret(""), SecretStrength::Empty);
+    assert_eq!(classify_secret("admin"), SecretStrength::Weak);
+    assert_eq!(classify_secret("Password"), SecretStrength::Weak);
+    assert_eq!(classify_secret("aaaaaaaaaaaaaaaaaaaa"), SecretStrength::Weak);
+    assert_eq!(classify_secret("Tr0ub4dor&3"), SecretStrength::Moderate);
+    assert_eq!(classify_secret("f8Kq2!zP9vLm#4Xw7RtY0bNc"), SecretStrength::Strong);
+}
+
+#[test]
+fn test_secret_report_has_no_values() {
+    let env = Korni::from_str("DB_HOST=localhost\nPASSWORD=admin\nAPI_TOKEN=f8Kq2zP9vLm4Xw7RtY0bNcQ1\n# OLD_PASSWORD=x")
+        .preserve_comments()
+        .track_positions()
+        .parse()
+        .unwrap();
+    let report = secrets(&env);
+    assert_eq!(report.len(), 2);
+    assert_eq!(report[0].key, "PASSWORD");
+    assert_eq!(report[0].strength, SecretStrength::Weak);
+    assert_eq!(report[0].length, 5);
+    assert_eq!(report[0].key_span.unwrap().start.line, 1);
+    assert_eq!(report[1].strength, SecretStrength::Strong);
+    assert!(!format!("{:?}", report).contains("admin"));
+
+    let weak = weak_secrets(&env);
+    assert_eq!(weak.len(), 1);
+    assert_eq!(weak[0].key, "PASSWORD");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_check_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    use korni::audit::check_permissions;
+    use korni::Error;
+
+    let dir = std::env::temp_dir().join(format!("korni-perms-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let secrets = dir.join(".env");
+    let public = dir.join("public.env");
+    std::fs::write(&secrets, "DB_PASSWORD=hunter2\n").unwrap();
+    std::fs::write(&public, "DB_HOST=localhost\n").unwrap();
+
+    let set_mode = |path: &std::path::Path, mode| std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    set_mode(&secrets, 0o644);
+    set_mode(&public, 0o644);
+
+    let err = check_permissions(&secrets).unwrap_err();
+    assert!(matches!(err, Error::InsecurePermissions { mode: 0o644, .. }), "{:?}", err);
+    assert_eq!(err.kind(), "InsecurePermissions");
+
+    // The loader only warns about world-readable files that hold secrets
+    let env = Korni::from_file(&secrets).parse().unwrap();
+    assert_eq!(env.warnings().len(), 1);
+    assert!(env.warnings()[0].to_string().contains("world-readable (mode 644)"));
+    assert!(Korni::from_file(&public).parse().unwrap().warnings().is_empty());
+
+    set_mode(&secrets, 0o600);
+    assert!(check_permissions(&secrets).is_ok());
+    assert!(Korni::from_file(&secrets).parse().unwrap().warnings().is_empty());
+
+    assert!(matches!(check_permissions(dir.join("missing.env")), Err(Error::Io(_))));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_is_gitignored() {
+    use korni::audit::is_gitignored;
+    use std::fs;
+
+    let root = std::env::temp_dir().join(format!("korni-gitignore-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join(".git/info")).unwrap();
+    fs::create_dir_all(root.join("config/local")).unwrap();
+    fs::create_dir_all(root.join("build/out")).unwrap();
+    fs::write(root.join(".gitignore"), "# env files\n.env\n.env.*\n!.env.example\n/build/\n**/local/*.env\n").unwrap();
+    fs::write(root.join("config/.gitignore"), "secrets.env\n").unwrap();
+    fs::write(root.join(".git/info/exclude"), "scratch.env\n").unwrap();
+
+    assert_eq!(is_gitignored(root.join(".env")), Some(true));
+    assert_eq!(is_gitignored(root.join(".env.production")), Some(true));
+    assert_eq!(is_gitignored(root.join(".env.example")), Some(false));
+    assert_eq!(is_gitignored(root.join("config/.env")), Some(true));
+    assert_eq!(is_gitignored(root.join("config/secrets.env")), Some(true));
+    assert_eq!(is_gitignored(root.join("secrets.env")), Some(false));
+    assert_eq!(is_gitignored(root.join("config/local/dev.env")), Some(true));
+    assert_eq!(is_gitignored(root.join("config/app.env")), Some(false));
+    assert_eq!(is_gitignored(root.join("build/out/app.env")), Some(true));
+    assert_eq!(is_gitignored(root.join("scratch.env")), Some(true));
+
+    fs::write(root.join("config/app.env"), "API_TOKEN=x\n").unwrap();
+    let builder = Korni::from_file(root.join("config/app.env"));
+    assert_eq!(builder.path(), Some(root.join("config/app.env").as_path()));
+    assert_eq!(builder.is_gitignored(), Some(false));
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn test_is_gitignored_outside_work_tree() {
+    assert_eq!(korni::audit::is_gitignored("/.env"), None);
+}
+
+#[test]
+fn test_shell_injection() {
+    let input = "\
+BACKUP_CMD=\"tar czf out.tgz; rm -rf /\"
+HOOK='echo `id` && $(curl x)'
+NAME=a;b
+TAG=v1.2
+ESCAPED=\"a\\\"b;c\"
+MULTI=\"one
+two|three\"
+";
+    let env = Korni::from_str(input).track_positions().parse().unwrap();
+
+    let findings = shell_injection(&env, &["*_CMD", "HOOK", "TAG", "ESCAPED", "MULTI"]);
+    let found: Vec<_> = findings.iter().map(|f| (f.key.as_str(), f.metacharacter)).collect();
+    assert_eq!(found, [
+        ("BACKUP_CMD", ";"),
+        ("HOOK", "`"),
+        ("HOOK", "`"),
+        ("HOOK", "&&"),
+        ("HOOK", "$("),
+        ("ESCAPED", ";"),
+        ("MULTI", "\n"),
+        ("MULTI", "|"),
+    ]);
+    let span = findings[3].span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "&&");
+    assert_eq!((span.start.line, span.start.col), (1, 16));
+    // Escapes shift the value against the source
+    assert_eq!(findings[5].span, None);
+    // Past the first line of a value, positions need the source
+    assert_eq!(findings[7].span, None);
+
+    // With the source attached, escapes and line breaks are accounted for
+    let env = env.with_source(input);
+    let findings = shell_injection(&env, &["ESCAPED", "MULTI"]);
+    let span = findings[0].span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], ";");
+    assert_eq!((span.start.line, span.start.col), (4, 13));
+    let span = findings[2].span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "|");
+    assert_eq!((span.start.line, span.start.col), (6, 3));
+}
+
+#[test]
+fn test_shell_injection_spans_with_value_options() {
+    let spans = |input: &str, korni: KorniBuilder<'_>| {
+        let env = korni.track_positions().retain_source().parse().unwrap();
+        shell_injection(&env, &["CMD"]).iter()
+            .map(|f| f.span.unwrap())
+            .map(|span| (span.start.offset, input[span.start.offset..span.end.offset].to_string()))
+            .collect::<Vec<_>>()
+    };
+
+    let input = "CMD=\"   a;b  \"";
+    assert_eq!(spans(input, Korni::from_str(input).trim_quoted_values()), [(9, ";".to_string())]);
+
+    // A normalized line break covers the `\r\n` it came from
+    let input = "CMD=\"a\r\n;c\"";
+    assert_eq!(spans(input, Korni::from_str(input).normalize_newlines()), [(6, "\r\n".to_string()), (8, ";".to_string())]);
+
+    let input = "CMD=\"a;\n\"";
+    assert_eq!(spans(input, Korni::from_str(input).strip_trailing_newline()), [(6, ";".to_string())]);
+
+    let input = "CMD=\"\\t a\r\n|b\r\n\"";
+    let korni = Korni::from_str(input).trim_quoted_values().normalize_newlines().strip_trailing_newline();
+    assert_eq!(spans(input, korni), [(9, "\r\n".to_string()), (11, "|".to_string())]);
+}
+
+#[test]
+fn test_cross_check() {
+    let dev = Korni::from_str("PORT=8080\nDB_PASSWORD=hunter2\nTIMEOUT=30\nDEBUG=1\nRATIO=1\nAPI_TOKEN=\n").parse().unwrap();
+    let staging = Korni::from_str("PORT=8081\nDB_PASSWORD=s3cret-prod\nTIMEOUT=30s\nDEBUG=true\nRATIO=0.5\nAPI_TOKEN=\n").parse().unwrap();
+    let prod = Korni::from_str("PORT=80\nDB_PASSWORD=s3cret-prod\nTIMEOUT=45s\n# DEBUG=false\nRATIO=0.25\nAPI_TOKEN=\nSENTRY_DSN=https://sentry.example/1\n").parse().unwrap();
+
+    let issues = cross_check(&[dev, staging, prod]);
+    let issue = |key: &str, kind| CrossCheckIssue { key: key.to_string(), kind };
+    assert_eq!(issues, [
+        issue("DB_PASSWORD", CrossCheckKind::SharedSecret { envs: vec![1, 2] }),
+        issue("TIMEOUT", CrossCheckKind::TypeMismatch {
+            types: vec![Some(InferredType::Integer), Some(InferredType::Duration), Some(InferredType::Duration)],
+        }),
+        issue("DEBUG", CrossCheckKind::Missing { missing_from: vec![2] }),
+        issue("SENTRY_DSN", CrossCheckKind::Missing { missing_from: vec![0, 1] }),
+    ]);
+    // Reports name environments and keys, never values
+    assert!(!format!("{:?}", issues).contains("s3cret"));
+}
@@ -0,0 +1,43 @@
+use std::time::{Duration, UNIX_EPOCH};
+use korni::{AuditLog, Error, InvalidKeyReason, Korni};
+
+#[test]
+fn test_audit_log_appends_annotated_entries() {
+    let dir = std::env::temp_dir().join(format!("korni-audit-log-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".env");
+    std::fs::write(&path, "API_URL=http://localhost").unwrap();
+
+    let log = AuditLog::new(&path);
+    let at = UNIX_EPOCH + Duration::from_secs(1_760_434_200);
+    log.append_at("API_URL", "https://api.example.com", "alice", at).unwrap();
+    log.append_at("GREETING", "hello world\nbye", "bob", at + Duration::from_secs(61)).unwrap();
+    log.append_at("API_URL", "https://api2.example.com", "carol", at + Duration::from_secs(3600)).unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "\
+API_URL=http://localhost
+# @changed-by: alice
+# @changed-at: 2025-10-14T09:30:00Z
+API_URL=https://api.example.com
+# @changed-by: bob
+# @changed-at: 2025-10-14T09:31:01Z
+GREETING=\"hello world\\nbye\"
+# @changed-by: carol
+# @changed-at: 2025-10-14T10:30:00Z
+API_URL=https://api2.example.com
+");
+
+    let env = Korni::from_file(&path).parse().unwrap();
+    assert_eq!(env.get("API_URL"), Some("https://api2.example.com"));
+    assert_eq!(env.get("GREETING"), Some("hello world\nbye"));
+
+    let history = log.history("API_URL").unwrap();
+    let authors: Vec<_> = history.iter().map(|kv| kv.annotation("changed-by")).collect();
+    assert_eq!(authors, [None, Some("alice"), Some("carol")]);
+
+    assert_eq!(log.append("BAD KEY", "1", "dave"), Err(Error::Expected { offset: 4, expected: "'='" }));
+    assert_eq!(log.append("1KEY", "1", "dave"), Err(Error::InvalidKey { offset: 0, reason: InvalidKeyReason::StartsWithDigit }));
+    assert!(log.append("OK", "1", "dave\nforged").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
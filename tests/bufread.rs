@@ -0,0 +1,109 @@
+use std::io::{BufReader, Read};
+use korni::{Entry, EnvIterator, Error, ParseOptions};
+
+const INPUT: &str = "\
+# header
+A=1
+export B='multi
+line'
+C=\"escaped \\\" quote
+still open\" # inline
+# @rotate-by: 2025-01-01
+# @owner: ops
+D=annotated
+E=first\\
+second
+bad line
+A=again
+# F=commented
+G=\"never closed
+H=2
+";
+
+fn both(input: &str, options: ParseOptions) -> (Vec<Entry<'static>>, Vec<Entry<'static>>) {
+    let whole = korni::parse_with_options(input, options).into_iter().map(Entry::into_owned).collect();
+    let streamed = EnvIterator::from_bufread_with_options(input.as_bytes(), options).collect();
+    (whole, streamed)
+}
+
+#[test]
+fn test_bufread_matches_whole_input_parse() {
+    for include_comments in [false, true] {
+        for track_positions in [false, true] {
+            for warn_duplicate_keys in [false, true] {
+                let options = ParseOptions { include_comments, track_positions, warn_duplicate_keys, ..ParseOptions::default() };
+                let (whole, streamed) = both(INPUT, options);
+                assert_eq!(streamed, whole, "{:?}", options);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_bufread_crlf_and_missing_final_newline() {
+    let input = "A=1\r\nB=\"x\r\ny\"\r\n\r\nC=3";
+    let options = ParseOptions { track_positions: true, ..ParseOptions::default() };
+    let (whole, streamed) = both(input, options);
+    assert_eq!(streamed, whole);
+}
+
+#[test]
+fn test_bufread_bom() {
+    let (whole, streamed) = both("\u{FEFF}A=1\nB=2\n", ParseOptions::default());
+    assert_eq!(streamed, whole);
+
+    let streamed: Vec<_> = EnvIterator::from_bufread("A=1\n\u{FEFF}B=2\n".as_bytes()).collect();
+    assert!(matches!(streamed[1], Entry::Error(Error::InvalidBom { offset: 4 })), "{:?}", streamed);
+}
+
+/// An endless `A0=x\nA1=x\n...` stream.
+struct Endless(usize, Vec<u8>);
+
+impl Read for Endless {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.1.is_empty() {
+            self.1 = format!("A{}=x\n", self.0).into_bytes();
+            self.0 += 1;
+        }
+        let n = buf.len().min(self.1.len());
+        buf[..n].copy_from_slice(&self.1[..n]);
+        self.1.drain(..n);
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_bufread_is_lazy() {
+    let keys: Vec<_> = EnvIterator::from_bufread(BufReader::new(Endless(0, Vec::new())))
+        .take(3)
+        .map(|e| e.key().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, vec!["A0", "A1", "A2"]);
+}
+
+#[test]
+fn test_bufread_invalid_utf8_ends_iteration() {
+    let entries: Vec<_> = EnvIterator::from_bufread(&b"A=1\nB=\xFF\nC=3\n"[..]).collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].key(), Some("A"));
+    assert!(matches!(entries[1], Entry::Error(Error::InvalidUtf8 { offset: 6, .. })));
+
+    // As the reader reports it, e.g. a decoding reader
+    let entries: Vec<_> = EnvIterator::from_bufread(BufReader::new(Failing(std::io::ErrorKind::InvalidData))).collect();
+    assert!(matches!(entries[..], [Entry::Error(Error::InvalidUtf8 { offset: 0, .. })]));
+}
+
+struct Failing(std::io::ErrorKind);
+
+impl Read for Failing {
+    fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+        Err(self.0.into())
+    }
+}
+
+#[test]
+fn test_bufread_io_error_ends_iteration() {
+    let entries: Vec<_> = EnvIterator::from_bufread(BufReader::new(Failing(std::io::ErrorKind::BrokenPipe))).collect();
+    assert_eq!(entries.len(), 1);
+    assert!(matches!(entries[0], Entry::Error(Error::Io(_))));
+}
@@ -0,0 +1,39 @@
+use std::fs;
+use std::path::PathBuf;
+use korni::Korni;
+
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("korni-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_cascade_later_files_override() {
+    let dir = scratch_dir("cascade");
+    fs::write(dir.join(".env"), "A=base\nB=base\nC=base\nD=base\n").unwrap();
+    fs::write(dir.join(".env.local"), "B=local\n").unwrap();
+    fs::write(dir.join(".env.prod"), "C=prod\n# D=disabled\nE=prod\n").unwrap();
+    fs::write(dir.join(".env.prod.local"), "D=prod-local\nBAD LINE\n").unwrap();
+
+    let env = Korni::cascade(&dir, Some("prod")).unwrap();
+    let values: Vec<_> = env.iter_ordered().map(|kv| (kv.key.as_ref(), kv.value.as_ref())).collect();
+    assert_eq!(values, [("A", "base"), ("B", "local"), ("C", "prod"), ("E", "prod"), ("D", "prod-local")]);
+    assert_eq!(env.errors().len(), 1);
+
+    let env = Korni::cascade(&dir, None).unwrap();
+    assert_eq!(env.get("C"), Some("base"));
+    assert_eq!(env.get("E"), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_cascade_missing_files_are_skipped() {
+    let dir = scratch_dir("cascade-empty");
+    let env = Korni::cascade(&dir, Some("dev")).unwrap();
+    assert_eq!(env.iter().count(), 0);
+    assert!(!env.has_errors());
+    fs::remove_dir_all(&dir).unwrap();
+}
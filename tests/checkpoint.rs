@@ -0,0 +1,51 @@
+use korni::{Entry, Error, ParseOptions, Parser};
+
+#[test]
+fn test_checkpoint_rewind() {
+    let input = "A=1\nB=2\nC=3\n";
+    let mut parser = Parser::new(input);
+    assert_eq!(parser.next_entry().unwrap().key(), Some("A"));
+
+    let checkpoint = parser.checkpoint();
+    assert_eq!(checkpoint.offset(), 4);
+    let first: Vec<_> = std::iter::from_fn(|| parser.next_entry()).collect();
+
+    parser.resume(checkpoint);
+    let again: Vec<_> = std::iter::from_fn(|| parser.next_entry()).collect();
+    assert_eq!(first, again);
+    assert_eq!(again[0].as_pair().unwrap().index, 1);
+}
+
+#[test]
+fn test_resume_after_append() {
+    let options = ParseOptions { warn_duplicate_keys: true, track_positions: true, ..ParseOptions::default() };
+    let mut log = String::from("A=1\n# @owner: ops\n");
+
+    let checkpoint = {
+        let mut parser = Parser::with_options(&log, options);
+        assert_eq!(parser.next_entry().unwrap().key(), Some("A"));
+        assert!(parser.next_entry().is_none());
+        parser.checkpoint()
+    };
+
+    log.push_str("B=2\nA=3\n");
+    let mut parser = Parser::with_options(&log, ParseOptions::default());
+    parser.resume(checkpoint);
+    let entries: Vec<_> = std::iter::from_fn(|| parser.next_entry()).collect();
+
+    let b = entries[0].as_pair().unwrap();
+    assert_eq!(b.key, "B");
+    assert_eq!(b.index, 1);
+    assert_eq!(b.annotation("owner"), Some("ops"));
+    assert_eq!(b.key_span.unwrap().start.line, 2);
+
+    assert_eq!(entries[1].key(), Some("A"));
+    match &entries[2] {
+        Entry::Warning(Error::DuplicateKey { key, first_span, .. }) => {
+            assert_eq!(key, "A");
+            assert_eq!(first_span.start.offset, 0);
+        },
+        other => panic!("expected duplicate warning, got {:?}", other),
+    }
+    assert_eq!(entries.len(), 3);
+}
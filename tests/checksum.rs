@@ -0,0 +1,54 @@
+use korni::{checksum, Error, Korni};
+
+#[test]
+fn test_sign_and_verify() {
+    let signed = checksum::sign("A=1\nB=2");
+    assert_eq!(signed, "A=1\nB=2\n# korni-checksum: sha256:beb5f2519a8d4a22ff19d6b4ba05806c75710dc1ad7e2ef37ee1d1a950d4b074\n");
+    assert_eq!(checksum::verify(&signed), Ok(()));
+    assert_eq!(checksum::sign(&signed), signed, "re-signing replaces the old line");
+    assert!(checksum::sign("").ends_with("sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\n"));
+
+    // Spans many 64-byte blocks
+    assert!(checksum::sign(&"x".repeat(999)).ends_with("sha256:ce7b1f5726a146b4149c07a422cc9db17874796c174a0e5460a9dc1718b4a55a\n"));
+
+    let tampered = signed.replace("B=2", "B=3");
+    assert!(matches!(checksum::verify(&tampered), Err(Error::ChecksumMismatch { expected: Some(_), .. })));
+    let truncated = &signed[..8];
+    assert!(matches!(checksum::verify(truncated), Err(Error::ChecksumMismatch { expected: None, .. })));
+}
+
+#[test]
+fn test_builder_verifies_checksum_and_manifest() {
+    let dir = std::env::temp_dir().join(format!("korni-checksum-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".env");
+
+    std::fs::write(&path, checksum::sign("KEY=value\n")).unwrap();
+    let env = Korni::from_file(&path).verify_checksum().parse().unwrap();
+    assert_eq!(env.get("KEY"), Some("value"));
+
+    let manifest = checksum::write_manifest(&path).unwrap();
+    assert_eq!(manifest, dir.join(".env.sha256"));
+    assert!(std::fs::read_to_string(&manifest).unwrap().ends_with("  .env\n"));
+    assert_eq!(checksum::verify_manifest(&path), Ok(()));
+
+    std::fs::write(&path, "KEY=other\n").unwrap();
+    let err = Korni::from_file(&path).verify_checksum().parse().unwrap_err();
+    assert_eq!(err.kind(), "ChecksumMismatch");
+    assert!(checksum::verify_manifest(&path).is_err());
+    assert!(Korni::from_file(&path).parse().is_ok(), "verification is opt-in");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_str_builder_verifies_checksum() {
+    let signed = checksum::sign("KEY=value\n");
+    let env = Korni::from_str(&signed).verify_checksum().parse().unwrap();
+    assert_eq!(env.get("KEY"), Some("value"));
+
+    let tampered = signed.replace("value", "other");
+    let err = Korni::from_str(&tampered).verify_checksum().parse().unwrap_err();
+    assert_eq!(err.kind(), "ChecksumMismatch");
+    assert!(Korni::from_bytes(tampered.as_bytes()).verify_checksum().parse().is_err());
+}
@@ -0,0 +1,101 @@
+use korni::schema::{KeySpec, Schema, ValueType, ViolationKind};
+use korni::Korni;
+
+mod generated {
+    include!("codegen/app_config.rs");
+}
+
+use generated::AppConfig;
+
+const EXAMPLE: &str = include_str!("codegen/example.env");
+
+#[test]
+fn test_from_annotated() {
+    let env = Korni::from_str(EXAMPLE).parse().unwrap();
+    let schema = Schema::from_annotated(&env).unwrap();
+
+    let keys: Vec<_> = schema.keys().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["DB_HOST", "DB_PORT", "DEBUG", "API_URL", "SAMPLE_RATE", "TYPE"]);
+    assert_eq!(
+        schema.get("DB_HOST"),
+        Some(&KeySpec::new(ValueType::String).required().describe("Database host name")),
+    );
+    assert_eq!(schema.get("DB_PORT"), Some(&KeySpec::new(ValueType::Integer).with_default("5432")));
+
+    let env = Korni::from_str("# @type: uuid\nID=1").parse().unwrap();
+    assert!(Schema::from_annotated(&env).unwrap_err().to_string().contains("Unknown @type 'uuid' for ID"));
+}
+
+#[test]
+fn test_generated_code_matches_checked_in_copy() {
+    let env = Korni::from_str(EXAMPLE).parse().unwrap();
+    let schema = Schema::from_annotated(&env).unwrap();
+    assert_eq!(schema.to_rust_struct("AppConfig"), include_str!("codegen/app_config.rs"));
+}
+
+#[test]
+fn test_generated_load() {
+    let env = Korni::from_str("DB_HOST=db\nDEBUG=yes\nAPI_URL=https://api\nTYPE=x\n").parse().unwrap();
+    let config = AppConfig::load(&env).unwrap();
+    assert_eq!(config, AppConfig {
+        db_host: "db".into(),
+        db_port: 5432,
+        debug: Some(true),
+        api_url: "https://api".into(),
+        sample_rate: None,
+        r#type: Some("x".into()),
+    });
+
+    let env = Korni::from_str("DB_HOST=db\nAPI_URL=https://api\nDB_PORT=http").track_positions().parse().unwrap();
+    let violation = AppConfig::load(&env).unwrap_err();
+    assert_eq!((violation.key.as_str(), violation.kind), ("DB_PORT", ViolationKind::InvalidType(ValueType::Integer)));
+    assert_eq!(violation.key_span.unwrap().start.line, 2);
+
+    let env = Korni::from_str("DB_HOST=db").parse().unwrap();
+    assert_eq!(AppConfig::load(&env).unwrap_err().kind, ViolationKind::Missing);
+}
+
+#[test]
+fn test_codegen_without_typed_or_required_keys() {
+    let schema = Schema::new().key("NAME", KeySpec::default());
+    let code = schema.to_rust_struct("Plain");
+    assert!(code.contains("pub name: Option<String>,"));
+    assert!(!code.contains("use korni::schema"));
+}
+
+#[test]
+fn test_rust_struct_disambiguates_colliding_fields() {
+    let schema = Schema::new()
+        .key("API_KEY", KeySpec::new(ValueType::String).required())
+        .key("api_key", KeySpec::new(ValueType::String))
+        .key("Api_Key", KeySpec::new(ValueType::String))
+        .key("TYPE", KeySpec::new(ValueType::String))
+        .key("type", KeySpec::new(ValueType::String));
+    let code = schema.to_rust_struct("Config");
+    assert!(code.contains("    pub api_key: String,\n"));
+    assert!(code.contains("    /// Read from `api_key`.\n    pub api_key_2: Option<String>,\n"));
+    assert!(code.contains("    pub api_key_3: Option<String>,\n"));
+    assert!(code.contains("    pub r#type: Option<String>,\n"));
+    assert!(code.contains("    pub type_2: Option<String>,\n"));
+    assert!(code.contains("            api_key_2: get(\"api_key\").map(str::to_string),\n"));
+}
+
+#[test]
+fn test_rust_struct_fields_are_identifiers() {
+    let schema = Schema::new()
+        .key("_", KeySpec::new(ValueType::String))
+        .key("__", KeySpec::new(ValueType::String))
+        .key("2FA_CODE", KeySpec::new(ValueType::String))
+        .key("DB-HOST", KeySpec::new(ValueType::String))
+        .key("DB_HOST", KeySpec::new(ValueType::String))
+        .key("ÄRGER", KeySpec::new(ValueType::String))
+        .key("\u{301}X", KeySpec::new(ValueType::String));
+    let code = schema.to_rust_struct("Config");
+    assert!(code.contains("    /// Read from `_`.\n    pub _field: Option<String>,\n"));
+    assert!(code.contains("    pub __: Option<String>,\n"));
+    assert!(code.contains("    /// Read from `2FA_CODE`.\n    pub _2fa_code: Option<String>,\n"));
+    assert!(code.contains("    /// Read from `DB-HOST`.\n    pub db_host: Option<String>,\n"));
+    assert!(code.contains("    /// Read from `DB_HOST`.\n    pub db_host_2: Option<String>,\n"));
+    assert!(code.contains("    pub ärger: Option<String>,\n"));
+    assert!(code.contains("    pub _\u{301}x: Option<String>,\n"));
+}
@@ -0,0 +1,46 @@
+// Generated by `korni codegen`; edit the schema instead.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    /// Database host name
+    pub db_host: String,
+    pub db_port: i64,
+    pub debug: Option<bool>,
+    pub api_url: String,
+    /// Fraction of requests traced.
+    pub sample_rate: Option<f64>,
+    pub r#type: Option<String>,
+}
+
+impl AppConfig {
+    #[allow(clippy::result_large_err)]
+    pub fn load(env: &korni::Environment<'_>) -> Result<Self, korni::schema::Violation> {
+        use korni::schema::{ValueType, Violation, ViolationKind};
+        let get = |key: &str| env.get_entry(key).filter(|kv| !kv.is_comment).map(|kv| kv.value.as_ref());
+        let missing = |key: &str| Violation::new(env, key, ViolationKind::Missing);
+        let invalid = |key: &str, value_type| Violation::new(env, key, ViolationKind::InvalidType(value_type));
+        Ok(Self {
+            db_host: match get("DB_HOST") {
+                Some(v) => v.to_string(),
+                None => return Err(missing("DB_HOST")),
+            },
+            db_port: {
+                let v = get("DB_PORT").unwrap_or("5432");
+                v.parse::<i64>().map_err(|_| invalid("DB_PORT", ValueType::Integer))?
+            },
+            debug: match get("DEBUG") {
+                Some(v) => Some(korni::schema::parse_bool(v).ok_or_else(|| invalid("DEBUG", ValueType::Bool))?),
+                None => None,
+            },
+            api_url: match get("API_URL") {
+                Some(v) => Some(v).filter(|v| ValueType::Url.matches(v)).ok_or_else(|| invalid("API_URL", ValueType::Url))?.to_string(),
+                None => return Err(missing("API_URL")),
+            },
+            sample_rate: match get("SAMPLE_RATE") {
+                Some(v) => Some(v.parse::<f64>().map_err(|_| invalid("SAMPLE_RATE", ValueType::Float))?),
+                None => None,
+            },
+            r#type: get("TYPE").map(str::to_string),
+        })
+    }
+}
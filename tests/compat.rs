@@ -0,0 +1,45 @@
+#![cfg(feature = "fuzz-compat")]
+
+use korni::compat::{compare, DockerEnvFile, Divergence, InputGenerator, Reference};
+
+#[test]
+fn test_docker_reference_semantics() {
+    let pairs = DockerEnvFile.parse("# c\n  A=1\nB='q' x\nNOVALUE\n").unwrap();
+    assert_eq!(pairs, vec![("A".into(), "1".into()), ("B".into(), "'q' x".into())]);
+    assert!(DockerEnvFile.parse("A B=1").is_err());
+}
+
+#[test]
+fn test_compatible_input() {
+    assert!(compare("A=1\nB=two\n", &DockerEnvFile).is_compatible());
+}
+
+#[test]
+fn test_quoted_value_diverges() {
+    let comparison = compare("A='x'", &DockerEnvFile);
+    assert_eq!(comparison.reference, "docker");
+    assert_eq!(comparison.divergences, vec![Divergence::ValueMismatch {
+        key: "A".into(),
+        korni: "x".into(),
+        reference: "'x'".into(),
+    }]);
+}
+
+#[test]
+fn test_rejection_divergences() {
+    let comparison = compare("A==1", &DockerEnvFile);
+    assert!(matches!(comparison.divergences[0], Divergence::RejectedByKorni { .. }));
+    assert_eq!(comparison.divergences[1], Divergence::MissingInKorni { key: "A".into() });
+}
+
+#[test]
+fn test_generator_is_deterministic() {
+    let mut a = InputGenerator::new(42);
+    let mut b = InputGenerator::new(42);
+    for _ in 0..100 {
+        let input = a.next_input(8);
+        assert_eq!(input, b.next_input(8));
+        // Never panics on arbitrary fragment soup
+        let _ = compare(&input, &DockerEnvFile);
+    }
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "conformance")]
+
+use korni::conformance::{load_vectors, run, CORPUS};
+
+#[test]
+fn test_corpus_passes() {
+    let vectors = load_vectors(CORPUS).unwrap();
+    assert!(vectors.len() > 30);
+    let failures = run(&vectors);
+    assert!(failures.is_empty(), "{:#?}", failures);
+}
+
+#[test]
+fn test_mismatch_reported() {
+    let vectors = load_vectors(r#"[{
+        "name": "wrong value",
+        "input": "K=v",
+        "expected": [{ "pair": { "key": "K", "value": "other" } }]
+    }, {
+        "name": "wrong count",
+        "input": "A=1\nB=2",
+        "expected": [{ "pair": { "key": "A", "value": "1" } }]
+    }]"#).unwrap();
+    let failures = run(&vectors);
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].vector, "wrong value");
+    assert_eq!(failures[0].index, Some(0));
+    assert_eq!(failures[1].index, None);
+}
+
+#[test]
+fn test_check_entries_from_other_implementation() {
+    let vectors = load_vectors(r#"[{ "name": "x", "input": "K='v'", "expected": [{ "pair": { "key": "K", "value": "v", "quote": "single" } }] }]"#).unwrap();
+    let entries = korni::parse("K='v'");
+    assert!(vectors[0].check_entries(&entries).is_ok());
+    assert!(vectors[0].check_entries(&[]).is_err());
+}
+
+#[test]
+fn test_invalid_vectors_rejected() {
+    assert!(load_vectors(r#"[{ "name": "x", "input": "K=v", "expected": [{ "bogus": 1 }] }]"#).is_err());
+}
@@ -0,0 +1,54 @@
+use korni::{DependencyGraph, Korni};
+
+fn graph(input: &str) -> DependencyGraph {
+    Korni::from_str(input).parse().unwrap().dependency_graph()
+}
+
+#[test]
+fn test_dependencies_and_dependents() {
+    let g = graph("\
+HOST=localhost
+PORT=5432
+URL=\"postgres://${HOST}:${PORT}/${HOST}\"
+BACKUP=${URL:-none}
+LITERAL='${HOST}'
+HOME_DIR=${HOME}
+");
+    assert_eq!(g.dependencies("URL"), ["HOST", "PORT"]);
+    assert_eq!(g.dependencies("BACKUP"), ["URL"]);
+    assert!(g.dependencies("LITERAL").is_empty());
+    assert!(g.dependencies("MISSING").is_empty());
+    assert_eq!(g.dependents("HOST"), ["URL"]);
+    assert_eq!(g.undefined(), ["HOME"]);
+    assert!(g.cycles().is_empty());
+
+    let order = g.topological_order().unwrap();
+    let pos = |key: &str| order.iter().position(|k| *k == key).unwrap();
+    assert_eq!(order.len(), 6);
+    assert!(pos("HOST") < pos("URL") && pos("PORT") < pos("URL") && pos("URL") < pos("BACKUP"));
+}
+
+#[test]
+fn test_cycles() {
+    let g = graph("A=${B}\nB=${C}\nC=${A}\nSELF=${SELF}\nD=${A}\n");
+    assert_eq!(g.cycles(), vec![vec!["A", "B", "C"], vec!["SELF"]]);
+    assert_eq!(g.topological_order(), Err(vec![vec!["A", "B", "C"], vec!["SELF"]]));
+}
+
+#[test]
+fn test_escaped_reference_with_source() {
+    let input = "A=1\nB=\"\\${A} ${A}\"\nC=\"\\${A}\"\n";
+    let env = Korni::from_str(input).track_positions().retain_source().parse().unwrap();
+    let g = env.dependency_graph();
+    assert_eq!(g.dependencies("B"), ["A"]);
+    assert!(g.dependencies("C").is_empty());
+
+    // Without the source only the decoded value is available
+    assert_eq!(graph(input).dependencies("C"), ["A"]);
+}
+
+#[test]
+fn test_to_dot() {
+    let g = graph("A=${B}\nB=x\n");
+    assert_eq!(g.to_dot(), "digraph env {\n    \"A\" -> \"B\";\n    \"B\";\n}\n");
+}
@@ -0,0 +1,209 @@
+use korni::{parse_with_options, Deprecation, Deprecations, Entry, Error, Korni, ParseOptions, Span};
+
+fn duplicate_options() -> ParseOptions {
+    ParseOptions { warn_duplicate_keys: true, ..ParseOptions::default() }
+}
+
+#[test]
+fn test_duplicate_key_warning_has_both_spans() {
+    let entries = parse_with_options("KEY=a\nOTHER=b\nKEY=c", duplicate_options());
+    let warning = entries.iter().find_map(|e| match e {
+        Entry::Warning(w) => Some(w.clone()),
+        _ => None,
+    });
+    assert_eq!(warning, Some(Error::DuplicateKey {
+        key: "KEY".into(),
+        first_span: Span::from_offsets(0, 3),
+        second_span: Span::from_offsets(14, 17),
+    }));
+}
+
+#[test]
+fn test_duplicate_key_warning_follows_shadowing_pair() {
+    let entries = parse_with_options("KEY=a\nKEY=b", duplicate_options());
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].as_pair().unwrap().value, "b");
+    assert!(matches!(entries[2], Entry::Warning(Error::DuplicateKey { .. })));
+}
+
+#[test]
+fn test_duplicate_key_warning_disabled_by_default() {
+    let entries = korni::parse("KEY=a\nKEY=b");
+    assert!(entries.iter().all(|e| !matches!(e, Entry::Warning(_))));
+}
+
+#[test]
+fn test_duplicate_key_warning_ignores_commented_pairs() {
+    let options = ParseOptions { include_comments: true, ..duplicate_options() };
+    let entries = parse_with_options("# KEY=old\nKEY=new", options);
+    assert!(entries.iter().all(|e| !matches!(e, Entry::Warning(_))));
+}
+
+#[test]
+fn test_environment_collects_warnings_separately() {
+    let env = Korni::from_str("KEY=a\nKEY=b").warn_duplicate_keys().parse().unwrap();
+    assert_eq!(env.get("KEY"), Some("b"));
+    assert!(!env.has_errors());
+    assert_eq!(env.warnings().len(), 1);
+    assert!(env.warnings()[0].to_string().contains("Duplicate key 'KEY'"));
+}
+
+#[test]
+fn test_unclosed_quote_names_key_not_value() {
+    let entries = parse_with_options("PRIVATE_KEY=\"-----BEGIN secret", ParseOptions::default());
+    let Entry::Error(e) = &entries[0] else { panic!("expected error, got {:?}", entries[0]) };
+    assert!(matches!(e, Error::UnclosedQuote { key, .. } if key == "PRIVATE_KEY"));
+    let msg = e.to_string();
+    assert_eq!(msg, "Unclosed double quote in value of PRIVATE_KEY starting at byte 12");
+    assert!(!msg.contains("secret"));
+}
+
+#[test]
+fn test_forbidden_whitespace_names_key() {
+    let entries = parse_with_options("TOKEN= abc", ParseOptions::default());
+    let Entry::Error(e) = &entries[0] else { panic!("expected error, got {:?}", entries[0]) };
+    assert_eq!(e.to_string(), "Whitespace not allowed after equals for TOKEN at byte 6");
+    assert!(!e.to_string().contains("abc"));
+}
+
+fn truncation_options() -> ParseOptions {
+    ParseOptions { warn_truncated_values: true, ..ParseOptions::default() }
+}
+
+#[test]
+fn test_truncated_value_warning_spans_dropped_text() {
+    let entries = parse_with_options("A=1\nGREETING=hello world  \n", truncation_options());
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[1].as_pair().unwrap().value, "hello");
+    assert_eq!(entries[2], Entry::Warning(Error::TruncatedValue {
+        key: "GREETING".into(),
+        dropped: Span::from_offsets(19, 24),
+    }));
+    let Entry::Warning(w) = &entries[2] else { unreachable!() };
+    assert!(!w.to_string().contains("world"));
+}
+
+#[test]
+fn test_truncated_value_warning_ignores_comments_and_quotes() {
+    let input = "A=1 # note\nB=2   \nC=\"x y\" \nD='z'\n";
+    let entries = parse_with_options(input, truncation_options());
+    assert!(entries.iter().all(|e| !matches!(e, Entry::Warning(_))), "{:?}", entries);
+}
+
+#[test]
+fn test_truncated_value_warning_disabled_by_default() {
+    let entries = parse_with_options("K=hello world", ParseOptions::default());
+    assert_eq!(entries.len(), 1);
+}
+
+#[test]
+fn test_truncated_value_warning_resolves_positions() {
+    let options = ParseOptions { track_positions: true, ..truncation_options() };
+    let entries = parse_with_options("A=1\nK=a\\\nb c", options);
+    let Entry::Warning(Error::TruncatedValue { dropped, .. }) = &entries[2] else { panic!("{:?}", entries) };
+    assert_eq!((dropped.start.line, dropped.start.col, dropped.start.offset), (2, 2, 11));
+}
+
+#[test]
+fn test_truncated_value_warning_with_duplicate_key() {
+    let options = ParseOptions { warn_duplicate_keys: true, ..truncation_options() };
+    let entries = parse_with_options("K=1\nK=2 3", options);
+    let kinds: Vec<_> = entries.iter().map(|e| match e {
+        Entry::Warning(w) => w.kind(),
+        _ => "entry",
+    }).collect();
+    assert_eq!(kinds, ["entry", "entry", "TruncatedValue", "DuplicateKey"]);
+}
+
+#[test]
+fn test_builder_warn_truncated_values() {
+    let env = Korni::from_str("K=hello world").warn_truncated_values().parse().unwrap();
+    assert_eq!(env.get("K"), Some("hello"));
+    assert_eq!(env.warnings().len(), 1);
+}
+
+#[test]
+fn test_max_value_len_truncates_with_warning() {
+    let options = ParseOptions { max_value_len: Some(4), ..ParseOptions::default() };
+    let entries = parse_with_options("BIG=abcdefgh\nESC=\"a\\nbcdef\"\nUTF=aaaéb\nOK=abcd\n", options);
+
+    let big = entries[0].as_pair().unwrap();
+    assert_eq!((big.value.as_ref(), big.truncated, big.decoded_len), ("abcd", true, 8));
+    assert_eq!(entries[1], Entry::Warning(Error::ValueTooLong { key: "BIG".into(), len: 8, limit: 4, offset: 4 }));
+    assert_eq!(entries[2].as_pair().unwrap().value, "a\nbc");
+    // Cut at a character boundary
+    assert_eq!(entries[4].as_pair().unwrap().value, "aaa");
+    let ok = entries[6].as_pair().unwrap();
+    assert_eq!((ok.value.as_ref(), ok.truncated), ("abcd", false));
+    assert_eq!(entries.len(), 7);
+
+    let env = Korni::from_str("BIG=abcdefgh").max_value_len(2).parse().unwrap();
+    assert_eq!(env.get("BIG"), Some("ab"));
+    assert_eq!(env.warnings()[0].to_string(), "Value of BIG at byte 4 is 8 bytes; truncated to 2");
+}
+
+#[test]
+fn test_deprecation_warnings() {
+    let options = ParseOptions { deprecations: Deprecations::ALL, ..ParseOptions::default() };
+    let entries = parse_with_options("export A=1\nB=\"x\" junk\n# C=3\nD='y' # fine\n# just a comment\n", options);
+    let warnings: Vec<&Error> = entries.iter().filter_map(|e| match e {
+        Entry::Warning(w) => Some(w),
+        _ => None,
+    }).collect();
+    assert_eq!(warnings, [
+        &Error::Deprecated { rule: Deprecation::ExportPrefix, key: "A".into(), offset: 0 },
+        &Error::Deprecated { rule: Deprecation::TrailingContent, key: "B".into(), offset: 17 },
+        &Error::Deprecated { rule: Deprecation::CommentedPair, key: "C".into(), offset: 22 },
+    ]);
+    // Reported in place
+    assert!(matches!(&entries[4], Entry::Warning(Error::Deprecated { rule: Deprecation::CommentedPair, .. })));
+    assert_eq!(warnings[1].to_string(), "Text after the closing quote of B at byte 17 is ignored");
+
+    let full = ParseOptions { deprecations: Deprecations::ALL, ..ParseOptions::full() };
+    let entries = parse_with_options("# C=3\n", full);
+    assert!(entries[0].as_pair().unwrap().is_comment);
+    assert_eq!(entries[1].as_warning().map(Error::kind), Some("Deprecated"));
+}
+
+#[test]
+fn test_deprecation_rules_are_separate() {
+    let rules = Deprecations { export_prefix: true, ..Deprecations::default() };
+    let env = Korni::from_str("export A=1\nB='x' junk\n# C=3\n").warn_deprecated(rules).parse().unwrap();
+    assert_eq!(env.warnings().len(), 1);
+    assert_eq!(env.warnings()[0].to_string(), "'export' prefix on A at byte 0");
+
+    let env = Korni::from_str("export A=1\nB='x' junk\n# C=3\n").parse().unwrap();
+    assert!(env.warnings().is_empty());
+}
+
+fn fixed(input: &str) -> Option<String> {
+    let error = parse_with_options(input, ParseOptions::default()).into_iter().find_map(|e| match e {
+        Entry::Error(e) => Some(e),
+        _ => None,
+    })?;
+    Some(error.fix(input)?.apply(input))
+}
+
+#[test]
+fn test_error_fixes() {
+    assert_eq!(fixed("KEY  =value\n").as_deref(), Some("KEY=value\n"));
+    assert_eq!(fixed("KEY= value\n").as_deref(), Some("KEY=value\n"));
+    assert_eq!(fixed("KEY==value\n").as_deref(), Some("KEY=value\n"));
+    assert_eq!(fixed("1KEY=value\n").as_deref(), Some("_1KEY=value\n"));
+    assert_eq!(fixed("A=1\nKEY='open  \nB=2\n").as_deref(), Some("A=1\nKEY='open'  \nB=2\n"));
+    assert_eq!(fixed("KEY=\"open\r\n").as_deref(), Some("KEY=\"open\"\r\n"));
+    assert_eq!(fixed("bad line\n"), None);
+
+    // Fixes follow the structured reason, not its message
+    let digit = korni::Error::InvalidKey { offset: 0, reason: korni::InvalidKeyReason::StartsWithDigit };
+    assert_eq!(digit.to_string(), "Invalid key at byte 0: Key starts with digit");
+    assert!(digit.fix("1KEY=value").is_some());
+    let ascii = korni::Error::InvalidKey { offset: 0, reason: korni::InvalidKeyReason::NonAscii };
+    assert!(ascii.fix("ÉKEY=value").is_none());
+
+    let input = "OK=1\nKEY =v\n";
+    let error = parse_with_options(input, ParseOptions::default())[1].as_error().cloned().unwrap();
+    let fix = error.fix(input).unwrap();
+    assert_eq!((fix.span.start.line, fix.span.start.col, fix.span.end.col), (1, 3, 4));
+    assert_eq!(fix.replacement, "");
+}
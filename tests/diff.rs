@@ -0,0 +1,105 @@
+use korni::{Change, Document, Korni};
+
+fn env(input: &str) -> korni::Environment<'_> {
+    Korni::from_str(input).preserve_comments().parse().unwrap()
+}
+
+#[test]
+fn test_environment_diff() {
+    let old = env("A=1\nB=2\nC=3\n# D=4\n");
+    let new = env("A=1\nB=20\nE=5\n");
+
+    let diff = old.diff(&new);
+    assert_eq!(diff.changes, vec![
+        Change::Changed { key: "B".into(), old: "2".into(), new: "20".into() },
+        Change::Removed { key: "C".into(), value: "3".into() },
+        Change::Added { key: "E".into(), value: "5".into() },
+    ]);
+    assert_eq!(diff.len(), 3);
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn test_diff_apply_to_document_touches_only_changed_lines() {
+    let local = "\
+# Local settings
+export API_URL=http://localhost # dev
+DB_PASSWORD='old'
+REMOVED=1
+KEEP=yes
+";
+    let prod = env("API_URL=\"https://api.example.com\"\nDB_PASSWORD='new secret'\nKEEP=yes\nADDED=1\n");
+
+    let mut doc = Document::parse(local);
+    doc.to_env().diff(&prod).apply_to(&mut doc);
+
+    assert_eq!(doc.as_str(), "\
+# Local settings
+export API_URL=https://api.example.com # dev
+DB_PASSWORD='new secret'
+KEEP=yes
+ADDED=1
+");
+    assert!(doc.to_env().diff(&prod).is_empty());
+}
+
+#[test]
+fn test_document_set_and_remove() {
+    let mut doc = Document::parse("A=1\nB=\nA=2");
+    doc.set("A", "3");
+    doc.set("B", "x");
+    doc.set("C", "4");
+    assert_eq!(doc.as_str(), "A=1\nB=x\nA=3\nC=4\n");
+
+    assert!(doc.remove("A"));
+    assert!(!doc.remove("A"));
+    assert_eq!(doc.as_str(), "B=x\nC=4\n");
+}
+
+#[test]
+fn test_document_remove_multiline_value() {
+    let mut doc = Document::parse("A=\"x\ny\"\nB=1\n");
+    doc.remove("A");
+    assert_eq!(doc.as_str(), "B=1\n");
+}
+
+#[test]
+fn test_diff_ignoring_volatile_keys() {
+    let old = env("A=1\nBUILD_NUMBER=41\nBUILD_SHA=abc\nDEPLOYED_AT=monday\nREBUILD=1\n");
+    let new = env("A=2\nBUILD_NUMBER=42\nDEPLOYED_AT=tuesday\nREBUILD=2\n");
+
+    let diff = old.diff(&new).ignoring(["BUILD_*", "DEPLOYED_AT"]);
+    assert_eq!(diff.changes, vec![
+        Change::Changed { key: "A".into(), old: "1".into(), new: "2".into() },
+        Change::Changed { key: "REBUILD".into(), old: "1".into(), new: "2".into() },
+    ]);
+    assert!(old.diff(&new).ignoring(vec!["*".to_string()]).is_empty());
+    assert_eq!(old.diff(&new).ignoring(Vec::<&str>::new()).len(), 5);
+}
+
+#[test]
+fn test_diff_to_json() {
+    let old = env("A=1\nB=2\nC=\"say \\\"hi\\\"\"\n");
+    let new = env("A=1\nB=20\nD=x\n");
+
+    assert_eq!(old.diff(&new).to_json(), concat!(
+        r#"{"added":[{"key":"D","value":"x"}],"#,
+        r#""removed":[{"key":"C","value":"say \"hi\""}],"#,
+        r#""changed":[{"key":"B","old":"2","new":"20"}]}"#,
+    ));
+    assert_eq!(old.diff(&old).to_json(), r#"{"added":[],"removed":[],"changed":[]}"#);
+}
+
+#[test]
+fn test_diff_redact() {
+    let old = env("DB_PASSWORD=old\nHOST=a\n");
+    let new = env("DB_PASSWORD=new\nHOST=b\nAPI_KEY=k\n");
+
+    let diff = old.diff(&new).redact(["*_PASSWORD", "API_KEY"]);
+    assert_eq!(diff.changes, vec![
+        Change::Added { key: "API_KEY".into(), value: korni::REDACTED.into() },
+        Change::Changed { key: "DB_PASSWORD".into(), old: korni::REDACTED.into(), new: korni::REDACTED.into() },
+        Change::Changed { key: "HOST".into(), old: "a".into(), new: "b".into() },
+    ]);
+    assert!(!old.diff(&new).redact(["*"]).to_json().contains(r#""b""#));
+}
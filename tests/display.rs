@@ -0,0 +1,110 @@
+use korni::{encode_value, parse, parse_with_options, Korni, ParseOptions, QuoteType};
+
+#[test]
+fn test_pair_display_preserves_quote_style() {
+    let entries = parse("A=plain\nB='single'\nC=\"double\"\nexport D=1");
+    let lines: Vec<_> = entries.iter().map(|e| e.to_string()).collect();
+    assert_eq!(lines, vec!["A=plain", "B='single'", "C=\"double\"", "export D=1"]);
+}
+
+#[test]
+fn test_pair_display_escapes_double_quoted() {
+    let entries = parse(r#"K="a\nb \"q\" \\ \$x""#);
+    assert_eq!(entries[0].to_string(), r#"K="a\nb \"q\" \\ \$x""#);
+}
+
+#[test]
+fn test_commented_pair_display() {
+    let entries = parse_with_options("# OLD=1", ParseOptions::full());
+    assert_eq!(entries[0].to_string(), "# OLD=1");
+}
+
+#[test]
+fn test_error_entry_display_is_comment() {
+    let entries = parse("1K=v");
+    assert_eq!(entries[0].to_string(), "# error: Invalid key at byte 0: Key starts with digit");
+}
+
+#[test]
+fn test_encode_value_escalates() {
+    assert_eq!(encode_value("has space", QuoteType::None), (QuoteType::Single, "'has space'".into()));
+    assert_eq!(encode_value("it's here", QuoteType::None), (QuoteType::Double, "\"it's here\"".into()));
+    assert_eq!(encode_value("$HOME path", QuoteType::None), (QuoteType::Single, "'$HOME path'".into()));
+    assert_eq!(encode_value("it's", QuoteType::Single), (QuoteType::Double, "\"it's\"".into()));
+    assert_eq!(encode_value("'start", QuoteType::None).0, QuoteType::Double);
+    assert_eq!(encode_value("trail\\", QuoteType::None).0, QuoteType::Single);
+    assert_eq!(encode_value("two\nlines", QuoteType::None).0, QuoteType::Double);
+    assert_eq!(encode_value("", QuoteType::None), (QuoteType::None, String::new()));
+}
+
+#[test]
+fn test_environment_display_round_trips() {
+    let input = "Z=last\nA='x y'\nM=\"tab\\there\"";
+    let env = Korni::from_str(input).parse().unwrap();
+    let rendered = env.to_string();
+    assert_eq!(rendered, "Z=last\nA='x y'\nM=\"tab\\there\"\n");
+
+    let reparsed = Korni::from_str(&rendered).parse().unwrap();
+    assert_eq!(reparsed.to_map(), env.to_map());
+}
+
+#[test]
+fn test_environment_display_sorted() {
+    let env = Korni::from_str("B=2\nA=1\nC=3").parse().unwrap();
+    assert_eq!(env.display_sorted().to_string(), "A=1\nB=2\nC=3\n");
+}
+
+#[test]
+fn test_display_with_export_policy() {
+    use korni::{ExportPolicy, WriteOptions};
+
+    let env = Korni::from_str("export B=2\nA='x y'\n").parse().unwrap();
+    let render = |export, sort_keys| env.display_with(WriteOptions { export, sort_keys, ..WriteOptions::default() }).to_string();
+    assert_eq!(render(ExportPolicy::Preserve, false), "export B=2\nA='x y'\n");
+    assert_eq!(render(ExportPolicy::Always, false), "export B=2\nexport A='x y'\n");
+    assert_eq!(render(ExportPolicy::Never, true), "A='x y'\nB=2\n");
+    assert_eq!(env.display_with(WriteOptions::default()).to_string(), env.to_string());
+}
+
+#[test]
+fn test_display_with_fold_strategies() {
+    use korni::{Fold, WriteOptions};
+
+    let input = "NOTE=\"line one\nline two\"\nTOKEN=abcdefghijklmnopqrstuvwxyz\nexport S=ok\n";
+    let env = Korni::from_str(input).parse().unwrap();
+    let render = |fold| env.display_with(WriteOptions { fold, ..WriteOptions::default() }).to_string();
+
+    assert_eq!(render(Fold::SingleLine), "NOTE=\"line one\\nline two\"\nTOKEN=abcdefghijklmnopqrstuvwxyz\nexport S=ok\n");
+    assert_eq!(render(Fold::Multiline), input);
+    assert_eq!(render(Fold::Continuation(12)), "\
+NOTE=\"line one\\nline two\"
+TOKEN=abcde\\
+fghijklmnop\\
+qrstuvwxyz
+export S=ok
+");
+
+    for fold in [Fold::SingleLine, Fold::Multiline, Fold::Continuation(12), Fold::Continuation(0)] {
+        let reparsed = Korni::from_str(&render(fold)).parse().unwrap().to_map();
+        assert_eq!(reparsed, env.to_map(), "{:?}", fold);
+    }
+}
+
+#[test]
+fn test_display_with_comment_policy() {
+    use korni::{CommentPolicy, WriteOptions};
+
+    let input = "# Generated by deploy\n# do not edit\nB=2\n# database\nA=1\n# OLD=0\n";
+    let env = Korni::from_str(input).preserve_comments().track_positions().retain_source().parse().unwrap();
+    let render = |comments, sort_keys| env.display_with(WriteOptions { comments, sort_keys, ..WriteOptions::default() }).to_string();
+
+    assert_eq!(render(CommentPolicy::Keep, false), input);
+    assert_eq!(env.to_string(), input);
+    assert_eq!(render(CommentPolicy::HeaderOnly, false), "# Generated by deploy\n# do not edit\nB=2\nA=1\n");
+    assert_eq!(render(CommentPolicy::Strip, false), "B=2\nA=1\n");
+    assert_eq!(render(CommentPolicy::Keep, true), "# Generated by deploy\n# do not edit\nA=1\nB=2\n# OLD=0\n");
+
+    // Without the source there is no comment text to write
+    let env = Korni::from_str(input).preserve_comments().parse().unwrap();
+    assert_eq!(env.to_string(), "B=2\nA=1\n# OLD=0\n");
+}
@@ -0,0 +1,416 @@
+use korni::{Document, Error, InvalidKeyReason, ParseOptions, SaveOptions};
+
+const INPUT: &str = "\
+APP_NAME=demo
+
+# --- Database ---
+DB_HOST=localhost
+DB_PORT=5432
+
+# ==== Cache ====
+# connection string
+REDIS_URL=redis://x
+# -----
+# --- Empty ---
+";
+
+#[test]
+fn test_document_is_lossless() {
+    let doc = Document::parse(INPUT);
+    assert_eq!(doc.to_string(), INPUT);
+    assert_eq!(doc.as_str(), INPUT);
+    assert_eq!(doc.get("DB_PORT").unwrap().value, "5432");
+    assert_eq!(doc.to_env().get("REDIS_URL"), Some("redis://x"));
+}
+
+#[test]
+fn test_sections() {
+    let doc = Document::parse(INPUT);
+    let sections = doc.sections();
+
+    let names: Vec<_> = sections.iter().map(|s| s.name).collect();
+    assert_eq!(names, vec![None, Some("Database"), Some("Cache"), Some("Empty")]);
+
+    let keys = |i: usize| sections[i].pairs.iter().map(|kv| kv.key.as_ref()).collect::<Vec<_>>();
+    assert_eq!(keys(0), vec!["APP_NAME"]);
+    assert_eq!(keys(1), vec!["DB_HOST", "DB_PORT"]);
+    assert_eq!(keys(2), vec!["REDIS_URL"]);
+    assert!(keys(3).is_empty());
+
+    assert!(sections[0].header.is_none());
+    assert_eq!(sections[1].header.unwrap().span.start.line, 2);
+}
+
+#[test]
+fn test_section_lookup() {
+    let doc = Document::parse(INPUT);
+    let db = doc.section("Database").unwrap();
+    assert_eq!(db.pairs.len(), 2);
+    assert!(doc.section("Missing").is_none());
+}
+
+#[test]
+fn test_no_leading_section_without_pairs() {
+    let doc = Document::parse("# --- Only ---\nA=1\n");
+    let sections = doc.sections();
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].name, Some("Only"));
+}
+
+#[test]
+fn test_rule_without_name_is_not_a_header() {
+    let doc = Document::parse("# ------\nA=1\n# -- Short --\nB=2\n");
+    let sections = doc.sections();
+    assert_eq!(sections.len(), 1);
+    assert_eq!(sections[0].name, None);
+    assert_eq!(sections[0].pairs.len(), 2);
+}
+
+#[test]
+fn test_sort_keys_moves_comments_with_pairs() {
+    let mut doc = Document::parse("\
+# file header
+
+# the zebra
+ZEBRA=1 # inline
+APPLE=2
+# multi-line
+MANGO=\"a
+b\"
+");
+    doc.sort_keys();
+    assert_eq!(doc.as_str(), "\
+# file header
+
+APPLE=2
+# multi-line
+MANGO=\"a
+b\"
+# the zebra
+ZEBRA=1 # inline
+");
+    assert_eq!(doc.get("MANGO").unwrap().value, "a\nb");
+}
+
+#[test]
+fn test_sort_keys_whole_file_vs_sections() {
+    let input = "\
+# --- B ---
+D=1
+C=2
+
+# --- A ---
+B=3
+A=4";
+
+    let mut doc = Document::parse(input);
+    doc.sort_keys_in_sections();
+    assert_eq!(doc.as_str(), "# --- B ---\nC=2\nD=1\n\n# --- A ---\nA=4\nB=3");
+
+    let mut doc = Document::parse(input);
+    doc.sort_keys();
+    assert_eq!(doc.as_str(), "# --- B ---\nA=4\nB=3\n\n# --- A ---\nC=2\nD=1");
+}
+
+#[test]
+fn test_sort_keys_keeps_sorted_file_and_crlf() {
+    let input = "A=1\r\nB=2\r\n";
+    let mut doc = Document::parse(input);
+    doc.sort_keys();
+    assert_eq!(doc.as_str(), input);
+
+    let mut doc = Document::parse("B=2\r\nA=1");
+    doc.sort_keys();
+    assert_eq!(doc.as_str(), "A=1\r\nB=2");
+}
+
+#[test]
+fn test_set_keeps_quote_style() {
+    let mut doc = Document::parse("A='one'\nB=\"two\"\nC=three # note\n");
+    doc.set("A", "uno");
+    doc.set("B", "dos");
+    doc.set("C", "tres");
+    assert_eq!(doc.as_str(), "A='uno'\nB=\"dos\"\nC=tres # note\n");
+}
+
+#[test]
+fn test_set_escalates_quotes_minimally() {
+    let mut doc = Document::parse("A='one'\nB=two\n");
+    doc.set("A", "it's");
+    doc.set("B", "two words");
+    doc.set("NEW", "a b");
+    assert_eq!(doc.as_str(), "A=\"it's\"\nB='two words'\nNEW='a b'\n");
+    assert_eq!(doc.get("A").unwrap().value, "it's");
+}
+
+#[test]
+fn test_upsert_appends_to_prefix_group() {
+    let mut doc = Document::parse(INPUT);
+    doc.upsert("DB_USER", "admin", Some("login for the primary"));
+    doc.upsert("REDIS_TTL", "60", None);
+    doc.upsert("LOG_LEVEL", "info", Some("verbosity"));
+    assert_eq!(doc.as_str(), "\
+APP_NAME=demo
+
+# --- Database ---
+DB_HOST=localhost
+DB_PORT=5432
+# login for the primary
+DB_USER=admin
+
+# ==== Cache ====
+# connection string
+REDIS_URL=redis://x
+REDIS_TTL=60
+# -----
+# --- Empty ---
+# verbosity
+LOG_LEVEL=info
+");
+}
+
+#[test]
+fn test_upsert_updates_in_place() {
+    let mut doc = Document::parse("A_X='1'\nB=2");
+    doc.upsert("A_X", "one", Some("ignored"));
+    doc.upsert("B_Y", "3", None);
+    doc.upsert("A_Z", "two\nlines", Some("multi\n\nline"));
+    assert_eq!(doc.as_str(), "A_X='one'\n# multi\n#\n# line\nA_Z=\"two\\nlines\"\nB=2\nB_Y=3\n");
+    assert_eq!(doc.get("A_Z").unwrap().value, "two\nlines");
+}
+
+#[test]
+fn test_edit_batch_commit() {
+    let mut doc = Document::parse("A=1\nB='2'\nC=3\nC=4");
+    doc.edit()
+        .set("A", "one")
+        .set("B", "two words")
+        .remove("C")
+        .set("D", "4")
+        .set("E", "5")
+        .remove("MISSING")
+        .commit();
+    assert_eq!(doc.as_str(), "A=one\nB='two words'\nD=4\nE=5\n");
+    assert_eq!(doc.get("E").unwrap().value, "5");
+}
+
+#[test]
+fn test_edit_later_operation_on_key_wins() {
+    let mut doc = Document::parse("A=1\n");
+    doc.edit().set("A", "2").remove("A").set("B", "x").set("B", "y").commit();
+    assert_eq!(doc.as_str(), "B=y\n");
+}
+
+#[test]
+fn test_edit_dry_run_leaves_document_unchanged() {
+    let mut doc = Document::parse("A=1\nB=2\nC=3\n");
+    let edit = doc.edit().set("B", "two").remove("C").set("D", "4");
+    assert_eq!(edit.dry_run(), "@@ -2,2 +2,2 @@\n-B=2\n-C=3\n+B=two\n+D=4\n");
+    drop(edit);
+    assert_eq!(doc.as_str(), "A=1\nB=2\nC=3\n");
+
+    let edit = doc.edit().remove("A").set("C", "x");
+    assert_eq!(edit.dry_run(), "@@ -1,1 +0,0 @@\n-A=1\n@@ -3,1 +2,1 @@\n-C=3\n+C=x\n");
+    assert_eq!(doc.edit().dry_run(), "");
+}
+
+#[test]
+fn test_edit_dry_run_of_large_file() {
+    // 50,000 lines: a full table of line pairs would need gigabytes
+    let source: String = (0..50_000).map(|i| format!("KEY_{}={}\n", i, i)).collect();
+    let mut doc = Document::parse(&source);
+    let edit = doc.edit().set("KEY_25000", "x");
+    assert_eq!(edit.dry_run(), "@@ -25001,1 +25001,1 @@\n-KEY_25000=25000\n+KEY_25000=x\n");
+}
+
+#[test]
+fn test_edit_dry_run_marks_missing_newline() {
+    let mut doc = Document::parse("A=1");
+    let edit = doc.edit().set("B", "2");
+    assert_eq!(edit.dry_run(), "@@ -1,1 +1,2 @@\n-A=1\n\\ No newline at end of file\n+A=1\n+B=2\n");
+    edit.set("A", "").commit();
+    assert_eq!(doc.as_str(), "A=\nB=2\n");
+}
+
+#[test]
+fn test_rename_key_rewrites_references() {
+    let mut doc = Document::parse("\
+DB_HOST=db
+# DB_HOST=old
+URL=\"postgres://${DB_HOST}:5432 \\${DB_HOST}\"
+FALLBACK=${DB_HOST:-localhost}
+LITERAL='${DB_HOST}'
+OTHER=${DB_HOSTNAME}
+");
+    assert_eq!(doc.rename_key("DB_HOST", "DATABASE_HOST"), Ok(true));
+    assert_eq!(doc.as_str(), "\
+DATABASE_HOST=db
+# DATABASE_HOST=old
+URL=\"postgres://${DATABASE_HOST}:5432 \\${DB_HOST}\"
+FALLBACK=${DATABASE_HOST:-localhost}
+LITERAL='${DB_HOST}'
+OTHER=${DB_HOSTNAME}
+");
+    assert_eq!(doc.get("DATABASE_HOST").unwrap().value, "db");
+}
+
+#[test]
+fn test_rename_key_errors() {
+    let mut doc = Document::parse("A=1\nB=${A}\n");
+    assert_eq!(doc.rename_key("MISSING", "C"), Ok(false));
+    assert_eq!(doc.rename_key("A", "B").unwrap_err().kind(), "Generic");
+    assert_eq!(doc.rename_key("A", "1X"), Err(Error::InvalidKey { offset: 0, reason: InvalidKeyReason::StartsWithDigit }));
+    assert_eq!(doc.rename_key("A", "NOT VALID"), Err(Error::Expected { offset: 4, expected: "'='" }));
+    assert_eq!(doc.rename_key("A", "CLÉ"), Err(Error::InvalidKey { offset: 2, reason: InvalidKeyReason::NonAscii }));
+    assert_eq!(doc.as_str(), "A=1\nB=${A}\n");
+
+    let mut doc = Document::parse_with_options("A=1\n", ParseOptions { unicode_keys: true, ..ParseOptions::default() });
+    assert_eq!(doc.rename_key("A", "CLÉ"), Ok(true));
+    assert_eq!(doc.as_str(), "CLÉ=1\n");
+}
+
+fn scratch_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("korni-{}-{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_save_round_trip_with_backup() {
+    let dir = scratch_dir("save");
+    let path = dir.join(".env");
+    std::fs::write(&path, "A=1\n").unwrap();
+
+    let mut doc = Document::from_file(&path).unwrap();
+    doc.set("A", "2");
+    doc.save_with(&path, SaveOptions { backup: true }).unwrap();
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "A=2\n");
+    assert_eq!(std::fs::read_to_string(dir.join(".env.bak")).unwrap(), "A=1\n");
+    let leftovers: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+    assert_eq!(leftovers.len(), 2, "{:?}", leftovers);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_without_backup_creates_file() {
+    let dir = scratch_dir("save-new");
+    let path = dir.join("app.env");
+    Document::parse("A=1\n").save(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "A=1\n");
+    assert!(!dir.join("app.env.bak").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_save_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = scratch_dir("save-perms");
+    let secrets = dir.join("secrets.env");
+    Document::parse("DB_PASSWORD=x\n").save(&secrets).unwrap();
+    assert_eq!(std::fs::metadata(&secrets).unwrap().permissions().mode() & 0o777, 0o600);
+
+    let shared = dir.join("shared.env");
+    std::fs::write(&shared, "A=1\n").unwrap();
+    std::fs::set_permissions(&shared, std::fs::Permissions::from_mode(0o640)).unwrap();
+    Document::parse("API_TOKEN=y\n").save(&shared).unwrap();
+    assert_eq!(std::fs::metadata(&shared).unwrap().permissions().mode() & 0o777, 0o640);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_from_file_missing() {
+    let err = Document::from_file("/nonexistent/korni/.env").unwrap_err();
+    assert!(matches!(err, korni::Error::Io(_)));
+}
+
+#[test]
+fn test_enable_and_disable_round_trip() {
+    let source = "A=1\n  #  export DEBUG=\"yes\"  # verbose\nB=2\n";
+    let mut doc = Document::parse(source);
+
+    assert!(doc.enable("DEBUG"));
+    assert_eq!(doc.as_str(), "A=1\n  export DEBUG=\"yes\"  # verbose\nB=2\n");
+    assert_eq!(doc.get("DEBUG").unwrap().value, "yes");
+
+    assert!(doc.disable("DEBUG"));
+    assert_eq!(doc.as_str(), "A=1\n  # export DEBUG=\"yes\"  # verbose\nB=2\n");
+    assert!(doc.get("DEBUG").is_none());
+    assert!(doc.enable("DEBUG"));
+    assert!(doc.disable("DEBUG"));
+    assert!(doc.enable("DEBUG"));
+    assert_eq!(doc.as_str(), "A=1\n  export DEBUG=\"yes\"  # verbose\nB=2\n");
+}
+
+#[test]
+fn test_enable_picks_last_commented_definition() {
+    let mut doc = Document::parse("# MODE=dev\n# MODE=prod\nOTHER=1\n");
+    assert!(doc.enable("MODE"));
+    assert_eq!(doc.as_str(), "# MODE=dev\nMODE=prod\nOTHER=1\n");
+    assert!(!doc.enable("MISSING"));
+    assert!(!doc.enable("OTHER"));
+}
+
+#[test]
+fn test_disable_comments_out_every_definition() {
+    let mut doc = Document::parse("K=1\nL=\"multi\nline\"\nK=2\n");
+    assert!(doc.disable("K"));
+    assert!(doc.disable("L"));
+    assert_eq!(doc.as_str(), "# K=1\n# L=\"multi\nline\"\n# K=2\n");
+    assert!(doc.get("K").is_none() && doc.get("L").is_none());
+    assert!(!doc.disable("K"));
+}
+
+#[test]
+fn test_entry_at_resolves_parts() {
+    use korni::{Located, Part};
+    let doc = Document::parse("# header\nexport KEY=\"value\" # note\n# OLD=1\n");
+    let part = |line, col| match doc.entry_at(line, col) {
+        Some(Located::Pair { pair, part }) => Some((pair.key.as_ref(), part)),
+        _ => None,
+    };
+
+    assert!(matches!(doc.entry_at(0, 3), Some(Located::Comment(c)) if c.content(doc.as_str()) == "header"));
+    assert_eq!(part(1, 0), Some(("KEY", Part::Export)));
+    assert_eq!(part(1, 7), Some(("KEY", Part::Key)));
+    assert_eq!(part(1, 10), Some(("KEY", Part::Equals)));
+    assert_eq!(part(1, 11), Some(("KEY", Part::Value)));
+    assert_eq!(part(1, 17), Some(("KEY", Part::Value)));
+    assert_eq!(part(1, 18), None);
+    assert_eq!(part(1, 21), Some(("KEY", Part::Comment)));
+    assert_eq!(part(2, 0), Some(("OLD", Part::Comment)));
+    assert_eq!(part(2, 2), Some(("OLD", Part::Key)));
+    assert_eq!(part(2, 6), Some(("OLD", Part::Value)));
+    assert!(doc.entry_at(9, 0).is_none());
+}
+
+#[test]
+fn test_occurrences_of_key() {
+    use korni::OccurrenceKind::{Commented, Definition, Reference};
+    let source = "DB_HOST=db\n# DB_HOST=old\nURL=\"postgres://${DB_HOST}\"\nLITERAL='${DB_HOST}'\n";
+    let doc = Document::parse(source);
+    let found: Vec<_> = doc.occurrences("DB_HOST").into_iter()
+        .map(|o| (o.kind, o.span.start.line, &source[o.span.range()]))
+        .collect();
+    assert_eq!(found, [(Definition, 0, "DB_HOST"), (Commented, 1, "DB_HOST"), (Reference, 2, "DB_HOST")]);
+
+    let reference = doc.occurrences("DB_HOST")[2];
+    assert_eq!((reference.span.start.col, reference.span.end.col), (18, 25));
+    assert!(doc.occurrences("MISSING").is_empty());
+}
+
+#[test]
+fn test_from_pairs() {
+    let doc = Document::from_pairs([("A", "1"), ("B", "two words"), ("C", "it's \"x\""), ("A", "3")]).unwrap();
+    assert_eq!(doc.to_string(), "A=3\nB='two words'\nC=\"it's \\\"x\\\"\"\n");
+    assert_eq!(doc.get("B").unwrap().value, "two words");
+
+    let empty = Document::from_pairs(Vec::<(String, String)>::new()).unwrap();
+    assert_eq!(empty.to_string(), "");
+    assert!(Document::from_pairs([("BAD KEY", "x")]).is_err());
+}
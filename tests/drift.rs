@@ -0,0 +1,45 @@
+use std::time::Duration;
+use korni::{Change, Korni, REDACTED};
+
+fn write(path: &std::path::Path, content: &str) {
+    std::fs::write(path, content).unwrap();
+    // Make sure the next check sees a new modification time or length
+    std::thread::sleep(Duration::from_millis(20));
+}
+
+#[test]
+fn test_watch_reports_changes_in_drift() {
+    let dir = std::env::temp_dir().join(format!("korni-drift-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(".env");
+    let reference = Korni::from_str("HOST=db\nDB_PASSWORD=s3cret\nBUILD_ID=1\n").parse().unwrap();
+
+    write(&path, "HOST=db\nDB_PASSWORD=s3cret\nBUILD_ID=7\n");
+    let mut watch = korni::drift::watch(&path, &reference, Duration::from_millis(5)).ignoring(["BUILD_*"]);
+    assert!(watch.poll().is_none(), "in sync apart from ignored keys");
+
+    write(&path, "HOST=other\nDB_PASSWORD=leaked\nBUILD_ID=8\n");
+    let diff = watch.next().unwrap().unwrap();
+    assert_eq!(diff.changes, vec![
+        Change::Changed { key: "DB_PASSWORD".into(), old: REDACTED.into(), new: REDACTED.into() },
+        Change::Changed { key: "HOST".into(), old: "db".into(), new: "other".into() },
+    ]);
+
+    // Same drift again (only an ignored key changed): nothing to report
+    write(&path, "HOST=other\nDB_PASSWORD=leaked\nBUILD_ID=9\n");
+    assert!(watch.poll().is_none());
+
+    // A secret changing again looks the same once redacted, but is drift
+    write(&path, "HOST=other\nDB_PASSWORD=leaked-again\nBUILD_ID=9\n");
+    let diff = watch.poll().unwrap().unwrap();
+    assert_eq!(diff.changes[0], Change::Changed { key: "DB_PASSWORD".into(), old: REDACTED.into(), new: REDACTED.into() });
+
+    std::fs::remove_file(&path).unwrap();
+    assert!(matches!(watch.poll(), Some(Err(korni::Error::Io(_)))));
+    assert!(watch.poll().is_none(), "errors are reported once");
+
+    write(&path, "HOST=db\nDB_PASSWORD=s3cret\n");
+    assert!(watch.poll().unwrap().unwrap().is_empty(), "back in sync");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -163,3 +163,21 @@ fn test_invalid_utf8_in_middle() {
         _ => panic!("Expected InvalidUtf8 error"),
     }
 }
+
+#[test]
+fn test_unparseable_lines_are_reported() {
+    let input = "-DASHED=1\n;SEMI=2\nOK=3\n@at";
+    let entries = parse(input);
+    assert_eq!(entries.len(), 4);
+    assert!(matches!(entries[0], korni::Entry::Error(Error::InvalidKey { offset: 0, .. })));
+    assert!(matches!(entries[1], korni::Entry::Error(Error::InvalidKey { offset: 10, .. })));
+    assert_eq!(entries[2].as_pair().unwrap().key, "OK");
+    assert!(matches!(entries[3], korni::Entry::Error(Error::InvalidKey { offset: 23, .. })));
+}
+
+#[test]
+fn test_blank_crlf_lines_are_not_errors() {
+    let entries = parse("A=1\r\n\r\n  \r\nB=2\r\n\r");
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|e| e.is_pair()));
+}
@@ -0,0 +1,39 @@
+use korni::{parse_with_options, Entry, Error, ParseOptions};
+
+fn entries() -> Vec<Entry<'static>> {
+    let options = ParseOptions { warn_duplicate_keys: true, ..ParseOptions::full() };
+    parse_with_options("# note\nKEY=v\n1BAD=x\nKEY=w", options)
+        .into_iter()
+        .map(Entry::into_owned)
+        .collect()
+}
+
+#[test]
+fn test_predicates() {
+    let entries = entries();
+    assert!(entries[0].is_comment());
+    assert!(entries[1].is_pair());
+    assert!(entries[2].is_error());
+    assert!(entries[4].is_warning());
+    assert!(!entries[0].is_pair());
+}
+
+#[test]
+fn test_as_accessors() {
+    let entries = entries();
+    assert!(entries[0].as_comment().is_some());
+    assert!(entries[1].as_comment().is_none());
+    assert!(matches!(entries[2].as_error(), Some(Error::InvalidKey { .. })));
+    assert!(entries[1].as_error().is_none());
+    assert!(matches!(entries[4].as_warning(), Some(Error::DuplicateKey { .. })));
+}
+
+#[test]
+fn test_key_and_into_pair() {
+    let mut entries = entries();
+    assert_eq!(entries[1].key(), Some("KEY"));
+    assert_eq!(entries[0].key(), None);
+    let pair = entries.remove(1).into_pair().unwrap();
+    assert_eq!(pair.value, "v");
+    assert!(entries.remove(0).into_pair().is_none());
+}
@@ -0,0 +1,318 @@
+use korni::{parse, Korni};
+
+#[test]
+fn test_pair_index_matches_entry_position() {
+    let entries = parse("A=1\nB==2\nC=3");
+    assert_eq!(entries.len(), 3);
+    assert_eq!(entries[0].as_pair().unwrap().index, 0);
+    assert_eq!(entries[2].as_pair().unwrap().index, 2);
+}
+
+#[test]
+fn test_pair_index_counts_comments() {
+    let env = Korni::from_str("# header\nA=1\n# note\nB=2")
+        .preserve_comments()
+        .parse()
+        .unwrap();
+    assert_eq!(env.get_entry("A").unwrap().index, 1);
+    assert_eq!(env.get_entry("B").unwrap().index, 3);
+}
+
+#[test]
+fn test_iter_ordered_follows_source_order() {
+    let input = (0..50).map(|i| format!("KEY_{}=v", 49 - i)).collect::<Vec<_>>().join("\n");
+    let env = Korni::from_str(&input).parse().unwrap();
+    let keys: Vec<_> = env.iter_ordered().map(|kv| kv.key.to_string()).collect();
+    let expected: Vec<_> = (0..50).map(|i| format!("KEY_{}", 49 - i)).collect();
+    assert_eq!(keys, expected);
+}
+
+#[test]
+fn test_error_report_without_source() {
+    let env = Korni::from_str("A=1\nB = 2").parse().unwrap();
+    assert_eq!(env.source(), None);
+    assert_eq!(
+        env.error_report(),
+        "error: Whitespace not allowed between key and equals for B at byte 4"
+    );
+}
+
+#[test]
+fn test_error_report_with_retained_source() {
+    let env = Korni::from_reader(std::io::Cursor::new("A=1\nB = 2"))
+        .retain_source()
+        .parse()
+        .unwrap();
+    assert_eq!(env.source(), Some("A=1\nB = 2"));
+    assert_eq!(
+        env.error_report(),
+        "error: Whitespace not allowed between key and equals for B at byte 4\n --> 2:1\n  |\n2 | B = 2\n  | ^"
+    );
+}
+
+#[test]
+fn test_get_entry_with_context() {
+    let env = Korni::from_str("A=1\nDB_URL=x")
+        .track_positions()
+        .retain_source()
+        .parse()
+        .unwrap();
+    let (kv, snippet) = env.get_entry_with_context("DB_URL").unwrap();
+    assert_eq!(kv.value, "x");
+    assert_eq!(snippet, "DB_URL is defined here\n --> 2:1\n  |\n2 | DB_URL=x\n  | ^^^^^^");
+    assert!(env.get_entry_with_context("MISSING").is_none());
+}
+
+#[test]
+fn test_get_entry_with_context_needs_source() {
+    let env = Korni::from_str("A=1").track_positions().parse().unwrap();
+    assert!(env.get_entry_with_context("A").is_none());
+}
+
+#[test]
+fn test_find_key_first_and_last() {
+    use korni::{find_key, find_key_with, Occurrence};
+    let input = "A=1\nDATABASE_URL=first\nB=2\nDATABASE_URL=second";
+    assert_eq!(find_key(input, "DATABASE_URL").unwrap().value, "first");
+    assert_eq!(find_key_with(input, "DATABASE_URL", Occurrence::Last).unwrap().value, "second");
+    assert!(find_key(input, "MISSING").is_none());
+}
+
+#[test]
+fn test_find_key_stops_before_later_errors() {
+    let input = "KEY=found\nBROKEN=\"unclosed";
+    let kv = korni::find_key(input, "KEY").unwrap();
+    assert_eq!(kv.value, "found");
+}
+
+#[test]
+fn test_stats() {
+    let input = "# header\nSHORT=1\nexport LONGER_KEY='quoted'\nD=\"a much longer value\"\n# OLD=1\nBAD = 2";
+    let env = Korni::from_str(input).preserve_comments().parse().unwrap();
+    let stats = env.stats();
+    assert_eq!(stats.pairs, 4);
+    assert_eq!(stats.unquoted, 2);
+    assert_eq!(stats.single_quoted, 1);
+    assert_eq!(stats.double_quoted, 1);
+    assert_eq!(stats.exported, 1);
+    assert_eq!(stats.commented, 1);
+    assert_eq!(stats.comments, 1);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.warnings, 0);
+    assert_eq!(stats.longest_key.as_deref(), Some("LONGER_KEY"));
+    assert_eq!(stats.longest_value_key.as_deref(), Some("D"));
+    assert_eq!(stats.longest_value_len, 19);
+    assert_eq!(stats.total_value_bytes, 1 + 6 + 19 + 1);
+}
+
+#[test]
+fn test_stats_empty() {
+    let stats = korni::Environment::new().stats();
+    assert_eq!(stats, korni::Stats::default());
+}
+
+#[test]
+fn test_merge_namespaced_and_strip_prefix() {
+    let mut env = Korni::from_str("APP_NAME=web\nDB_HOST=old\n").parse().unwrap();
+    let db = Korni::from_str("HOST=db.internal\nPORT=5432\n").parse().unwrap();
+    let cache = Korni::from_str("HOST=redis\n").parse().unwrap();
+
+    env.merge_namespaced("DB", db);
+    env.merge_namespaced("CACHE", cache);
+    assert_eq!(env.get("DB_HOST"), Some("db.internal"));
+    assert_eq!(env.get("DB_PORT"), Some("5432"));
+    assert_eq!(env.get("CACHE_HOST"), Some("redis"));
+    assert_eq!(env.get_entry("CACHE_HOST").unwrap().key, "CACHE_HOST");
+    assert_eq!(env.get("HOST"), None);
+    let keys: Vec<_> = env.iter_ordered().map(|kv| kv.key.as_ref()).collect();
+    assert_eq!(keys, ["APP_NAME", "DB_HOST", "DB_PORT", "CACHE_HOST"]);
+
+    let db = env.strip_prefix("DB");
+    assert_eq!(db.to_map().len(), 2);
+    assert_eq!(db.get("HOST"), Some("db.internal"));
+    assert_eq!(db.get_entry("PORT").unwrap().key, "PORT");
+    assert!(env.strip_prefix("APP_N").get("AME").is_none());
+}
+
+#[test]
+fn test_alternatives_from_commented_definitions() {
+    let input = "# DATABASE_URL=postgres://old\n# DATABASE_URL=postgres://staging\n# DATABASE_URL=postgres://old\nDATABASE_URL=postgres://prod\n# DATABASE_URL=postgres://prod\n";
+    let env = korni::Environment::from_entries(korni::parse_with_options(input, korni::ParseOptions::full()));
+    assert_eq!(env.alternatives("DATABASE_URL"), ["postgres://old", "postgres://staging"]);
+    assert_eq!(env.get("DATABASE_URL"), Some("postgres://prod"));
+    assert!(env.alternatives("MISSING").is_empty());
+
+    // Comments are not kept by default, so neither are alternatives
+    let env = Korni::from_str(input).parse().unwrap();
+    assert!(env.alternatives("DATABASE_URL").is_empty());
+
+    let mut merged = korni::Environment::new();
+    merged.merge_namespaced("APP", korni::Environment::from_entries(korni::parse_with_options("# URL=a\nURL=b\n", korni::ParseOptions::full())));
+    assert_eq!(merged.into_owned().alternatives("APP_URL"), ["a"]);
+}
+
+#[test]
+fn test_entry_at_offset() {
+    use korni::{Located, Part};
+    let input = "A=1 # one\nB=2\n";
+    let env = korni::Environment::from_entries(korni::parse_with_options(input, korni::ParseOptions::full()));
+    let at = |offset| match env.entry_at(offset) {
+        Some(Located::Pair { pair, part }) => Some((pair.key.to_string(), part)),
+        _ => None,
+    };
+    assert_eq!(at(0), Some(("A".into(), Part::Key)));
+    assert_eq!(at(12), Some(("B".into(), Part::Value)));
+    // The inline comment needs the source
+    assert_eq!(at(6), None);
+    let env = env.with_source(input);
+    assert_eq!(env.entry_at(6), Some(Located::Pair { pair: env.get_entry("A").unwrap(), part: Part::Comment }));
+
+    let env = Korni::from_str(input).parse().unwrap();
+    assert!(env.entry_at(0).is_none());
+}
+
+#[test]
+fn test_get_suggest_closest_key() {
+    let env = Korni::from_str("DATABASE_URL=postgres://db\nDATABASE_USER=admin\nPORT=80\n").parse().unwrap();
+    assert_eq!(env.get_suggest("PORT"), Ok("80"));
+    assert_eq!(env.get_suggest("DATABSE_URL"), Err(Some("DATABASE_URL")));
+    assert_eq!(env.get_suggest("DATABASE_USR"), Err(Some("DATABASE_USER")));
+    assert_eq!(env.get_suggest("port"), Err(None));
+    assert_eq!(env.get_suggest("PROT"), Err(None));
+    assert_eq!(env.get_suggest("POT"), Err(Some("PORT")));
+    assert_eq!(env.get_suggest("REDIS_HOST"), Err(None));
+}
+
+#[test]
+fn test_get_many() {
+    let env = Korni::from_str("DB_HOST=db\nDB_PORT=5432\n").parse().unwrap();
+    let [host, port, user] = env.get_many(["DB_HOST", "DB_PORT", "DB_USER"]);
+    assert_eq!((host, port, user), (Some("db"), Some("5432"), None));
+    assert_eq!(env.get_many([]), [None::<&str>; 0]);
+
+    let map = env.get_many_map(["DB_PORT", "DB_USER"]);
+    assert_eq!(map.len(), 1);
+    assert_eq!(map["DB_PORT"], "5432");
+}
+
+#[test]
+fn test_clones_share_pairs_until_changed() {
+    let base: String = (0..100).map(|i| format!("KEY_V{}={}\n", i, i)).collect();
+    let env = Korni::from_str(&base).parse().unwrap().into_owned();
+    let mut child = env.clone();
+
+    let extra: String = (0..60).map(|i| format!("V{}=changed\n", i)).collect::<String>() + "X=1\n";
+    child.merge_namespaced("KEY", Korni::from_str(&extra).parse().unwrap().into_owned());
+    assert_eq!(child.get("KEY_V5"), Some("changed"));
+    assert_eq!(child.get("KEY_V99"), Some("99"));
+    assert_eq!(child.get("KEY_X"), Some("1"));
+    assert_eq!(child.stats().pairs, 101);
+    assert_eq!(child.iter().count(), 101);
+
+    assert_eq!(env.get("KEY_V5"), Some("5"));
+    assert_eq!(env.get("KEY_X"), None);
+    assert_eq!(env.to_map().len(), 100);
+}
+
+#[test]
+fn test_clones_share_comments_and_diagnostics() {
+    let input: String = (0..100).map(|i| format!("# note {}\nK{}=v\n", i, i)).collect::<String>() + "1BAD=x\n";
+    let env = Korni::from_str(&input).preserve_comments().warn_duplicate_keys().parse().unwrap().into_owned();
+    let mut child = env.clone();
+    assert_eq!(child.comments().as_ptr(), env.comments().as_ptr());
+    assert_eq!(child.errors().as_ptr(), env.errors().as_ptr());
+
+    child.merge_namespaced("NS", Korni::from_str("2BAD=y\n").parse().unwrap().into_owned());
+    assert_eq!(child.errors().len(), 2);
+    assert_eq!(env.errors().len(), 1);
+    assert_eq!(child.comments().as_ptr(), env.comments().as_ptr());
+}
+
+#[test]
+fn test_overlay_records_only_its_changes() {
+    let base = Korni::from_str("HOST=prod\nPORT=443\nDEBUG=0\n").parse().unwrap().into_owned();
+    let mut child = base.overlay();
+    child.set("HOST", "localhost");
+    child.set("EXTRA", "1");
+    assert_eq!(child.remove("DEBUG").map(|kv| kv.value.into_owned()), Some("0".to_string()));
+    assert!(child.remove("DEBUG").is_none());
+
+    assert_eq!(child.get("HOST"), Some("localhost"));
+    assert_eq!(child.get("PORT"), Some("443"));
+    assert_eq!(child.get("DEBUG"), None);
+    let keys: Vec<_> = child.iter_ordered().map(|kv| kv.key.as_ref()).collect();
+    assert_eq!(keys, ["HOST", "PORT", "EXTRA"]);
+
+    // Grandchildren see the child's changes, the base sees none of them
+    let mut grandchild = child.overlay();
+    grandchild.set("DEBUG", "1");
+    assert_eq!((grandchild.get("HOST"), grandchild.get("DEBUG")), (Some("localhost"), Some("1")));
+    assert_eq!(child.get("DEBUG"), None);
+    assert_eq!(base.to_map().len(), 3);
+    assert_eq!((base.get("HOST"), base.get("DEBUG"), base.get("EXTRA")), (Some("prod"), Some("0"), None));
+}
+
+#[test]
+fn test_overlay_with_many_changes() {
+    let base: String = (0..50).map(|i| format!("K{}={}\n", i, i)).collect();
+    let base = Korni::from_str(&base).parse().unwrap().into_owned();
+    let mut child = base.overlay();
+    for i in 0..50 {
+        if i % 2 == 0 {
+            child.remove(&format!("K{}", i));
+        } else {
+            child.set(format!("K{}", i), "odd");
+        }
+        child.set(format!("NEW{}", i), i.to_string());
+    }
+    assert_eq!(child.to_map().len(), 75);
+    assert_eq!(child.get("K0"), None);
+    assert_eq!(child.get("K1"), Some("odd"));
+    assert_eq!(child.get("NEW49"), Some("49"));
+    assert_eq!(child.clone().into_owned().get("K3"), Some("odd"));
+    assert_eq!(base.get("K0"), Some("0"));
+    assert_eq!(base.to_map().len(), 50);
+}
+
+#[test]
+fn test_freeze_shares_across_threads() {
+    fn assert_shareable<T: Send + Sync + 'static>(_: &T) {}
+
+    let input = String::from("HOST=prod\nPORT=443\n");
+    let frozen = Korni::from_str(&input).parse().unwrap().freeze();
+    drop(input);
+    assert_shareable(&frozen);
+
+    let handles: Vec<_> = (0..4).map(|_| {
+        let env = frozen.clone();
+        std::thread::spawn(move || env.get("PORT").map(str::to_string))
+    }).collect();
+    for handle in handles {
+        assert_eq!(handle.join().unwrap().as_deref(), Some("443"));
+    }
+
+    // Overrides go to a separate environment
+    let mut child = frozen.overlay();
+    child.set("HOST", "localhost");
+    assert_eq!(frozen.get("HOST"), Some("prod"));
+}
+
+#[test]
+fn test_from_pairs() {
+    let env = korni::Environment::from_pairs([("HOST", "localhost"), ("MOTD", "hello world"), ("HOST", "prod")]).unwrap();
+    assert_eq!(env.get("HOST"), Some("prod"));
+    let keys: Vec<_> = env.iter_ordered().map(|kv| kv.key.as_ref()).collect();
+    assert_eq!(keys, ["HOST", "MOTD"]);
+    assert_eq!(env.get_entry("MOTD").unwrap().quote, korni::QuoteType::Single);
+
+    // Written out, it parses back to the same values
+    let written = env.to_string();
+    let reparsed = Korni::from_str(&written).parse().unwrap();
+    assert_eq!(reparsed.to_map(), env.to_map());
+
+    let owned = vec![("KEY".to_string(), "it's \"x\"".to_string())];
+    assert_eq!(korni::Environment::from_pairs(owned).unwrap().get("KEY"), Some("it's \"x\""));
+
+    let err = korni::Environment::from_pairs([("2FA", "x")]).unwrap_err();
+    assert_eq!(err, korni::validate_key("2FA").unwrap_err());
+}
@@ -0,0 +1,47 @@
+use std::fs::OpenOptions;
+use std::io::Write;
+use korni::{Entry, FollowParser, ParseOptions};
+
+fn keys(entries: &[Entry<'_>]) -> Vec<String> {
+    entries.iter().filter_map(|e| e.key()).map(str::to_string).collect()
+}
+
+#[test]
+fn test_follow_appended_entries() {
+    let path = std::env::temp_dir().join(format!("korni-follow-{}.env", std::process::id()));
+    std::fs::write(&path, "A=1\nB=2\n").unwrap();
+    let mut log = OpenOptions::new().append(true).open(&path).unwrap();
+
+    let options = ParseOptions { track_positions: true, ..ParseOptions::default() };
+    let mut follow = FollowParser::open_with_options(&path, options).unwrap();
+    assert_eq!(keys(&follow.poll()), vec!["A", "B"]);
+    assert!(follow.poll().is_empty());
+
+    // Unterminated line: held back until its newline arrives
+    write!(log, "C=3").unwrap();
+    assert!(follow.poll().is_empty());
+    write!(log, "3\nD=\"multi").unwrap();
+    let entries = follow.poll();
+    assert_eq!(keys(&entries), vec!["C"]);
+    assert_eq!(entries[0].as_pair().unwrap().value, "33");
+
+    // Open quote: held back until closed
+    write!(log, "\nline\n").unwrap();
+    assert!(follow.poll().is_empty());
+    write!(log, "end\"\nE=5\n").unwrap();
+    let entries = follow.poll();
+    assert_eq!(keys(&entries), vec!["D", "E"]);
+
+    let d = entries[0].as_pair().unwrap();
+    assert_eq!(d.value, "multi\nline\nend");
+    assert_eq!(d.index, 3);
+    assert_eq!(d.key_span.unwrap().start.line, 3);
+    assert_eq!(d.key_span.unwrap().start.offset, 13);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_follow_missing_file() {
+    assert!(matches!(FollowParser::open("/nonexistent/korni.env"), Err(korni::Error::Io(_))));
+}
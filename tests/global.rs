@@ -0,0 +1,27 @@
+use std::fs;
+
+// The global is initialized once per process, so this binary has one test
+#[test]
+fn test_global_loads_profile_cascade_once() {
+    let dir = std::env::temp_dir().join(format!("korni-global-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join(".env"), "NAME=base\nPORT=80\n").unwrap();
+    fs::write(dir.join(".env.staging"), "NAME=staging\n").unwrap();
+
+    std::env::set_current_dir(&dir).unwrap();
+    std::env::set_var(korni::PROFILE_VAR, "staging");
+
+    let threads: Vec<_> = (0..4).map(|_| std::thread::spawn(|| korni::var("NAME"))).collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), Some("staging"));
+    }
+    assert_eq!(korni::var("PORT"), Some("80"));
+    assert_eq!(korni::var("MISSING"), None);
+
+    fs::write(dir.join(".env"), "PORT=81\n").unwrap();
+    assert_eq!(korni::var("PORT"), Some("80"));
+    assert!(std::ptr::eq(korni::global(), korni::global()));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,57 @@
+use korni::{parse_with_options, EntriesExt, EnvIterator, ParseOptions, Parser};
+
+const INPUT: &str = "A=1\n# B=2\nC = 3\nD=4";
+
+#[test]
+fn test_env_iterator_pairs() {
+    let parser = Parser::with_options(INPUT, ParseOptions::full());
+    let keys: Vec<_> = parser.iter().pairs().map(|kv| kv.key).collect();
+    assert_eq!(keys, vec!["A", "B", "D"]);
+}
+
+#[test]
+fn test_env_iterator_ok_pairs() {
+    let parser = Parser::with_options(INPUT, ParseOptions::full());
+    let keys: Vec<_> = EnvIterator::new(parser).ok_pairs().map(|kv| kv.key).collect();
+    assert_eq!(keys, vec!["A", "D"]);
+}
+
+#[test]
+fn test_env_iterator_errors() {
+    let errors: Vec<_> = Parser::new(INPUT).iter().errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].offset(), 10);
+}
+
+#[test]
+fn test_entries_ext_on_vec() {
+    let entries = parse_with_options(INPUT, ParseOptions::full());
+    assert_eq!(entries.pairs().count(), 3);
+    assert_eq!(entries.ok_pairs().map(|kv| kv.key.as_ref()).collect::<Vec<_>>(), vec!["A", "D"]);
+    assert_eq!(entries.errors().count(), 1);
+}
+
+#[test]
+fn test_parse_entries_count() {
+    assert_eq!(korni::parse_entries_count(""), 0);
+    assert_eq!(korni::parse_entries_count("A=1\n# comment\nB=\"x\ny\"\nbad line\n"), 3);
+}
+
+#[test]
+fn test_parse_into_reuses_storage() {
+    let mut entries = Vec::new();
+    Parser::new("A=1\nB=2\n").parse_into(&mut entries);
+    assert!(entries.capacity() >= 2);
+    Parser::new("C=3").parse_into(&mut entries);
+
+    let keys: Vec<_> = entries.iter().filter_map(korni::Entry::key).collect();
+    assert_eq!(keys, vec!["A", "B", "C"]);
+}
+
+#[test]
+fn test_parse_presizes_from_line_count() {
+    let input = "A=1\n".repeat(100);
+    let entries = korni::parse(&input);
+    assert_eq!(entries.len(), 100);
+    assert!(entries.capacity() >= 100);
+}
@@ -0,0 +1,67 @@
+use korni::{parse, parse_with_options, Entry, LineIndex, ParseOptions, Position};
+
+#[test]
+fn test_position_from_offset() {
+    let index = LineIndex::new("A=1\nBB=2\r\nC=3");
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.position(0), Position { line: 0, col: 0, offset: 0 });
+    assert_eq!(index.position(6), Position { line: 1, col: 2, offset: 6 });
+    assert_eq!(index.position(10), Position { line: 2, col: 0, offset: 10 });
+    assert_eq!(index.position(99), Position { line: 2, col: 3, offset: 13 });
+}
+
+#[test]
+fn test_offset_from_line_col() {
+    let index = LineIndex::new("A=1\nBB=2");
+    assert_eq!(index.offset(1, 2), Some(6));
+    assert_eq!(index.offset(1, 4), Some(8));
+    assert_eq!(index.offset(0, 9), None);
+    assert_eq!(index.offset(5, 0), None);
+}
+
+#[test]
+fn test_line_text_strips_terminators() {
+    let source = "A=1\r\nB=2\n";
+    let index = LineIndex::new(source);
+    assert_eq!(index.line_text(source, 0), Some("A=1"));
+    assert_eq!(index.line_text(source, 1), Some("B=2"));
+    assert_eq!(index.line_text(source, 2), Some(""));
+}
+
+#[test]
+fn test_utf16_col() {
+    let source = "K=🚀x";
+    let index = LineIndex::new(source);
+    // 🚀 is 4 bytes but 2 UTF-16 code units
+    assert_eq!(index.position(6).col, 6);
+    assert_eq!(index.utf16_col(source, 6), 4);
+}
+
+#[test]
+fn test_tracked_positions_have_lines() {
+    let entries = parse_with_options("# c\nA=1\n  B=\"x\"", ParseOptions::full());
+    match &entries[0] {
+        Entry::Comment(c) => assert_eq!(c.span.start.line, 0),
+        _ => panic!("Should be comment"),
+    }
+    let b = entries[2].as_pair().unwrap();
+    let key_span = b.key_span.unwrap();
+    assert_eq!((key_span.start.line, key_span.start.col), (2, 2));
+    let close = b.close_quote_pos.unwrap();
+    assert_eq!((close.line, close.col), (2, 6));
+}
+
+#[test]
+fn test_error_render() {
+    let source = "A=1\nBAD=\"open";
+    let entries = parse(source);
+    let err = match &entries[1] {
+        Entry::Error(e) => e,
+        _ => panic!("Should be error"),
+    };
+    let rendered = err.render(source);
+    assert_eq!(
+        rendered,
+        "Unclosed double quote in value of BAD starting at byte 8\n --> 2:5\n  |\n2 | BAD=\"open\n  |     ^"
+    );
+}
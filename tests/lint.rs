@@ -0,0 +1,166 @@
+use korni::lint::{check_duplicate_values, check_key_names, check_placeholders, check_system_vars, check_url_credentials, fix_key_names, is_placeholder, is_system_var, KeyConvention, PlaceholderKind};
+use korni::{Error, Korni};
+
+#[test]
+fn test_apply_convention() {
+    let screaming = KeyConvention::ScreamingSnakeCase;
+    assert_eq!(screaming.apply("dbHost"), "DB_HOST");
+    assert_eq!(screaming.apply("DbHost"), "DB_HOST");
+    assert_eq!(screaming.apply("db_host"), "DB_HOST");
+    assert_eq!(screaming.apply("DB2HOST"), "DB2HOST");
+    assert_eq!(screaming.apply("__private"), "__PRIVATE");
+    assert!(screaming.matches("DATABASE_URL"));
+    assert!(!screaming.matches("database_url"));
+
+    let snake = KeyConvention::SnakeCase;
+    assert_eq!(snake.apply("DB_HOST"), "db_host");
+    assert_eq!(snake.apply("apiKey"), "api_key");
+    assert!(snake.matches("database_url"));
+}
+
+#[test]
+fn test_check_key_names() {
+    let env = Korni::from_str("GOOD=1\ndbHost=x\n# oldKey=y\nlower=2\n")
+        .preserve_comments()
+        .track_positions()
+        .parse()
+        .unwrap();
+
+    let issues = check_key_names(&env, KeyConvention::ScreamingSnakeCase);
+    let found: Vec<_> = issues.iter().map(|i| (i.key.as_str(), i.suggestion.as_str())).collect();
+    assert_eq!(found, vec![("dbHost", "DB_HOST"), ("lower", "LOWER")]);
+    assert_eq!(issues[0].key_span.unwrap().start.offset, 7);
+
+    let issues = check_key_names(&env, KeyConvention::SnakeCase);
+    let found: Vec<_> = issues.iter().map(|i| i.key.as_str()).collect();
+    assert_eq!(found, vec!["GOOD", "dbHost"]);
+}
+
+#[test]
+fn test_fix_key_names_preserves_layout() {
+    let input = "# settings\nexport dbHost=\"x\" # inline\napiKey='y'\nAPI_URL=z\n";
+    let fixed = fix_key_names(input, KeyConvention::ScreamingSnakeCase).unwrap();
+    assert_eq!(fixed, "# settings\nexport DB_HOST=\"x\" # inline\nAPI_KEY='y'\nAPI_URL=z\n");
+}
+
+#[test]
+fn test_fix_key_names_renames_redefinitions() {
+    let fixed = fix_key_names("port=1\nport=2\n", KeyConvention::ScreamingSnakeCase).unwrap();
+    assert_eq!(fixed, "PORT=1\nPORT=2\n");
+}
+
+#[test]
+fn test_fix_key_names_detects_collisions() {
+    let err = fix_key_names("DB_HOST=a\ndbHost=b\n", KeyConvention::ScreamingSnakeCase).unwrap_err();
+    match err {
+        Error::Generic { offset, message } => {
+            assert_eq!(offset, 10);
+            assert!(message.contains("collides with 'DB_HOST'"), "{}", message);
+        },
+        other => panic!("unexpected error {:?}", other),
+    }
+}
+
+#[test]
+fn test_check_duplicate_values() {
+    let input = "\
+DATABASE_URL=postgres://db/app
+DEBUG=true
+READ_URL=postgres://db/app
+# OLD_URL=postgres://db/app
+VERBOSE=true
+EMPTY=
+ALSO_EMPTY=
+REPLICA_URL='postgres://db/app'
+";
+    let env = Korni::from_str(input).preserve_comments().track_positions().parse().unwrap();
+
+    let groups = check_duplicate_values(&env, 5);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].keys, ["DATABASE_URL", "READ_URL", "REPLICA_URL"]);
+    let span = groups[0].value_spans[1].unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "postgres://db/app");
+
+    let groups = check_duplicate_values(&env, 0);
+    let keys: Vec<_> = groups.iter().map(|g| g.keys.clone()).collect();
+    assert_eq!(keys, [vec!["DATABASE_URL", "READ_URL", "REPLICA_URL"], vec!["DEBUG", "VERBOSE"]]);
+}
+
+#[test]
+fn test_is_placeholder() {
+    for value in ["changeme", "CHANGE_ME", "change-me", "<your-key-here>", "<api key>", "xxx", "XXXXXXXX", "TODO", "your_api_key_here", "YOUR-TOKEN-HERE", "..."] {
+        assert!(is_placeholder(value), "{:?}", value);
+    }
+    for value in ["localhost", "x", "xy", "<", "<>", "todos", "box", "yourhost.example.com", "sk_live_abc123"] {
+        assert!(!is_placeholder(value), "{:?}", value);
+    }
+}
+
+#[test]
+fn test_check_placeholders() {
+    let input = "API_KEY=<your-key-here>\nHOST=localhost\nSECRET=changeme\nDB_URL=\nOPTIONAL=\n# OLD=xxx\n";
+    let env = Korni::from_str(input).preserve_comments().track_positions().parse().unwrap();
+
+    let issues = check_placeholders(&env, &["DB_URL", "MISSING"]);
+    let found: Vec<_> = issues.iter().map(|i| (i.key.as_str(), i.kind)).collect();
+    assert_eq!(found, [
+        ("API_KEY", PlaceholderKind::Template),
+        ("SECRET", PlaceholderKind::Template),
+        ("DB_URL", PlaceholderKind::Empty),
+    ]);
+    let span = issues[0].value_span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "<your-key-here>");
+}
+
+#[test]
+fn test_check_url_credentials() {
+    let input = "\
+DATABASE_URL=postgres://app:s3cret@db:5432/app
+PUBLIC_URL=https://example.com/a@b
+USER_ONLY=https://token@github.com
+EMPTY_PASS=redis://user:@cache
+ESCAPED=\"amqp://guest:pa\\$s@mq\"
+";
+    let env = Korni::from_str(input).track_positions().parse().unwrap();
+
+    let issues = check_url_credentials(&env, true);
+    let found: Vec<_> = issues.iter().map(|i| (i.key.as_str(), i.url.as_str())).collect();
+    assert_eq!(found, [
+        ("DATABASE_URL", "postgres://app:[REDACTED]@db:5432/app"),
+        ("ESCAPED", "amqp://guest:[REDACTED]@mq"),
+    ]);
+    assert_eq!(issues[0].suggested_keys, ("DATABASE_USER".to_string(), "DATABASE_PASSWORD".to_string()));
+    let span = issues[0].password_span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "s3cret");
+    assert_eq!((span.start.line, span.start.col), (0, 28));
+    // Escapes shift the value against the source, which maps them when attached
+    assert_eq!(issues[1].password_span, None);
+    let env = env.with_source(input);
+    let span = check_url_credentials(&env, true)[1].password_span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "pa\\$s");
+    assert_eq!((span.start.line, span.start.col), (4, 22));
+    assert_eq!(issues[1].suggested_keys.1, "ESCAPED_PASSWORD");
+
+    let issues = check_url_credentials(&env, false);
+    assert_eq!(issues[0].url, "postgres://app:s3cret@db:5432/app");
+}
+
+#[test]
+fn test_check_system_vars() {
+    let input = "APP_NAME=demo\nPATH=/opt/bin\nHOME=/srv\n# USER=root\nLD_PRELOAD=./hook.so\nPYTHONPATH=src\nPath=x\nTZ=UTC\n";
+    let env = Korni::from_str(input).preserve_comments().track_positions().parse().unwrap();
+
+    let issues = check_system_vars(&env, &[]);
+    let found: Vec<_> = issues.iter().map(|i| (i.key.as_str(), i.execution)).collect();
+    assert_eq!(found, [("PATH", true), ("HOME", false), ("LD_PRELOAD", true), ("PYTHONPATH", true), ("TZ", false)]);
+    let span = issues[2].key_span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "LD_PRELOAD");
+    assert_eq!(span.start.line, 4);
+
+    let issues = check_system_vars(&env, &["TZ", "PYTHON*", "HOME"]);
+    let found: Vec<_> = issues.iter().map(|i| i.key.as_str()).collect();
+    assert_eq!(found, ["PATH", "LD_PRELOAD"]);
+
+    assert!(is_system_var("LD_LIBRARY_PATH"));
+    assert!(!is_system_var("path"));
+}
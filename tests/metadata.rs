@@ -0,0 +1,33 @@
+use korni::{Document, Korni, Metadata};
+
+#[test]
+fn test_metadata_from_leading_block() {
+    let input = "\
+# Production settings
+# korni: version=2, profile=prod
+# korni: schema = billing
+API_URL=https://api.example.com
+# korni: profile=ignored
+";
+    let env = Korni::from_str(input).parse().unwrap();
+    let metadata = env.metadata().unwrap();
+    assert_eq!(metadata.version, Some(2));
+    assert_eq!(metadata.profile.as_deref(), Some("prod"));
+    assert_eq!(metadata.extra.get("schema").map(String::as_str), Some("billing"));
+    assert_eq!(env.get("API_URL"), Some("https://api.example.com"));
+
+    assert_eq!(Document::parse(input).to_env().metadata(), Some(metadata));
+    assert!(Korni::from_str("A=1\n# korni: version=1\n").parse().unwrap().metadata().is_none());
+}
+
+#[test]
+fn test_malformed_metadata_is_a_warning() {
+    assert!(Metadata::from_source("# korni: version=two\nA=1").is_err());
+    assert!(Metadata::from_source("# korni: prod").is_err());
+    assert_eq!(Metadata::from_source("\u{feff}\n# korni: version=1"), Ok(Some(Metadata { version: Some(1), ..Metadata::default() })));
+
+    let env = Korni::from_str("# korni: version=x\nA=1").parse().unwrap();
+    assert!(env.metadata().is_none());
+    assert_eq!(env.warnings()[0].to_string(), "Metadata version 'x' is not a number at byte 0");
+    assert_eq!(env.get("A"), Some("1"));
+}
@@ -0,0 +1,29 @@
+use std::sync::{Arc, Mutex};
+use korni::{Korni, ParseMetrics, ParseOptions, Parser};
+
+#[test]
+fn test_parse_observed_reports_counts() {
+    let input = "A=1\nB=\"x\\ny\"\n# note\nBAD ROW\nA=2\n";
+    let seen = Mutex::new(Vec::new());
+    let options = ParseOptions { include_comments: true, warn_duplicate_keys: true, ..ParseOptions::default() };
+    let entries = Parser::with_options(input, options).parse_observed(&|m: &ParseMetrics| seen.lock().unwrap().push(*m));
+
+    let seen = seen.into_inner().unwrap();
+    assert_eq!(seen.len(), 1);
+    let m = seen[0];
+    assert_eq!(m.bytes, input.len());
+    assert_eq!(m.entries, entries.len());
+    assert_eq!((m.pairs, m.errors, m.warnings, m.owned_strings), (3, 1, 1, 1));
+}
+
+#[test]
+fn test_builder_observer_is_called_per_parse() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    let observer = Arc::new(move |m: &ParseMetrics| sink.lock().unwrap().push(m.pairs));
+
+    Korni::from_str("A=1\nB=2").observer(observer.clone()).parse().unwrap();
+    Korni::from_reader("C=3".as_bytes()).observer(observer).parse().unwrap();
+    Korni::from_str("D=4").parse().unwrap();
+    assert_eq!(*seen.lock().unwrap(), [2, 1]);
+}
@@ -0,0 +1,31 @@
+use korni::{preview, PreviewEntry, QuoteType};
+
+#[test]
+fn test_preview_lists_pairs_cheaply() {
+    let input = "\
+# settings
+export HOST=localhost
+API_TOKEN='abcdefghijklmnop'
+NOTE=\"line one\\n\\\"quoted\\\"
+still note\"
+BAD KEY=1
+EMPTY=
+UNI=ééé
+";
+    let entries = preview(input, 8);
+    let summary: Vec<_> = entries.iter().map(|e| (e.key, e.value, e.truncated)).collect();
+    assert_eq!(summary, [
+        ("HOST", "localhos", true),
+        ("API_TOKEN", "abcdefgh", true),
+        ("NOTE", "line one", true),
+        ("EMPTY", "", false),
+        ("UNI", "ééé", false),
+    ]);
+    assert_eq!(preview("UNI=ééé", 5)[0].value, "éé");
+
+    assert_eq!(entries[1], PreviewEntry { key: "API_TOKEN", value: "abcdefgh", len: 16, truncated: true, quote: QuoteType::Single, secret: true });
+    assert_eq!(entries[2].quote, QuoteType::Double);
+    assert_eq!(entries[2].len, "line one\\n\\\"quoted\\\"\nstill note".len());
+    assert!(!entries[0].secret);
+    assert!(preview("", 10).is_empty());
+}
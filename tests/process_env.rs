@@ -0,0 +1,180 @@
+use korni::{Environment, Error, Korni, Platform};
+use std::ffi::OsStr;
+
+#[test]
+fn test_apply_os_sets_process_vars() {
+    let env = Korni::from_str("KORNI_TEST_APPLY_OS=hello").parse().unwrap();
+    env.apply_os();
+    assert_eq!(std::env::var("KORNI_TEST_APPLY_OS").unwrap(), "hello");
+}
+
+#[test]
+fn test_apply_os_skips_commented_pairs_and_respects_policy() {
+    std::env::set_var("KORNI_TEST_APPLY_OS_SET", "process");
+    std::env::remove_var("KORNI_TEST_APPLY_OS_OFF");
+    let input = "KORNI_TEST_APPLY_OS_SET=file\n# KORNI_TEST_APPLY_OS_OFF=file\n";
+
+    let env = Korni::from_str(input).preserve_comments().parse().unwrap();
+    env.apply_os();
+    assert!(std::env::var_os("KORNI_TEST_APPLY_OS_OFF").is_none());
+    assert_eq!(std::env::var("KORNI_TEST_APPLY_OS_SET").unwrap(), "process");
+
+    let file_wins = Korni::from_str(input).preserve_comments().override_policy(korni::OverridePolicy::FileWins).parse().unwrap();
+    file_wins.apply_os();
+    assert_eq!(std::env::var("KORNI_TEST_APPLY_OS_SET").unwrap(), "file");
+    assert!(std::env::var_os("KORNI_TEST_APPLY_OS_OFF").is_none());
+}
+
+#[test]
+fn test_from_process_env_os_captures_utf8() {
+    std::env::set_var("KORNI_TEST_CAPTURE_OS", "captured");
+    let env = Environment::from_process_env_os();
+    assert_eq!(env.get("KORNI_TEST_CAPTURE_OS"), Some("captured"));
+    assert_eq!(env.get_os("KORNI_TEST_CAPTURE_OS"), Some(OsStr::new("captured")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_value_round_trips() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let raw = OsStr::from_bytes(b"caf\xE9");
+    std::env::set_var("KORNI_TEST_NON_UTF8", raw);
+
+    let env = Environment::from_process_env_os();
+    assert_eq!(env.get("KORNI_TEST_NON_UTF8"), None);
+    assert_eq!(env.get_os("KORNI_TEST_NON_UTF8"), Some(raw));
+
+    std::env::remove_var("KORNI_TEST_NON_UTF8");
+    env.apply_os();
+    assert_eq!(std::env::var_os("KORNI_TEST_NON_UTF8").as_deref(), Some(raw));
+}
+
+#[test]
+fn test_apply_override_policies() {
+    use korni::OverridePolicy;
+
+    std::env::set_var("KORNI_TEST_POLICY_SET", "process");
+    std::env::remove_var("KORNI_TEST_POLICY_NEW");
+    let input = "KORNI_TEST_POLICY_SET=file\nKORNI_TEST_POLICY_NEW=file\n# KORNI_TEST_POLICY_OFF=file\n";
+
+    let env = Korni::from_str(input).preserve_comments().parse().unwrap();
+    assert_eq!(env.override_policy(), OverridePolicy::ProcessEnvWins);
+    assert_eq!(env.apply(), ["KORNI_TEST_POLICY_NEW"]);
+    assert_eq!(std::env::var("KORNI_TEST_POLICY_SET").unwrap(), "process");
+    assert_eq!(std::env::var("KORNI_TEST_POLICY_NEW").unwrap(), "file");
+    assert!(std::env::var_os("KORNI_TEST_POLICY_OFF").is_none());
+
+    std::env::set_var("KORNI_TEST_POLICY_NEW", "process");
+    let ignore = Korni::from_str(input).override_policy(OverridePolicy::Ignore).parse().unwrap();
+    assert!(ignore.apply().is_empty());
+    assert_eq!(std::env::var("KORNI_TEST_POLICY_NEW").unwrap(), "process");
+
+    let file_wins = Korni::from_str(input).override_policy(OverridePolicy::FileWins).parse().unwrap();
+    assert_eq!(file_wins.apply(), ["KORNI_TEST_POLICY_SET", "KORNI_TEST_POLICY_NEW"]);
+    assert_eq!(std::env::var("KORNI_TEST_POLICY_SET").unwrap(), "file");
+}
+
+#[test]
+fn test_snapshot_filters_and_redacts() {
+    std::env::set_var("KORNI_SNAP_HOST", "db.internal");
+    std::env::set_var("KORNI_SNAP_PASSWORD", "hunter2");
+    std::env::set_var("KORNI_SNAP_MOTD", "hello world");
+    std::env::set_var("KORNI_OTHER_VAR", "x");
+
+    let doc = korni::snapshot(["KORNI_SNAP_*"]);
+    assert_eq!(
+        doc.to_string(),
+        "KORNI_SNAP_HOST=db.internal\nKORNI_SNAP_MOTD='hello world'\nKORNI_SNAP_PASSWORD=[REDACTED]\n",
+    );
+
+    let path = std::env::temp_dir().join(format!("korni-snapshot-{}.env", std::process::id()));
+    korni::snapshot_to_file(&path, ["KORNI_SNAP_HOST", "KORNI_OTHER_*"]).unwrap();
+    let env = Korni::from_file(&path).parse().unwrap();
+    assert_eq!(env.get("KORNI_SNAP_HOST"), Some("db.internal"));
+    assert_eq!(env.get("KORNI_OTHER_VAR"), Some("x"));
+    assert_eq!(env.get("KORNI_SNAP_MOTD"), None);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_to_os_map() {
+    let env = Korni::from_str("A=1\nB='two words'").parse().unwrap();
+    let map = env.to_os_map();
+    assert_eq!(map.len(), 2);
+    assert_eq!(map.get(OsStr::new("B")).map(|v| v.as_os_str()), Some(OsStr::new("two words")));
+
+    let output = std::process::Command::new("sh")
+        .args(["-c", "printf %s \"$B\""])
+        .env_clear()
+        .envs(map)
+        .output();
+    if let Ok(output) = output {
+        assert_eq!(output.stdout, b"two words");
+    }
+}
+
+#[test]
+fn test_to_os_map_skips_commented_pairs() {
+    let env = Korni::from_str("A=1\n# B=2\n").preserve_comments().parse().unwrap();
+    let map = env.to_os_map();
+    assert_eq!(map.len(), 1);
+    assert!(!map.contains_key(OsStr::new("B")));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_to_os_map_keeps_non_utf8() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let raw = OsStr::from_bytes(b"\xFFraw");
+    std::env::set_var("KORNI_TEST_OS_MAP_RAW", raw);
+    let map = Environment::from_process_env_os().to_os_map();
+    assert_eq!(map.get(OsStr::new("KORNI_TEST_OS_MAP_RAW")).map(|v| v.as_os_str()), Some(raw));
+}
+
+#[test]
+fn test_check_platform_limits() {
+    let env = Korni::from_str("SMALL=1").parse().unwrap();
+    for platform in [Platform::Windows, Platform::Linux, Platform::MacOs] {
+        assert!(env.check_platform_limits(platform).is_empty());
+    }
+
+    let big = "x".repeat(40_000);
+    let input = format!("SMALL=1\nBIG={}", big);
+    let env = Korni::from_str(&input).parse().unwrap();
+    let errors = env.check_platform_limits(Platform::Windows);
+    assert_eq!(errors, vec![Error::PlatformLimit { key: Some("BIG".into()), size: 40_004, limit: 32_767, platform: Platform::Windows }]);
+    assert_eq!(errors[0].to_string(), "Variable BIG is 40004 characters, over the Windows limit of 32767");
+    assert!(env.check_platform_limits(Platform::Linux).is_empty());
+
+    // Commented-out pairs are never applied
+    let input = format!("SMALL=1\n# BIG={}", big);
+    let env = Korni::from_str(&input).preserve_comments().parse().unwrap();
+    assert!(env.check_platform_limits(Platform::Windows).is_empty());
+}
+
+#[test]
+fn test_check_platform_limits_block() {
+    let chunk = "x".repeat(100_000);
+    let input: String = (0..12).map(|i| format!("K{}={}\n", i, chunk)).collect();
+    let env = Korni::from_str(&input).parse().unwrap();
+
+    let errors = env.check_platform_limits(Platform::MacOs);
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(errors[0], Error::PlatformLimit { key: None, limit: 1_048_576, .. }));
+    assert!(env.check_platform_limits(Platform::Linux).is_empty());
+    // Windows counts each variable, not the block
+    assert_eq!(env.check_platform_limits(Platform::Windows).len(), 12);
+}
+
+#[test]
+fn test_check_platform_limits_linux_counts_bytes() {
+    // Linux counts bytes: 65,536 two-byte characters are over 128 KiB
+    let input = format!("WIDE={}", "é".repeat(65_536));
+    let env = Korni::from_str(&input).parse().unwrap();
+    let errors = env.check_platform_limits(Platform::Linux);
+    assert_eq!(errors, vec![Error::PlatformLimit { key: Some("WIDE".into()), size: 131_078, limit: 131_072, platform: Platform::Linux }]);
+    assert_eq!(errors[0].kind(), "PlatformLimit");
+    assert!(!errors[0].to_string().contains('é'));
+}
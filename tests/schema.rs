@@ -0,0 +1,262 @@
+use korni::schema::{InferredType, KeySpec, Rule, Schema, ValueType, ViolationKind};
+use korni::Korni;
+
+fn env(input: &str) -> korni::Environment<'_> {
+    Korni::from_str(input).preserve_comments().track_positions().parse().unwrap()
+}
+
+#[test]
+fn test_value_types() {
+    assert!(ValueType::Integer.matches("-42"));
+    assert!(!ValueType::Integer.matches("4.2"));
+    assert!(ValueType::Float.matches("4.2"));
+    assert!(ValueType::Bool.matches("YES"));
+    assert!(!ValueType::Bool.matches("maybe"));
+    assert!(ValueType::Url.matches("postgres+tls://db/app"));
+    assert!(!ValueType::Url.matches("://db"));
+    assert!(!ValueType::Url.matches("db:5432"));
+    assert!(ValueType::String.matches(""));
+}
+
+#[test]
+fn test_required_and_types() {
+    let schema = Schema::new()
+        .key("DB_HOST", KeySpec::new(ValueType::String).required())
+        .key("DB_PORT", KeySpec::new(ValueType::Integer).required())
+        .key("DEBUG", KeySpec::new(ValueType::Bool));
+    let env = env("# DB_HOST=old\nDB_PORT=abc\n");
+
+    let violations = schema.validate(&env);
+    let found: Vec<_> = violations.iter().map(|v| (v.key.as_str(), v.kind.clone())).collect();
+    assert_eq!(found, [
+        ("DB_HOST", ViolationKind::Missing),
+        ("DB_PORT", ViolationKind::InvalidType(ValueType::Integer)),
+    ]);
+    assert_eq!(violations[0].key_span, None);
+    assert_eq!(violations[1].key_span.unwrap().start.offset, 14);
+    assert_eq!(violations[1].to_string(), "DB_PORT is not a valid integer (byte 14)");
+}
+
+#[test]
+fn test_range_rule() {
+    let schema = Schema::new().range("DB_PORT", 1, 65535).range("UNSET", 0, 1);
+    assert!(schema.validate(&env("DB_PORT=5432")).is_empty());
+
+    let violations = schema.validate(&env("DB_PORT=70000"));
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].kind, ViolationKind::OutOfRange { min: 1, max: 65535 });
+    assert_eq!(violations[0].to_string(), "DB_PORT must be an integer from 1 to 65535 (byte 0)");
+    assert_eq!(schema.validate(&env("DB_PORT=http")).len(), 1);
+}
+
+#[test]
+fn test_all_or_none_rule() {
+    let schema = Schema::new().all_or_none(["TLS_CERT", "TLS_KEY", "TLS_CA"]);
+    assert!(schema.validate(&env("OTHER=1")).is_empty());
+    assert!(schema.validate(&env("TLS_CERT=a\nTLS_KEY=b\nTLS_CA=c")).is_empty());
+
+    let violations = schema.validate(&env("TLS_CERT=a.pem\n# TLS_KEY=b.pem\n"));
+    let keys: Vec<_> = violations.iter().map(|v| v.key.as_str()).collect();
+    assert_eq!(keys, ["TLS_KEY", "TLS_CA"]);
+    assert_eq!(violations[0].kind, ViolationKind::Incomplete);
+    assert_eq!(violations[0].related[0].0, "TLS_CERT");
+    assert_eq!(violations[0].related[0].1.unwrap().start.offset, 0);
+    assert_eq!(violations[0].to_string(), "TLS_KEY must be set together with TLS_CERT");
+
+    assert_eq!(schema.rules(), [Rule::AllOrNone(vec!["TLS_CERT".into(), "TLS_KEY".into(), "TLS_CA".into()])]);
+}
+
+#[test]
+fn test_redeclaring_a_key_replaces_it() {
+    let schema = Schema::new()
+        .key("PORT", KeySpec::new(ValueType::String))
+        .key("HOST", KeySpec::default())
+        .key("PORT", KeySpec::new(ValueType::Integer).required());
+    let keys: Vec<_> = schema.keys().map(|(key, _)| key).collect();
+    assert_eq!(keys, ["PORT", "HOST"]);
+    assert_eq!(schema.get("PORT").unwrap().value_type, ValueType::Integer);
+}
+
+#[test]
+fn test_requires_if_rule() {
+    let schema = Schema::new()
+        .key("STORAGE", KeySpec::new(ValueType::String))
+        .requires_if("S3_BUCKET", "STORAGE", "s3");
+    assert!(schema.validate(&env("STORAGE=disk")).is_empty());
+    assert!(schema.validate(&env("STORAGE=s3\nS3_BUCKET=assets")).is_empty());
+    assert!(schema.validate(&env("# STORAGE=s3")).is_empty());
+
+    let violations = schema.validate(&env("APP=x\nSTORAGE=s3\n# S3_BUCKET=old\n"));
+    assert_eq!(violations.len(), 1);
+    let violation = &violations[0];
+    assert_eq!(violation.key, "S3_BUCKET");
+    assert_eq!(violation.kind, ViolationKind::RequiredIf { condition: "STORAGE".into(), equals: "s3".into() });
+    assert_eq!(violation.key_span, None);
+    assert_eq!(violation.related[0].0, "STORAGE");
+    assert_eq!(violation.related[0].1.unwrap().start.offset, 6);
+    assert_eq!(violation.to_string(), "S3_BUCKET is required when STORAGE=s3");
+}
+
+#[test]
+fn test_with_defaults() {
+    let schema = Schema::new()
+        .key("HOST", KeySpec::new(ValueType::String).with_default("localhost"))
+        .key("PORT", KeySpec::new(ValueType::Integer).with_default("8080"))
+        .key("MOTD", KeySpec::default().with_default("hello world"))
+        .key("NAME", KeySpec::default().required());
+
+    let env = env("PORT=443\n# HOST=old\n").with_defaults(&schema);
+    assert_eq!(env.get("PORT"), Some("443"));
+    assert_eq!(env.get("HOST"), Some("localhost"));
+    assert_eq!(env.get("NAME"), None);
+
+    let host = env.get_entry("HOST").unwrap();
+    assert!(!host.is_comment);
+    assert_eq!(host.annotation("provenance"), Some("default"));
+    assert_eq!(env.get_entry("PORT").unwrap().annotation("provenance"), None);
+
+    let keys: Vec<_> = env.iter_ordered().map(|kv| kv.key.as_ref()).collect();
+    assert_eq!(keys, ["PORT", "HOST", "MOTD"]);
+    assert!(env.to_string().contains("MOTD='hello world'"));
+    assert_eq!(schema.validate(&env).len(), 1);
+}
+
+#[test]
+fn test_to_json_schema() {
+    let schema = Schema::new()
+        .key("DB_HOST", KeySpec::new(ValueType::String).required().describe("Database \"host\""))
+        .key("DB_PORT", KeySpec::new(ValueType::Integer).with_default("5432"))
+        .key("DEBUG", KeySpec::new(ValueType::Bool).with_default("yes"))
+        .key("RATE", KeySpec::new(ValueType::Float).with_default("NaN"))
+        .key("API_URL", KeySpec::new(ValueType::Url))
+        .range("WORKERS", 1, 64)
+        .all_or_none(["TLS_CERT", "TLS_KEY"])
+        .requires_if("S3_BUCKET", "STORAGE", "s3");
+
+    let json: serde_json::Value = serde_json::from_str(&schema.to_json_schema()).unwrap();
+    assert_eq!(json["$schema"], "https://json-schema.org/draft/2020-12/schema");
+    assert_eq!(json["type"], "object");
+    assert_eq!(json["required"], serde_json::json!(["DB_HOST"]));
+    assert_eq!(json["additionalProperties"], serde_json::json!({"type": "string"}));
+
+    let properties = &json["properties"];
+    assert_eq!(properties["DB_HOST"], serde_json::json!({"type": "string", "description": "Database \"host\""}));
+    let integer = ValueType::Integer.pattern().unwrap();
+    assert_eq!(properties["DB_PORT"], serde_json::json!({"type": "string", "pattern": integer, "default": "5432"}));
+    assert_eq!(properties["DEBUG"], serde_json::json!({"type": "string", "pattern": ValueType::Bool.pattern(), "default": "yes"}));
+    assert_eq!(properties["RATE"], serde_json::json!({"type": "string", "pattern": ValueType::Float.pattern(), "default": "NaN"}));
+    assert_eq!(properties["API_URL"], serde_json::json!({"type": "string", "pattern": ValueType::Url.pattern()}));
+    assert_eq!(properties["WORKERS"], serde_json::json!({"type": "string", "pattern": integer, "$comment": "an integer from 1 to 64"}));
+    assert_eq!(properties["TLS_KEY"], serde_json::json!({"type": "string"}));
+
+    assert_eq!(json["dependentRequired"], serde_json::json!({"TLS_CERT": ["TLS_KEY"], "TLS_KEY": ["TLS_CERT"]}));
+    assert_eq!(json["allOf"], serde_json::json!([{
+        "if": {"properties": {"STORAGE": {"const": "s3"}}, "required": ["STORAGE"]},
+        "then": {"required": ["S3_BUCKET"]},
+    }]));
+
+    assert_eq!(ValueType::String.pattern(), None);
+
+    let empty: serde_json::Value = serde_json::from_str(&Schema::new().to_json_schema()).unwrap();
+    assert_eq!(empty["properties"], serde_json::json!({}));
+    assert!(empty.get("required").is_none());
+}
+
+#[test]
+fn test_value_type_patterns_agree_with_matches() {
+    let values = [
+        "0", "1", "-7", "+42", "007", "1.5", "1.", ".5", "-2e10", "1E+3", "e5", "1e", "..", "inf", "-Infinity", "NaN", "nan",
+        "infinit", "true", "FALSE", "Yes", "no", "ON", "off", "y", "2", "http://x", "postgres+ssl://db/app", "1http://x",
+        "http://", "://x", "ht tp://x", "mailto:a@b", "", " 1", "1 ", "hello",
+    ];
+    for value_type in [ValueType::Integer, ValueType::Float, ValueType::Bool, ValueType::Url] {
+        let pattern = regex::Regex::new(value_type.pattern().unwrap()).unwrap();
+        for value in values {
+            assert_eq!(pattern.is_match(value), value_type.matches(value), "{:?} as {}", value, value_type.name());
+        }
+    }
+    assert_eq!(ValueType::String.pattern(), None);
+}
+
+#[test]
+fn test_inferred_type() {
+    let cases: &[(&str, InferredType)] = &[
+        ("8080", InferredType::Integer),
+        ("-3", InferredType::Integer),
+        ("0.25", InferredType::Float),
+        ("1e-3", InferredType::Float),
+        ("true", InferredType::Bool),
+        ("off", InferredType::Bool),
+        ("postgres://db:5432/app", InferredType::Url),
+        ("/var/log/app", InferredType::Path),
+        ("./data", InferredType::Path),
+        ("C:\\temp", InferredType::Path),
+        ("{\"a\": [1, 2.5, null], \"b\": \"\\u00e9\"}", InferredType::Json),
+        ("[]", InferredType::Json),
+        ("c2VjcmV0LXZhbHVlLTEyMw==", InferredType::Base64),
+        ("30s", InferredType::Duration),
+        ("500ms", InferredType::Duration),
+        ("1h30m", InferredType::Duration),
+        ("hello world", InferredType::String),
+        ("{not json}", InferredType::String),
+        ("1.2.3", InferredType::String),
+        ("inf", InferredType::String),
+        ("ABCDEFGHIJKLMNOP", InferredType::String),
+    ];
+    for &(value, expected) in cases {
+        assert_eq!(InferredType::infer(value).0, expected, "{:?}", value);
+    }
+    assert_eq!(InferredType::infer("1"), (InferredType::Integer, 0.6));
+    assert_eq!(InferredType::infer("0755"), (InferredType::Integer, 0.5));
+    assert_eq!(InferredType::infer(""), (InferredType::String, 0.0));
+    assert_eq!(InferredType::Duration.value_type(), ValueType::String);
+    assert_eq!(InferredType::Url.value_type(), ValueType::Url);
+}
+
+#[test]
+fn test_infer_types() {
+    let input = "PORT=8080\n# OLD=1\nDEBUG=yes\nTIMEOUT=30s\nNAME=app\n";
+    let env = env(input);
+    let guesses = env.infer_types();
+    let found: Vec<_> = guesses.iter().map(|g| (g.key.as_str(), g.inferred, g.confidence)).collect();
+    assert_eq!(found, [
+        ("PORT", InferredType::Integer, 1.0),
+        ("DEBUG", InferredType::Bool, 0.9),
+        ("TIMEOUT", InferredType::Duration, 0.9),
+        ("NAME", InferredType::String, 1.0),
+    ]);
+    let span = guesses[2].value_span.unwrap();
+    assert_eq!(&input[span.start.offset..span.end.offset], "30s");
+}
+
+#[test]
+fn test_infer_from() {
+    let env = env("PORT=8080\nDEBUG=true\nDB_URL=postgres://db/app\nTIMEOUT=30s\n# OLD=1\nEMPTY=\n");
+    let schema = Schema::infer_from(&env);
+    let keys: Vec<_> = schema.keys().map(|(key, spec)| (key, spec.value_type, spec.required)).collect();
+    assert_eq!(keys, [
+        ("PORT", ValueType::Integer, true),
+        ("DEBUG", ValueType::Bool, true),
+        ("DB_URL", ValueType::Url, true),
+        ("TIMEOUT", ValueType::String, true),
+        ("EMPTY", ValueType::String, true),
+    ]);
+    assert!(schema.validate(&env).is_empty());
+}
+
+#[test]
+fn test_infer_from_samples() {
+    let dev = Korni::from_str("PORT=8080\nRATIO=1\nVERBOSE=1\nDEBUG_TOOLBAR=1\nNAME=dev\n").parse().unwrap();
+    let prod = Korni::from_str("PORT=80\nRATIO=0.5\nVERBOSE=false\nNAME=42\nSENTRY_DSN=https://sentry.example/1\n").parse().unwrap();
+    let schema = Schema::infer_from_samples(&[dev, prod]);
+    let keys: Vec<_> = schema.keys().map(|(key, spec)| (key, spec.value_type, spec.required)).collect();
+    assert_eq!(keys, [
+        ("PORT", ValueType::Integer, true),
+        ("RATIO", ValueType::Float, true),
+        ("VERBOSE", ValueType::Bool, true),
+        ("DEBUG_TOOLBAR", ValueType::Integer, false),
+        ("NAME", ValueType::String, true),
+        ("SENTRY_DSN", ValueType::Url, false),
+    ]);
+    assert!(Schema::infer_from_samples(&[]).keys().next().is_none());
+}
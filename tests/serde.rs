@@ -0,0 +1,40 @@
+#![cfg(feature = "serde")]
+
+use korni::{parse_with_options, ParseOptions};
+use serde_json::json;
+
+#[test]
+fn test_serialize_pair_entry() {
+    let entries = parse_with_options("export KEY='v'", ParseOptions::fast());
+    let value = serde_json::to_value(&entries[0]).unwrap();
+    assert_eq!(value["Pair"]["key"], "KEY");
+    assert_eq!(value["Pair"]["value"], "v");
+    assert_eq!(value["Pair"]["quote"], "Single");
+    assert_eq!(value["Pair"]["is_exported"], true);
+    assert_eq!(value["Pair"]["key_span"], json!(null));
+}
+
+#[test]
+fn test_serialize_spans() {
+    let entries = parse_with_options("# hi\nK=v", ParseOptions::full());
+    let value = serde_json::to_value(&entries).unwrap();
+    assert_eq!(value[0]["Comment"]["content_span"]["start"], json!({ "line": 0, "col": 2, "offset": 2 }));
+    assert_eq!(value[1]["Pair"]["key_span"]["end"]["offset"], 6);
+}
+
+#[test]
+fn test_serialize_error() {
+    let entries = parse_with_options("K=\"open", ParseOptions::fast());
+    let value = serde_json::to_value(&entries[0]).unwrap();
+    assert_eq!(value, json!({ "Error": { "UnclosedQuote": { "key": "K", "quote_type": "double", "offset": 2 } } }));
+}
+
+#[test]
+fn test_serialize_diff_matches_to_json() {
+    let old = korni::Korni::from_str("A=1\nB=2\n").parse().unwrap();
+    let new = korni::Korni::from_str("B=3\nC=\"x\\ny\"\n").parse().unwrap();
+    let diff = old.diff(&new);
+    let value = serde_json::to_value(&diff).unwrap();
+    assert_eq!(value, serde_json::from_str::<serde_json::Value>(&diff.to_json()).unwrap());
+    assert_eq!(value["changed"], json!([{ "key": "B", "old": "2", "new": "3" }]));
+}
@@ -0,0 +1,104 @@
+use korni::{spec, Entry, Error, KeyValuePair, ParseOptions, QuoteType};
+
+fn parse_pair(input: &str) -> Option<KeyValuePair<'_>> {
+    korni::parse(input).into_iter().find_map(|entry| match entry {
+        Entry::Pair(kv) => Some(*kv),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_escape_table_matches_parser() {
+    for &(escaped, unescaped) in spec::ESCAPES {
+        assert_eq!(spec::unescape(escaped), Some(unescaped));
+        assert_eq!(spec::escape(unescaped), Some(escaped));
+        let input = format!("KEY=\"a\\{}b\"", escaped);
+        assert_eq!(parse_pair(&input).unwrap().value, format!("a{}b", unescaped));
+    }
+
+    // Anything else keeps its backslash
+    assert_eq!(spec::unescape('x'), None);
+    assert_eq!(parse_pair("KEY=\"a\\xb\"").unwrap().value, "a\\xb");
+}
+
+#[test]
+fn test_is_valid_key_matches_parser() {
+    for key in ["KEY", "_private", "DB2", "a_b_c", "2FA", "", "KEY-NAME", "KEY.NAME", "KÉY", "ключ", "CAFE\u{301}", "\u{301}KEY", "A٣", "Ⅻ_KEY", "A\u{2028}"] {
+        let parsed = parse_pair(&format!("{}=value", key)).is_some_and(|kv| kv.key == key);
+        assert_eq!(spec::is_valid_key(key), parsed, "{:?}", key);
+
+        let options = ParseOptions { unicode_keys: true, ..ParseOptions::default() };
+        let input = format!("{}=value", key);
+        let parsed = korni::parse_with_options(&input, options).iter()
+            .any(|entry| matches!(entry, Entry::Pair(kv) if kv.key == key));
+        assert_eq!(spec::is_valid_key_with(key, true), parsed, "{:?} with unicode keys", key);
+    }
+
+    assert!(spec::is_key_char('_', false));
+    assert!(!spec::is_key_char('é', false));
+    assert!(spec::is_key_char('é', true));
+    assert!(spec::is_key_char('\u{301}', true));
+    assert!(!spec::is_key_start('\u{301}', true));
+    assert!(!spec::is_key_start('1', false));
+}
+
+#[test]
+fn test_quote_rules_round_trip() {
+    for value in ["plain", "two words", "it's", "line\nbreak", "=lead", "trail\\", "\"quoted\"", "$HOME", ""] {
+        if spec::can_be_unquoted(value) {
+            let input = format!("KEY={}\n", value);
+            let kv = parse_pair(&input).unwrap();
+            assert_eq!((kv.value.as_ref(), kv.quote), (value, QuoteType::None), "{:?}", value);
+        }
+        if spec::can_be_single_quoted(value) {
+            let input = format!("KEY='{}'\n", value);
+            let kv = parse_pair(&input).unwrap();
+            assert_eq!(kv.value, value, "{:?}", value);
+        }
+    }
+
+    assert!(!spec::can_be_unquoted("two words"));
+    assert!(!spec::can_be_unquoted("trail\\"));
+    assert!(!spec::can_be_single_quoted("it's"));
+    assert!(!spec::can_be_single_quoted("line\nbreak"));
+}
+
+#[test]
+fn test_validate_key_matches_parser_errors() {
+    assert_eq!(korni::validate_key("DB_HOST"), Ok(()));
+    for key in ["2FA", "KEY-NAME", "KEY NAME", "KEY ", "KÉY", "ключ", "", "-KEY"] {
+        let input = format!("{}=value", key);
+        let parsed = korni::parse(&input).into_iter().find_map(|entry| match entry {
+            Entry::Error(error) => Some(error),
+            _ => None,
+        });
+        assert_eq!(korni::validate_key(key).err(), parsed, "{:?}", key);
+    }
+}
+
+#[test]
+fn test_validate_value_for() {
+    assert_eq!(korni::validate_value_for(QuoteType::None, "plain"), Ok(()));
+    assert_eq!(korni::validate_value_for(QuoteType::None, "=lead"), Err(Error::DoubleEquals { offset: 0 }));
+    assert_eq!(korni::validate_value_for(QuoteType::None, "two words").unwrap_err().offset(), 3);
+    assert!(korni::validate_value_for(QuoteType::None, "trail\\").is_err());
+    assert!(korni::validate_value_for(QuoteType::None, "'quoted'").is_err());
+
+    assert_eq!(korni::validate_value_for(QuoteType::Single, "two words"), Ok(()));
+    assert_eq!(korni::validate_value_for(QuoteType::Single, "it's").unwrap_err().offset(), 2);
+    assert!(korni::validate_value_for(QuoteType::Single, "line\nbreak").is_err());
+
+    assert_eq!(korni::validate_value_for(QuoteType::Double, "it's \"all\"\n$fine\\"), Ok(()));
+
+    // Whatever passes reads back unchanged once encoded
+    for value in ["plain", "two words", "it's", "=lead", "trail\\"] {
+        for quote in [QuoteType::None, QuoteType::Single, QuoteType::Double] {
+            if korni::validate_value_for(quote, value).is_ok() {
+                let (written, encoded) = korni::encode_value(value, quote);
+                assert_eq!(written, quote, "{:?}", value);
+                let input = format!("KEY={}\n", encoded);
+                assert_eq!(parse_pair(&input).unwrap().value, value);
+            }
+        }
+    }
+}
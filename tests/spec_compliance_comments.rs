@@ -73,7 +73,7 @@ fn test_hash_escaped_double_check() { assert_pair("K=\"\\#\"", "K", "\\#"); }
 #[test]
 fn test_option_include_comments() {
     let input = "# C1\nK=v\n# C2";
-    let options = ParseOptions { include_comments: true, track_positions: false };
+    let options = ParseOptions { include_comments: true, track_positions: false, ..ParseOptions::default() };
     let entries = korni::parse_with_options(input, options);
     assert_eq!(entries.len(), 3);
     assert!(matches!(entries[0], Entry::Comment(_)));
@@ -86,7 +86,7 @@ fn test_option_include_comments() {
 #[test]
 fn test_commented_pair_detection() {
     let input = "# K=v";
-    let options = ParseOptions { include_comments: true, track_positions: false };
+    let options = ParseOptions { include_comments: true, track_positions: false, ..ParseOptions::default() };
     let entries = korni::parse_with_options(input, options);
     
     // Parser logic for commented pair:
@@ -100,7 +100,7 @@ fn test_commented_pair_detection() {
 #[test]
 fn test_commented_pair_indent() {
     let input = "  # K=v";
-    let options = ParseOptions { include_comments: true, track_positions: false };
+    let options = ParseOptions { include_comments: true, track_positions: false, ..ParseOptions::default() };
     let entries = korni::parse_with_options(input, options);
     let pair = entries[0].as_pair().expect("Should be parsed as commented pair");
     assert_eq!(pair.key, "K");
@@ -110,7 +110,7 @@ fn test_commented_pair_indent() {
 #[test]
 fn test_commented_pair_quoted() {
     let input = "# K=\"v\"";
-    let options = ParseOptions { include_comments: true, track_positions: false };
+    let options = ParseOptions { include_comments: true, track_positions: false, ..ParseOptions::default() };
     let entries = korni::parse_with_options(input, options);
     let pair = entries[0].as_pair().unwrap();
     assert_eq!(pair.value, "v");
@@ -122,7 +122,7 @@ fn test_commented_pair_invalid() {
     // "# 1K=v" -> Invalid key 1K
     // Should fallback to Entry::Comment
     let input = "# 1K=v";
-    let options = ParseOptions { include_comments: true, track_positions: false };
+    let options = ParseOptions { include_comments: true, track_positions: false, ..ParseOptions::default() };
     let entries = korni::parse_with_options(input, options);
     assert!(matches!(entries[0], Entry::Comment(_)));
 }
@@ -135,3 +135,58 @@ fn test_empty_lines_ignored() {
     let entries = parse(input);
     assert_eq!(entries.len(), 0);
 }
+
+// --- 7. Configurable Comment Characters ---
+
+#[test]
+fn test_semicolon_is_not_comment_by_default() {
+    let entries = parse("; K=v");
+    assert!(entries.iter().all(|e| e.as_pair().is_none() && !matches!(e, Entry::Comment(_))));
+}
+
+#[test]
+fn test_semicolon_comment_char() {
+    let options = ParseOptions { comment_chars: b"#;", include_comments: true, ..ParseOptions::default() };
+    let entries = korni::parse_with_options("; section\n# other\nK=v", options);
+    assert_eq!(entries.len(), 3);
+    assert!(matches!(entries[0], Entry::Comment(_)));
+    assert!(matches!(entries[1], Entry::Comment(_)));
+    assert_eq!(entries[2].as_pair().unwrap().key, "K");
+}
+
+#[test]
+fn test_semicolon_commented_pair() {
+    let options = ParseOptions { comment_chars: b";", include_comments: true, ..ParseOptions::default() };
+    let entries = korni::parse_with_options("; K=v", options);
+    let pair = entries[0].as_pair().unwrap();
+    assert!(pair.is_comment);
+    assert_eq!(pair.value, "v");
+}
+
+// --- 8. Comment Spans ---
+
+#[test]
+fn test_comment_content_span() {
+    let input = "#   hello world\r\nK=v";
+    let options = ParseOptions { include_comments: true, ..ParseOptions::default() };
+    let entries = korni::parse_with_options(input, options);
+    match &entries[0] {
+        Entry::Comment(c) => {
+            assert_eq!(c.span.range(), 0..16);
+            assert_eq!(c.content_span.range(), 4..15);
+            assert_eq!(c.content(input), "hello world");
+        }
+        _ => panic!("Should be comment"),
+    }
+}
+
+#[test]
+fn test_empty_comment_content_span() {
+    let input = "#";
+    let options = ParseOptions { include_comments: true, ..ParseOptions::default() };
+    let entries = korni::parse_with_options(input, options);
+    match &entries[0] {
+        Entry::Comment(c) => assert!(c.content_span.is_empty()),
+        _ => panic!("Should be comment"),
+    }
+}
@@ -1,6 +1,6 @@
 mod common;
 use common::{assert_pair, assert_error};
-use korni::parse;
+use korni::{parse_with_options, ParseOptions};
 
 // --- 1. Valid UTF-8 ---
 
@@ -10,14 +10,40 @@ fn test_utf8_valid_chars() { assert_pair("K=äöü", "K", "äöü"); }
 #[test]
 fn test_utf8_key_is_invalid() {
     let input = "ä=v";
-    let entries = parse(input);
-    // Should be skipped due to invalid start char (non-ascii)
-    // The bytes are C3 A4. Not ascii.
-    // So consume_key consumes 0.
-    // Then check for empty key error conditions strictly?
-    // Code says: if key_start == key_end: ...
-    // "ä" is not '=', so it falls through to recover_line.
-    assert_eq!(entries.len(), 0);
+    // Non-ASCII keys are rejected with a clear error rather than skipped
+    assert_error(input, "Non-ASCII character in key");
+}
+
+#[test]
+fn test_utf8_inside_key_is_invalid() {
+    assert_error("KEYä=v", "Non-ASCII character in key");
+}
+
+#[test]
+fn test_unicode_keys_option() {
+    let options = ParseOptions { unicode_keys: true, ..ParseOptions::default() };
+    let entries = parse_with_options("ä=v
+GRÜSSE_日本=hi
+_ключ=x", options);
+    let keys: Vec<_> = entries.iter().map(|e| e.as_pair().unwrap().key.to_string()).collect();
+    assert_eq!(keys, vec!["ä", "GRÜSSE_日本", "_ключ"]);
+}
+
+#[test]
+fn test_unicode_keys_are_xid_identifiers() {
+    let options = ParseOptions { unicode_keys: true, ..ParseOptions::default() };
+    // `é` written as `e` and a combining acute accent
+    let entries = parse_with_options("CAFE\u{301}=v", options);
+    assert_eq!(entries[0].as_pair().unwrap().key, "CAFE\u{301}");
+    let entries = parse_with_options("\u{301}KEY=v", options);
+    assert!(matches!(entries[0], korni::Entry::Error(korni::Error::InvalidKey { reason: korni::InvalidKeyReason::NotAKey, .. })));
+}
+
+#[test]
+fn test_unicode_keys_digit_start_still_invalid() {
+    let options = ParseOptions { unicode_keys: true, ..ParseOptions::default() };
+    let entries = parse_with_options("٣K=v", options);
+    assert!(matches!(entries[0], korni::Entry::Error(korni::Error::InvalidKey { .. })));
 }
 
 // --- 2. BOM Handling ---
@@ -1,6 +1,6 @@
 mod common;
 use common::{assert_exported, assert_not_exported, assert_error};
-use korni::{parse, Entry};
+use korni::{parse, parse_with_options, Entry, Error, ParseOptions};
 
 // --- 1. Basic Export ---
 
@@ -90,6 +90,25 @@ fn test_export_only_error() {
 fn test_export_space_only() {
     let input = "export ";
     let entries = parse(input);
-    assert_eq!(entries.len(), 0);
+    assert_eq!(entries.len(), 1);
+    assert!(matches!(entries[0], Entry::Error(Error::Expected { offset: 7, .. })));
+}
+
+
+// --- Forbid Export Policy ---
+
+#[test]
+fn test_forbid_export_errors() {
+    let options = ParseOptions { forbid_export: true, ..ParseOptions::default() };
+    let entries = parse_with_options("export K=v\nOTHER=ok", options);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0], Entry::Error(Error::ExportForbidden { offset: 0 }));
+    assert_eq!(entries[1].as_pair().unwrap().key, "OTHER");
 }
 
+#[test]
+fn test_forbid_export_allows_export_like_keys() {
+    let options = ParseOptions { forbid_export: true, ..ParseOptions::default() };
+    let entries = parse_with_options("exportK=v", options);
+    assert_eq!(entries[0].as_pair().unwrap().key, "exportK");
+}
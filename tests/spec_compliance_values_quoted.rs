@@ -182,3 +182,57 @@ fn test_consecutive_backslashes() {
     assert_pair("K=\"a\\\\\\\\b\"", "K", "a\\\\b"); // 4 -> 2
 }
 
+
+// --- 14. Non-ASCII Around Escapes ---
+
+#[test]
+fn test_double_unicode_with_escapes() {
+    assert_pair("K=\"ü\\nö\\\"多\"", "K", "ü\nö\"多");
+    assert_pair("K=\"日本\\t語\"", "K", "日本\t語");
+}
+
+#[test]
+fn test_double_unknown_escape_before_unicode() {
+    assert_pair("K=\"a\\éb\"", "K", "a\\éb");
+    assert_pair("K=\"\\🦀\"", "K", "\\🦀");
+}
+
+#[test]
+fn test_double_trailing_backslash_unclosed() { assert_error("K=\"ü\\", "Unclosed"); }
+
+// --- 15. Normalization Options ---
+
+fn normalized(input: &str, options: korni::ParseOptions) -> std::borrow::Cow<'_, str> {
+    korni::parse_with_options(input, options).remove(0).into_pair().unwrap().value
+}
+
+#[test]
+fn test_trim_quoted_values() {
+    let options = korni::ParseOptions { trim_quoted_values: true, ..korni::ParseOptions::default() };
+    assert_eq!(normalized("K=\"  a b \t\"", options), "a b");
+    assert_eq!(normalized("K=' x '", options), "x");
+    assert_eq!(normalized("K=\" \\tx\\n \"", options), "x");
+    assert!(matches!(normalized("K=\" x \"", options), std::borrow::Cow::Borrowed("x")));
+}
+
+#[test]
+fn test_normalize_newlines() {
+    let options = korni::ParseOptions { normalize_newlines: true, ..korni::ParseOptions::default() };
+    assert_eq!(normalized("K=\"a\r\nb\r\nc\"", options), "a\nb\nc");
+    assert_eq!(normalized("K='a\r\nb'", options), "a\nb");
+    assert_eq!(normalized("K=\"a\\r\\nb\"", options), "a\nb");
+}
+
+#[test]
+fn test_strip_trailing_newline() {
+    let options = korni::ParseOptions { strip_trailing_newline: true, ..korni::ParseOptions::default() };
+    let pem = "K=\"-----BEGIN KEY-----\nabc\n-----END KEY-----\n\"";
+    assert_eq!(normalized(pem, options), "-----BEGIN KEY-----\nabc\n-----END KEY-----");
+    assert_eq!(normalized("K='a\r\n'", options), "a");
+    assert_eq!(normalized("K=\"a\n\n\"", options), "a\n");
+}
+
+#[test]
+fn test_normalization_off_by_default() {
+    assert_pair("K=\" a\r\n\"", "K", " a\r\n");
+}
@@ -163,3 +163,38 @@ fn test_complex_url() { assert_pair("URL=https://u:p@h:80/p?q=v&k=v#f", "URL", "
 fn test_complex_path_windows() { assert_pair("PATH=C:\\User\\Name\\Docs", "PATH", "C:\\User\\Name\\Docs"); }
 #[test]
 fn test_complex_path_unix() { assert_pair("PATH=/usr/local/bin:/usr/bin", "PATH", "/usr/local/bin:/usr/bin"); }
+
+// --- Lenient Mode: unquoted_to_eol ---
+
+fn eol_values(input: &str) -> Vec<String> {
+    let options = korni::ParseOptions { unquoted_to_eol: true, ..korni::ParseOptions::default() };
+    korni::parse_with_options(input, options).iter()
+        .map(|e| e.as_pair().unwrap_or_else(|| panic!("{:?} gave {:?}", input, e)).value.to_string())
+        .collect()
+}
+
+#[test]
+fn test_eol_keeps_internal_whitespace() {
+    assert_eq!(eol_values("K=hello world"), ["hello world"]);
+    assert_eq!(eol_values("K=a \t b\r\nN=c"), ["a \t b", "c"]);
+}
+
+#[test]
+fn test_eol_strips_trailing_whitespace_and_comment() {
+    assert_eq!(eol_values("K=hello world   \nN=v # note\nM=x\t#y"), ["hello world", "v", "x"]);
+}
+
+#[test]
+fn test_eol_hash_without_space_is_value() {
+    assert_eq!(eol_values("K=a#b c"), ["a#b c"]);
+}
+
+#[test]
+fn test_eol_continuation() {
+    assert_eq!(eol_values("K=one two\\\nthree four # c"), ["one twothree four"]);
+}
+
+#[test]
+fn test_eol_strict_default_unchanged() {
+    assert_pair("K=hello world", "K", "hello");
+}
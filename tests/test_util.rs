@@ -0,0 +1,92 @@
+#![cfg(feature = "test-util")]
+
+use korni::test::{dialect_roundtrip_check, with_env, Dialect};
+use korni::{CommentPolicy, ExportPolicy, Fold, Korni, ParseOptions, WriteOptions};
+
+#[test]
+fn test_with_env_applies_and_restores() {
+    std::env::set_var("KORNI_TU_EXISTING", "before");
+    std::env::remove_var("KORNI_TU_NEW");
+
+    let result = with_env("KORNI_TU_EXISTING=during\nKORNI_TU_NEW=\"a b\"\n# KORNI_TU_OFF=1", || {
+        assert_eq!(std::env::var("KORNI_TU_EXISTING").unwrap(), "during");
+        assert_eq!(std::env::var("KORNI_TU_NEW").unwrap(), "a b");
+        assert!(std::env::var("KORNI_TU_OFF").is_err());
+        42
+    });
+
+    assert_eq!(result, 42);
+    assert_eq!(std::env::var("KORNI_TU_EXISTING").unwrap(), "before");
+    assert!(std::env::var("KORNI_TU_NEW").is_err());
+}
+
+#[test]
+fn test_with_env_environment_and_nesting() {
+    let env = Korni::from_str("KORNI_TU_OUTER=1\nKORNI_TU_SHARED=outer").parse().unwrap();
+    with_env(&env, || {
+        with_env("KORNI_TU_SHARED=inner", || {
+            assert_eq!(std::env::var("KORNI_TU_SHARED").unwrap(), "inner");
+            assert_eq!(std::env::var("KORNI_TU_OUTER").unwrap(), "1");
+        });
+        assert_eq!(std::env::var("KORNI_TU_SHARED").unwrap(), "outer");
+    });
+    assert!(std::env::var("KORNI_TU_SHARED").is_err());
+}
+
+#[test]
+fn test_with_env_restores_after_panic() {
+    let result = std::panic::catch_unwind(|| {
+        with_env("KORNI_TU_PANIC=1", || panic!("boom"));
+    });
+    assert!(result.is_err());
+    assert!(std::env::var("KORNI_TU_PANIC").is_err());
+    // The lock is usable again
+    with_env("KORNI_TU_PANIC=2", || assert_eq!(std::env::var("KORNI_TU_PANIC").unwrap(), "2"));
+}
+
+#[test]
+fn test_env_file_fixture() {
+    use korni::test::EnvFile;
+
+    let file = EnvFile::builder()
+        .comment("service")
+        .key("A", "1")
+        .key("B", "two words")
+        .line("BROKEN LINE")
+        .create();
+    assert_eq!(file.path().file_name().unwrap(), ".env");
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), "# service\nA=1\nB='two words'\nBROKEN LINE\n");
+
+    let env = Korni::from_file(file.path()).parse().unwrap();
+    assert_eq!(env.get("B"), Some("two words"));
+    assert_eq!(env.errors().len(), 1);
+
+    let other = EnvFile::builder().name(".env.local").key("A", "2").create();
+    assert_ne!(other.dir(), file.dir());
+    let dir = file.dir().to_path_buf();
+    drop(file);
+    assert!(!dir.exists());
+}
+
+#[test]
+fn test_dialect_roundtrip_check() {
+    let input = "\
+# header
+export HOST=localhost
+MOTD='hello world'
+MULTI=\"line one\\nline two \\\"quoted\\\" $HOME\"
+LONG=aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa
+# OLD=1
+EMPTY=
+";
+    for parse in [ParseOptions::default(), ParseOptions::full()] {
+        for export in [ExportPolicy::Preserve, ExportPolicy::Always, ExportPolicy::Never] {
+            for fold in [Fold::SingleLine, Fold::Multiline, Fold::Continuation(20)] {
+                for comments in [CommentPolicy::Keep, CommentPolicy::HeaderOnly, CommentPolicy::Strip] {
+                    let write = WriteOptions { export, sort_keys: comments == CommentPolicy::Strip, fold, comments };
+                    dialect_roundtrip_check(Dialect { parse, write }, input);
+                }
+            }
+        }
+    }
+}
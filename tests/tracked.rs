@@ -0,0 +1,52 @@
+use std::time::SystemTime;
+use korni::{Korni, TrackedEnvironment};
+
+fn tracked(input: &str) -> TrackedEnvironment<'_> {
+    TrackedEnvironment::new(Korni::from_str(input).preserve_comments().parse().unwrap())
+}
+
+#[test]
+fn test_records_hits_and_misses_in_order() {
+    let env = tracked("A=1\nB=2\n");
+    let before = SystemTime::now();
+    assert_eq!(env.get("A"), Some("1"));
+    assert_eq!(env.get("NOPE"), None);
+    assert_eq!(env.get_or("B", "x"), "2");
+
+    let accesses = env.accesses();
+    let keys: Vec<_> = accesses.iter().map(|a| (a.key.as_str(), a.hit)).collect();
+    assert_eq!(keys, [("A", true), ("NOPE", false), ("B", true)]);
+    assert!(accesses.iter().all(|a| a.at >= before));
+    assert!(accesses.windows(2).all(|w| w[0].at <= w[1].at));
+}
+
+#[test]
+fn test_report_used_missing_unused() {
+    let env = tracked("DB_URL=x\nPORT=80\nLEGACY=1\n# DISABLED=1\n");
+    env.get("PORT");
+    env.get("DB_URL");
+    env.get("PORT");
+    env.get("TIMEOUT");
+    env.inner().get("LEGACY");
+
+    let report = env.report();
+    assert_eq!(report.used, ["DB_URL", "PORT"]);
+    assert_eq!(report.missing, ["TIMEOUT"]);
+    assert_eq!(report.unused, ["LEGACY"]);
+    assert_eq!(report.accesses.len(), 4);
+
+    env.clear();
+    assert!(env.accesses().is_empty());
+    assert_eq!(env.into_inner().get("LEGACY"), Some("1"));
+}
+
+#[test]
+fn test_shared_between_threads() {
+    let env = tracked("A=1\n");
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| assert_eq!(env.get("A"), Some("1")));
+        }
+    });
+    assert_eq!(env.accesses().len(), 4);
+}
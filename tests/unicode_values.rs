@@ -0,0 +1,116 @@
+use std::borrow::Cow;
+use korni::{encode_value, Entry, EnvIterator, Parser, QuoteType};
+
+/// Small deterministic xorshift generator, so failures are reproducible.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[(self.next() % items.len() as u64) as usize]
+    }
+}
+
+/// ASCII, 2-, 3- and 4-byte characters and a combining mark.
+const PLAIN: &[char] = &['a', 'Z', '0', ' ', '=', '#', '\'', 'é', 'ß', 'Ж', '多', '語', '€', '🦀', '😀', '\u{301}'];
+const SPECIAL: &[char] = &['"', '\\', '$', '\n', '\r', '\t'];
+
+fn random_string(rng: &mut Rng, plain_only: bool) -> String {
+    let len = rng.next() % 24;
+    (0..len)
+        .map(|_| if !plain_only && rng.next().is_multiple_of(4) { rng.pick(SPECIAL) } else { rng.pick(PLAIN) })
+        .collect()
+}
+
+fn single_value(input: &str) -> Cow<'_, str> {
+    let mut entries = Parser::new(input).parse();
+    assert_eq!(entries.len(), 1, "input {:?} gave {:?}", input, entries);
+    match entries.remove(0) {
+        Entry::Pair(kv) => kv.value,
+        other => panic!("input {:?} gave {:?}", input, other),
+    }
+}
+
+#[test]
+fn test_double_quoted_roundtrip_random_unicode() {
+    let mut rng = Rng(0x9E37_79B9_7F4A_7C15);
+    for _ in 0..2000 {
+        let value = random_string(&mut rng, false);
+        let (quote, encoded) = encode_value(&value, QuoteType::Double);
+        assert_eq!(quote, QuoteType::Double);
+        let input = format!("K={}", encoded);
+        assert_eq!(single_value(&input), value, "input {:?}", input);
+    }
+}
+
+#[test]
+fn test_borrowed_and_owned_decoding_agree() {
+    let mut rng = Rng(0xD1B5_4A32_D192_ED03);
+    for _ in 0..2000 {
+        let head = random_string(&mut rng, true);
+        let tail = random_string(&mut rng, true);
+        let escape = rng.pick(&[("\\n", "\n"), ("\\\"", "\""), ("\\$", "$"), ("\\\\", "\\"), ("\\é", "\\é"), ("\\🦀", "\\🦀")]);
+
+        let borrowed_input = format!("K=\"{}{}\"", head, tail);
+        let borrowed = single_value(&borrowed_input);
+        assert!(matches!(borrowed, Cow::Borrowed(_)), "input {:?}", borrowed_input);
+        assert_eq!(borrowed, format!("{}{}", head, tail));
+
+        let owned_input = format!("K=\"{}{}{}\"", head, escape.0, tail);
+        let owned = single_value(&owned_input);
+        assert!(matches!(owned, Cow::Owned(_)), "input {:?}", owned_input);
+        assert_eq!(owned, format!("{}{}{}", head, escape.1, tail), "input {:?}", owned_input);
+    }
+}
+
+#[test]
+fn test_parse_borrowed_matches_parse_on_random_unicode() {
+    let mut rng = Rng(0x2545_F491_4F6C_DD1D);
+    for _ in 0..500 {
+        let value = random_string(&mut rng, true);
+        let input = format!("K=\"{}\"\n", value);
+        let pairs: Vec<_> = Parser::new(&input).parse_borrowed().collect::<Result<_, _>>().unwrap();
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].value, single_value(&input));
+    }
+}
+
+#[test]
+fn test_bufread_matches_parse_on_random_unicode() {
+    let mut rng = Rng(0xA076_1D64_78BD_642F);
+    let mut input = String::new();
+    for i in 0..300 {
+        let (_, encoded) = encode_value(&random_string(&mut rng, false), QuoteType::Double);
+        input.push_str(&format!("K{}={}\n", i, encoded));
+    }
+    let streamed: Vec<Entry<'static>> = EnvIterator::from_bufread(input.as_bytes()).collect();
+    let parsed: Vec<Entry<'_>> = Parser::new(&input).parse();
+    assert_eq!(streamed.len(), parsed.len());
+    for (s, p) in streamed.iter().zip(&parsed) {
+        match (s, p) {
+            (Entry::Pair(s), Entry::Pair(p)) => assert_eq!(s.value, p.value, "key {}", p.key),
+            other => panic!("unexpected entries {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn test_unquoted_continuation_keeps_unicode() {
+    let mut rng = Rng(0x94D0_49BB_1331_11EB);
+    let plain: Vec<char> = PLAIN.iter().copied().filter(|c| !matches!(c, ' ' | '\'' | '#')).collect();
+    for _ in 0..500 {
+        let len = 1 + rng.next() % 12;
+        let head: String = (0..len).map(|_| rng.pick(&plain)).collect();
+        let tail: String = (0..len).map(|_| rng.pick(&plain)).collect();
+        let input = format!("K=x{}\\\n{}", head, tail);
+        let value = single_value(&input);
+        assert!(matches!(value, Cow::Owned(_)));
+        assert_eq!(value, format!("x{}{}", head, tail), "input {:?}", input);
+    }
+}
@@ -0,0 +1,77 @@
+use korni::{parse_with_options, KeyValuePair, ParseOptions};
+
+fn parse_pair(input: &str) -> KeyValuePair<'_> {
+    let entries = parse_with_options(input, ParseOptions::full());
+    entries.into_iter().next().unwrap().as_pair().unwrap().clone()
+}
+
+#[test]
+fn test_raw_and_content_spans_double_quoted() {
+    let input = r#"KEY="a\nb""#;
+    let kv = parse_pair(input);
+    assert_eq!(kv.raw_span.unwrap().range(), 4..10);
+    assert_eq!(kv.content_span.unwrap().range(), 5..9);
+    assert_eq!(&input[kv.content_span.unwrap().range()], r"a\nb");
+    assert_eq!(kv.decoded_len, 3);
+}
+
+#[test]
+fn test_raw_and_content_spans_unquoted() {
+    let kv = parse_pair("KEY=value");
+    assert_eq!(kv.raw_span, kv.content_span);
+    assert_eq!(kv.content_span.unwrap().range(), 4..9);
+}
+
+#[test]
+fn test_spans_absent_without_positions() {
+    let entries = korni::parse("KEY='v'");
+    let kv = entries[0].as_pair().unwrap();
+    assert_eq!(kv.raw_span, None);
+    assert_eq!(kv.content_span, None);
+    assert_eq!(kv.decoded_len, 1);
+}
+
+#[test]
+fn test_source_offset_across_escapes() {
+    let input = r#"KEY="a\tb\qc""#;
+    let kv = parse_pair(input);
+    assert_eq!(kv.value, "a\tb\\qc");
+    assert_eq!(kv.source_offset(input, 0), Some(5)); // a
+    assert_eq!(kv.source_offset(input, 1), Some(6)); // \t escape
+    assert_eq!(kv.source_offset(input, 2), Some(8)); // b
+    assert_eq!(kv.source_offset(input, 3), Some(9)); // \ of unknown escape
+    assert_eq!(kv.source_offset(input, 4), Some(10)); // q
+    assert_eq!(kv.source_offset(input, 5), Some(11)); // c
+    assert_eq!(kv.source_offset(input, 6), Some(12)); // end of content
+    assert_eq!(kv.source_offset(input, 7), None);
+}
+
+#[test]
+fn test_source_offset_across_continuation() {
+    let input = "KEY=ab\\\r\ncd";
+    let kv = parse_pair(input);
+    assert_eq!(kv.value, "abcd");
+    assert_eq!(kv.source_offset(input, 1), Some(5));
+    assert_eq!(kv.source_offset(input, 2), Some(9));
+}
+
+#[test]
+fn test_source_offset_multibyte() {
+    let input = "KEY='äb'";
+    let kv = parse_pair(input);
+    assert_eq!(kv.source_offset(input, 2), Some(7));
+}
+
+#[test]
+fn test_source_offset_with_value_options() {
+    let options = ParseOptions { trim_quoted_values: true, normalize_newlines: true, strip_trailing_newline: true, ..ParseOptions::full() };
+    let input = "KEY=\"  a\r\nb\n\"";
+    let entries = parse_with_options(input, options);
+    let kv = entries[0].as_pair().unwrap();
+    assert_eq!(kv.value, "a\nb");
+    assert_eq!(kv.source_offset(input, 0), Some(7)); // a
+    assert_eq!(kv.source_offset(input, 1), Some(8)); // the \r\n it was normalized from
+    assert_eq!(kv.source_offset(input, 2), Some(10)); // b
+    assert_eq!(kv.source_offset(input, 3), Some(11)); // the dropped \n
+    assert_eq!(kv.source_offset(input, 4), None);
+}
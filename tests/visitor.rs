@@ -0,0 +1,58 @@
+use korni::{Comment, Error, KeyValuePair, ParseOptions, Parser, Visitor};
+use std::ops::ControlFlow;
+
+#[derive(Default)]
+struct Recorder {
+    keys: Vec<String>,
+    comments: usize,
+    errors: usize,
+    stop_at: Option<&'static str>,
+}
+
+impl<'a> Visitor<'a> for Recorder {
+    fn on_pair(&mut self, pair: KeyValuePair<'a>) -> ControlFlow<()> {
+        self.keys.push(pair.key.to_string());
+        if self.stop_at == Some(pair.key.as_ref()) {
+            return ControlFlow::Break(());
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn on_comment(&mut self, _comment: Comment) -> ControlFlow<()> {
+        self.comments += 1;
+        ControlFlow::Continue(())
+    }
+
+    fn on_error(&mut self, _error: Error) -> ControlFlow<()> {
+        self.errors += 1;
+        ControlFlow::Continue(())
+    }
+}
+
+#[test]
+fn test_parse_with_visits_all_entries() {
+    let mut recorder = Recorder::default();
+    let mut parser = Parser::with_options("# c\nA=1\nB = 2\nC=3", ParseOptions::full());
+    assert_eq!(parser.parse_with(&mut recorder), ControlFlow::Continue(()));
+    assert_eq!(recorder.keys, vec!["A", "C"]);
+    assert_eq!(recorder.comments, 1);
+    assert_eq!(recorder.errors, 1);
+}
+
+#[test]
+fn test_parse_with_early_termination() {
+    let mut recorder = Recorder { stop_at: Some("B"), ..Recorder::default() };
+    let mut parser = Parser::new("A=1\nB=2\nC=3");
+    assert_eq!(parser.parse_with(&mut recorder), ControlFlow::Break(()));
+    assert_eq!(recorder.keys, vec!["A", "B"]);
+    // Parsing can continue where the visitor stopped
+    assert_eq!(parser.next_entry().unwrap().as_pair().unwrap().key, "C");
+}
+
+#[test]
+fn test_default_visitor_methods_continue() {
+    struct Nothing;
+    impl Visitor<'_> for Nothing {}
+    let mut parser = Parser::new("A=1\n1B=2");
+    assert_eq!(parser.parse_with(&mut Nothing), ControlFlow::Continue(()));
+}
@@ -0,0 +1,71 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use korni::{BorrowedPair, Error, Parser, QuoteType};
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    f();
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_parse_borrowed_does_not_allocate() {
+    let input = "# Database\nDB_HOST=localhost\nexport DB_PORT=5432\r\n\nNAME='single quoted'\nGREETING=\"hello world\" # inline\n# @owner: ops\nEMPTY=\n";
+    let mut pairs = [BorrowedPair { key: "", value: "", quote: QuoteType::None, is_exported: false }; 8];
+    let mut count = 0;
+
+    let allocations = allocations_during(|| {
+        for pair in Parser::new(input).parse_borrowed() {
+            pairs[count] = pair.unwrap();
+            count += 1;
+        }
+    });
+    assert_eq!(allocations, 0);
+
+    assert_eq!(count, 5);
+    assert_eq!(pairs[0], BorrowedPair { key: "DB_HOST", value: "localhost", quote: QuoteType::None, is_exported: false });
+    assert_eq!(pairs[1].key, "DB_PORT");
+    assert!(pairs[1].is_exported);
+    assert_eq!(pairs[2].value, "single quoted");
+    assert_eq!(pairs[3].value, "hello world");
+    assert_eq!(pairs[3].quote, QuoteType::Double);
+    assert_eq!(pairs[4].value, "");
+}
+
+#[test]
+fn test_parse_borrowed_rejects_values_needing_allocation() {
+    let input = "A=\"line\\nbreak\"\nB=ok\nC=con\\\ntinued\nbad line\n";
+    let results: Vec<_> = Parser::new(input).parse_borrowed().collect();
+
+    assert!(matches!(&results[0], Err(Error::Generic { offset: 0, message }) if message.contains("cannot be borrowed")));
+    assert_eq!(results[1].as_ref().unwrap().value, "ok");
+    assert!(matches!(&results[2], Err(Error::Generic { offset: 21, .. })));
+    assert!(matches!(&results[3], Err(Error::InvalidKey { .. }) | Err(Error::Expected { .. })));
+    assert_eq!(results.len(), 4);
+}
+
+#[test]
+fn test_parse_borrowed_reports_misplaced_bom() {
+    let results: Vec<_> = Parser::new("# Database\n\u{FEFF}A=1\n").parse_borrowed().collect();
+    assert!(matches!(results[0], Err(Error::InvalidBom { .. })));
+}